@@ -0,0 +1,17 @@
+-- Spot launch options for nodes declared with allocation_mode 'spot'. Until now the
+-- mode was only used for pricing: run_instances was never given market options, so a
+-- "spot" node was launched and billed as on-demand while create priced it at the spot
+-- rate and spawn set up an interruption queue nothing would ever post to.
+--
+-- spot_max_price is the per-hour ceiling handed to AWS (NULL = the on-demand price,
+-- which is AWS's own default). spot_request_type is 'one-time' or 'persistent', and
+-- spot_interruption_behavior is 'terminate', 'stop' or 'hibernate'. All three are NULL
+-- for on-demand nodes.
+--
+-- market_type records what the instance was actually launched as ('spot' or
+-- 'on-demand'), read back from the instance's lifecycle. It is NULL while no instance
+-- exists, and is cleared again when the cluster is terminated.
+ALTER TABLE nodes ADD COLUMN spot_max_price REAL NULL;
+ALTER TABLE nodes ADD COLUMN spot_request_type TEXT NULL;
+ALTER TABLE nodes ADD COLUMN spot_interruption_behavior TEXT NULL;
+ALTER TABLE nodes ADD COLUMN market_type TEXT NULL;
@@ -0,0 +1,7 @@
+-- Result of the post-spawn EFA check (`fi_info -p efa` run on the node over SSH).
+-- Clusters with use_elastic_fabric_adapters used to report EFA while the instances
+-- actually came up on plain ENA, and MPI benchmarks silently ran over TCP.
+--
+-- NULL means the check has not run (EFA disabled, or no live instance), 1 means the
+-- efa provider was found, 0 means it was not. Cleared again on termination.
+ALTER TABLE nodes ADD COLUMN efa_available BOOLEAN NULL;
@@ -0,0 +1,21 @@
+-- Typed timeline of what happened to a cluster: state transitions, node launches and
+-- failures, spot notices, restores, capacity fallbacks and task runs. The log files
+-- under LOGS_DIRECTORY say the same in prose, one file per invocation, which made
+-- rebuilding the timeline of a recovery experiment a matter of grepping several of
+-- them and lining up their timestamps by hand.
+--
+-- node_id is set for events about a single node. details is a JSON object with the
+-- event's structured fields (e.g. '{"from":"running","to":"restoring"}'), NULL when
+-- the message says it all.
+CREATE TABLE cluster_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    cluster_id VARCHAR(32) NOT NULL,
+    event_type TEXT NOT NULL,
+    node_id VARCHAR(32) NULL,
+    message TEXT NOT NULL,
+    details TEXT NULL,
+    created_at DATETIME NOT NULL,
+    FOREIGN KEY (cluster_id) REFERENCES clusters(id)
+);
+
+CREATE INDEX idx_cluster_events_cluster_id_created_at ON cluster_events (cluster_id, created_at);
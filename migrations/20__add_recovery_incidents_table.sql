@@ -0,0 +1,34 @@
+-- One row per recovery performed by `cluster watch`, with the time each phase was
+-- reached. The experiments this tool supports measure how long recovery takes, and
+-- until now that meant reading it off log timestamps.
+--
+-- failure_detected_at is when the incident was opened; every other phase is NULL until
+-- reached, and stays NULL when it does not apply (no notice for an on-demand node, no
+-- EFS mount on a cluster without EFS, no task relaunch without --tasks-yaml).
+-- notice_received_at is the spot interruption notice for one of the failed nodes, and
+-- so precedes the detection. instances_requested_at is when the provider accepted the
+-- replacement launch requests.
+--
+-- mode is 'replace' or 'scale_down' (--no-replace). failed_ips is a JSON array of the
+-- private IPs that failed. outcome is 'in_progress' until finished_at is set, then
+-- 'recovered', 'failed' (see error), or 'abandoned' when the monitor stopped mid-way
+-- and a later incident superseded it.
+CREATE TABLE recovery_incidents (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    cluster_id VARCHAR(32) NOT NULL,
+    mode TEXT NOT NULL,
+    failed_ips TEXT NOT NULL,
+    outcome TEXT NOT NULL DEFAULT 'in_progress',
+    error TEXT NULL,
+    notice_received_at DATETIME NULL,
+    failure_detected_at DATETIME NOT NULL,
+    enis_released_at DATETIME NULL,
+    instances_requested_at DATETIME NULL,
+    instances_running_at DATETIME NULL,
+    ssh_ready_at DATETIME NULL,
+    efs_mounted_at DATETIME NULL,
+    init_done_at DATETIME NULL,
+    task_relaunched_at DATETIME NULL,
+    finished_at DATETIME NULL,
+    FOREIGN KEY (cluster_id) REFERENCES clusters(id)
+);
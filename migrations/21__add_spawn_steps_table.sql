@@ -0,0 +1,18 @@
+-- Steps of the spawn cycle already completed for a cluster, so `cluster spawn --resume`
+-- can continue an interrupted or failed spawn instead of starting over. Cluster-wide
+-- steps (VPC, subnet, internet gateway, route table, security group, EFS device and
+-- mount target, SSH key, placement group) have node_id ''; per-node steps (ENI, EIP,
+-- instance, SSH, EFS mount, init) carry the node's id.
+--
+-- resource_id is the provider's identifier for what the step created, when there is
+-- one (e.g. 'vpc-0abc...'); security groups store a JSON array. A plain `cluster
+-- spawn` clears the cluster's rows, and a restore clears those of the failed nodes.
+CREATE TABLE spawn_steps (
+    cluster_id VARCHAR(32) NOT NULL,
+    node_id VARCHAR(32) NOT NULL DEFAULT '',
+    step TEXT NOT NULL,
+    resource_id TEXT NULL,
+    completed_at DATETIME NOT NULL,
+    PRIMARY KEY (cluster_id, node_id, step),
+    FOREIGN KEY (cluster_id) REFERENCES clusters(id)
+);
@@ -0,0 +1,8 @@
+-- SHA-256 of the tasks YAML a run belongs to, so `cluster tasks --resume` (and the
+-- relaunch after a `watch` restore) can tell the runs of this very pipeline from those
+-- of an earlier or edited file, and skip only the ones that already succeeded.
+--
+-- NULL for runs recorded before the column existed; those are never resumed.
+ALTER TABLE task_runs ADD COLUMN tasks_file_hash TEXT NULL;
+
+CREATE INDEX idx_task_runs_cluster_hash ON task_runs (cluster_id, tasks_file_hash);
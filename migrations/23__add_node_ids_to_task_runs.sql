@@ -0,0 +1,6 @@
+-- Nodes a task run used, as a JSON array of node ids. Tasks may now run on a subset of
+-- the cluster, side by side with others, so "the cluster" no longer says where a run
+-- happened.
+--
+-- NULL for runs recorded before the column existed, which used every node.
+ALTER TABLE task_runs ADD COLUMN node_ids TEXT NULL;
@@ -0,0 +1,7 @@
+-- Matrix parameters of a task run, as a JSON object of name -> value (e.g.
+-- {"k":"4","storage":"memory"}). A task with a `matrix:` runs once per combination of
+-- its values, and results can be grouped by these instead of by parsing the directory
+-- names the scripts chose.
+--
+-- NULL for tasks without a matrix.
+ALTER TABLE task_runs ADD COLUMN params TEXT NULL;
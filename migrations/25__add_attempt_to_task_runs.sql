@@ -0,0 +1,6 @@
+-- Attempt number of a task run. A task with `retries: N` runs a failed or timed-out run
+-- again up to N times, and every attempt gets its own row, numbered from 1, so flaky
+-- runs stay visible instead of being overwritten by the attempt that succeeded.
+--
+-- status gains 'timeout' for attempts killed after the task's `timeout:`.
+ALTER TABLE task_runs ADD COLUMN attempt INTEGER NOT NULL DEFAULT 1;
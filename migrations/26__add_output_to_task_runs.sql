@@ -0,0 +1,10 @@
+-- What a finished task run left behind. The log on the head node is removed by the
+-- cleanup before the task's next run, so its last lines are kept here and the whole
+-- log is downloaded to log_path, under the tasks file's results_local_dir.
+--
+-- exit_code is NULL when the script's exit code could not be read (SSH error,
+-- timeout). wall_secs is the time from started_at to finished_at.
+ALTER TABLE task_runs ADD COLUMN exit_code INTEGER NULL;
+ALTER TABLE task_runs ADD COLUMN wall_secs REAL NULL;
+ALTER TABLE task_runs ADD COLUMN log_tail TEXT NULL;
+ALTER TABLE task_runs ADD COLUMN log_path TEXT NULL;
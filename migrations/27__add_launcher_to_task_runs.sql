@@ -0,0 +1,4 @@
+-- Launcher the task run's job was started with ('openmpi', 'mpich', 'intel-mpi', 'srun'
+-- or 'none'), so `cluster watch` knows how to get a checkpoint signal to the ranks of
+-- the runs in progress. NULL for runs recorded before it was kept, which used Open MPI.
+ALTER TABLE task_runs ADD COLUMN launcher TEXT NULL;
@@ -0,0 +1,10 @@
+-- How `cluster watch` asks an application to checkpoint on a spot interruption notice:
+-- a JSON object with the process name pattern to signal, the signal, an optional
+-- command to run instead, and an optional file the application creates once the
+-- checkpoint is flushed, waited for up to a timeout. NULL falls back to signalling
+-- the job through its launcher.
+--
+-- It comes from the cluster YAML, and from the tasks YAML for the runs it starts,
+-- which take precedence.
+ALTER TABLE clusters ADD COLUMN checkpoint_signal TEXT NULL;
+ALTER TABLE task_runs ADD COLUMN checkpoint_signal TEXT NULL;
@@ -0,0 +1,3 @@
+-- Workload manager set up on the cluster during spawn: 'slurm', or NULL for none.
+-- With Slurm, node 0 runs slurmctld and every node runs slurmd.
+ALTER TABLE clusters ADD COLUMN scheduler TEXT NULL;
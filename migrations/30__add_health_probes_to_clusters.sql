@@ -0,0 +1,5 @@
+-- Application-level checks `cluster watch` runs on every node besides looking at
+-- instance state: a JSON array of probes (SSH reachability, a mount point, free
+-- disk, a custom command), each with the number of consecutive failures after
+-- which the node is restored. NULL runs none.
+ALTER TABLE clusters ADD COLUMN health_probes TEXT NULL;
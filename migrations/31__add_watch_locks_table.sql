@@ -0,0 +1,15 @@
+-- The `cluster watch` process monitoring a cluster, at most one per cluster. A
+-- second watcher would race the first through every restore, so a watcher inserts
+-- its row before its first health check and deletes it on exit. A row whose pid
+-- is no longer running belongs to a watcher that was killed, and is taken over.
+--
+-- socket_path is the Unix socket `cluster watch-status` and `cluster watch-stop`
+-- reach the watcher through; log_path is where a detached watcher writes.
+CREATE TABLE watch_locks (
+    cluster_id VARCHAR(32) PRIMARY KEY NOT NULL,
+    pid INTEGER NOT NULL,
+    socket_path TEXT NULL,
+    log_path TEXT NULL,
+    started_at DATETIME NOT NULL,
+    FOREIGN KEY (cluster_id) REFERENCES clusters(id)
+);
@@ -0,0 +1,8 @@
+-- Zones `cluster watch` may move a node to when none of its recovery slot's
+-- preferred instance types has capacity in the cluster's availability_zone: a
+-- JSON array, tried in order. NULL keeps every node in availability_zone.
+ALTER TABLE clusters ADD COLUMN fallback_availability_zones TEXT NULL;
+
+-- The zone a node was moved to. NULL means the cluster's availability_zone.
+-- Cleared again on termination, so a respawn starts back in the cluster's zone.
+ALTER TABLE nodes ADD COLUMN availability_zone TEXT NULL;
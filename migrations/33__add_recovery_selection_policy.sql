@@ -0,0 +1,10 @@
+-- How `cluster watch` picks among a recovery slot's preferred_instance_types:
+-- 'ordered', 'cheapest' or 'cheapest_per_vcpu'. NULL means 'ordered'.
+ALTER TABLE clusters ADD COLUMN recovery_selection_policy TEXT NULL;
+
+-- Highest hourly price a replacement may launch at. NULL means no ceiling.
+ALTER TABLE clusters ADD COLUMN recovery_max_price_per_hour REAL NULL;
+
+-- The slot's preferred_instance_types as ranked and filtered by the selection
+-- policy at the latest restore: a JSON array. NULL means the declared order.
+ALTER TABLE recovery_nodes ADD COLUMN selected_instance_types TEXT NULL;
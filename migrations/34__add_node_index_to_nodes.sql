@@ -0,0 +1,11 @@
+-- The position a node's cloud resources are named and addressed by: its network
+-- interface, Elastic IP, instance name and private IP all derive from it. It was
+-- the node's position in rowid order, which shifts when a node is removed
+-- (scale-down, retired spot node) and so pointed the survivors at each other's
+-- resources. Assigned once at insert, so removing a node leaves the others alone.
+ALTER TABLE nodes ADD COLUMN node_index INTEGER NOT NULL DEFAULT 0;
+
+UPDATE nodes SET node_index = (
+    SELECT COUNT(*) FROM nodes AS earlier
+    WHERE earlier.cluster_id = nodes.cluster_id AND earlier.rowid < nodes.rowid
+);
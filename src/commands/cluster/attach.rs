@@ -0,0 +1,85 @@
+use crate::commands::cluster::tasks::{follow_log, is_run_session};
+use crate::database::models::{Cluster, ClusterState, slot_order};
+use crate::utils::{self, ssh::SshSession};
+
+use anyhow::Result;
+use sqlx::sqlite::SqlitePool;
+use tokio::time::Duration;
+
+/// Streams the log of the task run in progress on a cluster, from the tmux
+/// session `cluster tasks` started it in, until that run ends. Works the same
+/// whether the run was started from a terminal or relaunched by `watch`.
+pub async fn attach(pool: &SqlitePool, cluster_id: &str, task_name: Option<&str>) -> Result<()> {
+    let cluster = match Cluster::fetch_by_id(pool, cluster_id).await? {
+        Some(c) => c,
+        None => anyhow::bail!("Cluster (id='{}') not found", cluster_id),
+    };
+    if cluster.state != ClusterState::Running {
+        anyhow::bail!(
+            "Cluster '{}' is not running (current state: {})",
+            cluster.id,
+            cluster.state
+        );
+    }
+
+    // Tasks run on the head node, the first in slot order.
+    let mut nodes = cluster.get_nodes(pool).await?;
+    slot_order(&mut nodes);
+    let Some(head) = nodes.first() else {
+        anyhow::bail!("Cluster '{}' has no nodes", cluster.id);
+    };
+    let head_ip = match &head.public_ip {
+        Some(ip) => ip.clone(),
+        None => anyhow::bail!("Head node has no public IP"),
+    };
+    let head_ssh = SshSession::for_cluster_node(
+        &cluster.provider_id,
+        head.private_ip.as_deref().unwrap_or_default(),
+        Some(&head_ip),
+        &head_ip,
+        &utils::expand_tilde(&cluster.private_ssh_key_path),
+    );
+
+    let sessions: Vec<String> = head_ssh
+        .run_command("tmux list-sessions -F '#{session_name}' 2>/dev/null; true")
+        .await?
+        .lines()
+        .filter(|session| is_run_session(session, task_name))
+        .map(|session| session.to_string())
+        .collect();
+    let session_name = match (sessions.as_slice(), task_name) {
+        ([session], _) => session,
+        ([], Some(name)) => {
+            anyhow::bail!("Task '{}' is not running on Cluster '{}'", name, cluster.id)
+        }
+        ([], None) => anyhow::bail!("No task is running on Cluster '{}'", cluster.id),
+        (_, _) => anyhow::bail!(
+            "Several task runs are in progress on Cluster '{}' ({}), pick one with --task",
+            cluster.id,
+            sessions.join(", ")
+        ),
+    };
+
+    tracing::info!(
+        "Attached to '{}' on Cluster '{}', Ctrl+C to detach",
+        session_name,
+        cluster.id
+    );
+    let tmux = head_ssh.wait_for_tmux(session_name, Duration::from_secs(60));
+    tokio::pin!(tmux);
+    tokio::select! {
+        result = &mut tmux => result?,
+        followed = follow_log(&head_ssh, session_name) => {
+            if let Err(e) = followed {
+                tracing::warn!("{}", e);
+            }
+            tmux.await?
+        }
+    }
+    tracing::info!(
+        "Run '{}' ended with exit code {}",
+        session_name,
+        head_ssh.tmux_exit_code(session_name).await?
+    );
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use crate::database::models::Node;
+use crate::integrations::cloud_interface::{
+    BlockStoragePricing, CloudInfoProvider, CloudProvider, SpotPricing,
+};
+use crate::integrations::providers::aws::EbsPricing;
+use crate::utils;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+const GP3_THROUGHPUT_BASELINE_MBS: f64 = 125.0;
+const GP3_PROVISIONED_THROUGHPUT_MBS: f64 = 500.0;
+const GP3_IOPS_BASELINE: f64 = 3000.0;
+const GP3_IOPS_PER_MONTH: f64 = 0.005;
+const IO1_IO2_IOPS_PER_MONTH: f64 = 0.065;
+const PUBLIC_IPV4_PER_HOUR: f64 = 0.005;
+const HOURS_PER_MONTH: f64 = 730.0;
+
+#[derive(Serialize, Deserialize)]
+pub struct NodeCostBreakdown {
+    pub node_id: String,
+    pub instance_type: String,
+    pub allocation_mode: String,
+    pub instance_cost_per_hour: f64,
+    pub ebs_cost_per_hour: f64,
+    pub iops_cost_per_hour: f64,
+    pub public_ip_cost_per_hour: f64,
+    pub node_total_per_hour: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CostBreakdown {
+    pub nodes: Vec<NodeCostBreakdown>,
+    pub total_per_hour: f64,
+}
+
+/// Prices `nodes` at the provider's live rates in `region`, spot nodes at the
+/// spot price of `availability_zone` where the provider quotes one.
+pub async fn estimate_cost(
+    cloud_interface: &CloudProvider,
+    region: &str,
+    availability_zone: &str,
+    nodes: &[Node],
+) -> Result<CostBreakdown> {
+    let unique_instance_types: Vec<String> = nodes
+        .iter()
+        .map(|n| n.instance_type.clone())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let instance_pricing_tracker =
+        utils::ProgressTracker::new(unique_instance_types.len() as u64, Some("instance pricing"));
+    let live_instance_prices = cloud_interface
+        .fetch_prices(region, &unique_instance_types, &instance_pricing_tracker)
+        .await?;
+    instance_pricing_tracker.finish_with_message("Instance pricing fetched");
+
+    let spot_instance_types: Vec<String> = nodes
+        .iter()
+        .filter(|n| n.allocation_mode == "spot")
+        .map(|n| n.instance_type.clone())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let live_spot_prices = match cloud_interface.spot_pricing() {
+        Some(spot_pricing) if !spot_instance_types.is_empty() => {
+            spot_pricing
+                .fetch_spot_prices(region, &spot_instance_types, availability_zone)
+                .await?
+        }
+        _ => HashMap::new(),
+    };
+
+    // Providers without separately billed block storage include the disk and
+    // the public IPv4 address in the instance price.
+    let unique_volume_types: HashSet<String> =
+        nodes.iter().map(|n| n.root_volume_type.clone()).collect();
+    let mut ebs_price_cache: HashMap<String, EbsPricing> = HashMap::new();
+    let public_ip_cost_per_hour = match cloud_interface.block_storage_pricing() {
+        Some(storage_pricing) => {
+            for vt in &unique_volume_types {
+                ebs_price_cache.insert(
+                    vt.clone(),
+                    storage_pricing.fetch_ebs_pricing(region, vt).await?,
+                );
+            }
+            PUBLIC_IPV4_PER_HOUR
+        }
+        None => 0.0,
+    };
+
+    let mut cost_nodes: Vec<NodeCostBreakdown> = vec![];
+    for node in nodes {
+        let instance_cost_per_hour = if node.allocation_mode == "spot" {
+            *live_spot_prices.get(&node.instance_type).unwrap_or(
+                live_instance_prices
+                    .get(&node.instance_type)
+                    .unwrap_or(&0.0),
+            )
+        } else {
+            *live_instance_prices
+                .get(&node.instance_type)
+                .unwrap_or(&0.0)
+        };
+
+        let (ebs_cost_per_hour, iops_cost_per_hour) = match ebs_price_cache
+            .get(&node.root_volume_type)
+        {
+            Some(ebs_pricing) => {
+                let storage_cost =
+                    node.root_volume_gb as f64 * ebs_pricing.storage_per_gb_month / HOURS_PER_MONTH;
+                let extra_throughput =
+                    (GP3_PROVISIONED_THROUGHPUT_MBS - GP3_THROUGHPUT_BASELINE_MBS).max(0.0);
+                let throughput_cost =
+                    extra_throughput * ebs_pricing.throughput_per_mbs_month / HOURS_PER_MONTH;
+
+                let iops_cost_per_hour =
+                    match (node.root_volume_iops, node.root_volume_type.as_str()) {
+                        (Some(iops), "gp3") => {
+                            let extra = (iops as f64 - GP3_IOPS_BASELINE).max(0.0);
+                            extra * GP3_IOPS_PER_MONTH / HOURS_PER_MONTH
+                        }
+                        (Some(iops), "io1") | (Some(iops), "io2") => {
+                            iops as f64 * IO1_IO2_IOPS_PER_MONTH / HOURS_PER_MONTH
+                        }
+                        _ => 0.0,
+                    };
+                (storage_cost + throughput_cost, iops_cost_per_hour)
+            }
+            None => (0.0, 0.0),
+        };
+
+        let node_total_per_hour = instance_cost_per_hour
+            + ebs_cost_per_hour
+            + iops_cost_per_hour
+            + public_ip_cost_per_hour;
+
+        cost_nodes.push(NodeCostBreakdown {
+            node_id: node.id.clone(),
+            instance_type: node.instance_type.clone(),
+            allocation_mode: node.allocation_mode.clone(),
+            instance_cost_per_hour,
+            ebs_cost_per_hour,
+            iops_cost_per_hour,
+            public_ip_cost_per_hour,
+            node_total_per_hour,
+        });
+    }
+    let total_per_hour = cost_nodes.iter().map(|n| n.node_total_per_hour).sum();
+    Ok(CostBreakdown {
+        nodes: cost_nodes,
+        total_per_hour,
+    })
+}
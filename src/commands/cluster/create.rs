@@ -1,8 +1,13 @@
+use crate::commands::cluster::cost::estimate_cost;
 use crate::database::models::{
-    Cluster, ClusterState, InstanceType, Node, Provider, ProviderConfig, RecoveryNode, ShellCommand,
+    Cluster, ClusterState, InstanceType, Node, Provider, ProviderConfig, RecoveryNode,
+    SelectionPolicy, ShellCommand,
 };
-use crate::integrations::{cloud_interface::CloudInfoProvider, providers::aws::AwsInterface};
+use crate::integrations::cloud_interface::{CloudInfoProvider, CloudProvider};
 use crate::utils;
+use crate::utils::health_probe::HealthProbe;
+use crate::utils::launcher::CheckpointSignal;
+use crate::utils::slurm::Scheduler;
 
 use anyhow::Result;
 use chrono::Utc;
@@ -13,32 +18,6 @@ use std::fs;
 use std::path::Path;
 use serde_json;
 
-const GP3_THROUGHPUT_BASELINE_MBS: f64 = 125.0;
-const GP3_PROVISIONED_THROUGHPUT_MBS: f64 = 500.0;
-const GP3_IOPS_BASELINE: f64 = 3000.0;
-const GP3_IOPS_PER_MONTH: f64 = 0.005;
-const IO1_IO2_IOPS_PER_MONTH: f64 = 0.065;
-const PUBLIC_IPV4_PER_HOUR: f64 = 0.005;
-const HOURS_PER_MONTH: f64 = 730.0;
-
-#[derive(Serialize, Deserialize)]
-struct NodeCostBreakdown {
-    node_id: String,
-    instance_type: String,
-    allocation_mode: String,
-    instance_cost_per_hour: f64,
-    ebs_cost_per_hour: f64,
-    iops_cost_per_hour: f64,
-    public_ip_cost_per_hour: f64,
-    node_total_per_hour: f64,
-}
-
-#[derive(Serialize, Deserialize)]
-struct CostBreakdown {
-    nodes: Vec<NodeCostBreakdown>,
-    total_per_hour: f64,
-}
-
 #[derive(Debug, Deserialize, Serialize)]
 struct ClusterYaml {
     id: Option<String>,
@@ -57,6 +36,13 @@ struct ClusterYaml {
     efs_provisioned_throughput_mbs: Option<f64>,
     nodes: Vec<NodeYaml>,
     on_interruption: Option<InterruptionPolicyYaml>,
+    /// How `watch` asks the application to checkpoint on a spot interruption
+    /// notice. Omit to signal the job through its launcher.
+    checkpoint_signal: Option<CheckpointSignal>,
+    /// Workload manager set up during spawn.
+    scheduler: Option<Scheduler>,
+    /// Checks `watch` runs on every node besides looking at instance state.
+    health_probes: Option<Vec<HealthProbe>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -69,11 +55,26 @@ pub struct NodeYaml {
     root_volume_type: Option<String>,
     root_volume_iops: Option<i64>,
     init_commands: Option<Vec<String>>,
+    /// Highest hourly price to pay for a spot node. Omit to cap at the on-demand
+    /// price, which is what AWS does when no maximum is given.
+    spot_max_price: Option<f64>,
+    /// 'one-time' (default) or 'persistent'.
+    spot_request_type: Option<String>,
+    /// 'terminate' (default), 'stop' or 'hibernate'.
+    spot_interruption_behavior: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct InterruptionPolicyYaml {
     nodes: Vec<RecoveryNodeYaml>,
+    /// Zones of the same region a restore may move a worker to, in order, when
+    /// its own zone has no capacity for any of its preferred types.
+    fallback_availability_zones: Option<Vec<String>>,
+    /// How a restore picks among a slot's preferred_instance_types: 'ordered'
+    /// (default), 'cheapest' or 'cheapest_per_vcpu'.
+    selection_policy: Option<SelectionPolicy>,
+    /// Highest hourly price, in USD, a replacement may launch at.
+    max_price_per_hour: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -267,14 +268,15 @@ pub async fn create(
     };
 
     // Get cloud interface
-    let config_vars = provider_config.get_config_vars(pool).await?;
     let provider_id = provider_config.provider_id.clone();
-    let cloud_interface = match provider_id.as_str() {
-        "aws" => AwsInterface { config_vars },
-        _ => {
-            anyhow::bail!("Provider '{}' is currently not supported.", &provider_id)
-        }
-    };
+    let cloud_interface = CloudProvider::from_provider_config(pool, &provider_config).await?;
+
+    if cluster_yaml.use_elastic_file_system && provider_id != "aws" {
+        anyhow::bail!(
+            "use_elastic_file_system is only supported by the 'aws' provider (got '{}')",
+            provider_id
+        )
+    }
 
     tracing::info!("Validating cloud provider connection and cluster node data...");
 
@@ -384,7 +386,7 @@ pub async fn create(
         let allocation_mode = match &node_definition.allocation_mode {
             Some(mode) => match mode.to_lowercase().as_str() {
                 "spot" => match instance_type_details.supports_spot {
-                    true => mode.to_string(),
+                    true => "spot".to_string(),
                     false => {
                         anyhow::bail!(
                             "Failed validating allocation_mode for node '{}': 'spot' mode not \
@@ -408,6 +410,91 @@ pub async fn create(
             None => "on-demand".to_string(), // Default when not specified
         };
 
+        // Validate spot options
+        let has_spot_options = node_definition.spot_max_price.is_some()
+            || node_definition.spot_request_type.is_some()
+            || node_definition.spot_interruption_behavior.is_some();
+        let (spot_max_price, spot_request_type, spot_interruption_behavior) =
+            if allocation_mode == "spot" {
+                if let Some(max_price) = node_definition.spot_max_price
+                    && max_price <= 0.0
+                {
+                    anyhow::bail!(
+                        "spot_max_price for node {} must be greater than zero, got {}",
+                        i + 1,
+                        max_price
+                    )
+                }
+                let request_type = match node_definition.spot_request_type.as_deref() {
+                    Some(rt) => match rt.to_lowercase().as_str() {
+                        "one-time" | "one_time" => Some("one-time"),
+                        "persistent" => Some("persistent"),
+                        invalid => anyhow::bail!(
+                            "Invalid spot_request_type '{}' for node {}. Valid options: one-time, persistent",
+                            invalid,
+                            i + 1
+                        ),
+                    },
+                    None => None,
+                };
+                let interruption_behavior =
+                    match node_definition.spot_interruption_behavior.as_deref() {
+                        Some(ib) => match ib.to_lowercase().as_str() {
+                            "terminate" => Some("terminate"),
+                            "stop" => Some("stop"),
+                            "hibernate" => Some("hibernate"),
+                            invalid => anyhow::bail!(
+                                "Invalid spot_interruption_behavior '{}' for node {}. Valid options: terminate, stop, hibernate",
+                                invalid,
+                                i + 1
+                            ),
+                        },
+                        None => None,
+                    };
+                // run_instances only accepts two shapes of spot request: a one-time
+                // request that terminates on interruption, or a persistent one that
+                // stops or hibernates (AWS can only resume the instance through the
+                // request that launched it). Whichever half is omitted is inferred
+                // from the other, and a mismatch is rejected here rather than at
+                // spawn time, after the network has already been built.
+                let (request_type, interruption_behavior) =
+                    match (request_type, interruption_behavior) {
+                        (None, None) => ("one-time", "terminate"),
+                        (Some(rt), None) => {
+                            (rt, if rt == "persistent" { "stop" } else { "terminate" })
+                        }
+                        (None, Some(behavior)) => {
+                            (if behavior == "terminate" { "one-time" } else { "persistent" }, behavior)
+                        }
+                        (Some(rt), Some(behavior)) => {
+                            if (rt == "one-time") != (behavior == "terminate") {
+                                anyhow::bail!(
+                                    "spot_request_type '{}' cannot be combined with \
+                                    spot_interruption_behavior '{}' for node {}. Use 'one-time' \
+                                    with 'terminate', or 'persistent' with 'stop' or 'hibernate'",
+                                    rt,
+                                    behavior,
+                                    i + 1
+                                )
+                            }
+                            (rt, behavior)
+                        }
+                    };
+                (
+                    node_definition.spot_max_price,
+                    Some(request_type.to_string()),
+                    Some(interruption_behavior.to_string()),
+                )
+            } else if has_spot_options {
+                anyhow::bail!(
+                    "Spot options are set for node {}, but its allocation_mode is '{}'",
+                    i + 1,
+                    allocation_mode
+                )
+            } else {
+                (None, None, None)
+            };
+
         // Validate node_affinity
         if cluster_yaml.use_node_affinity && !instance_type_details.has_affinity_settings {
             anyhow::bail!(
@@ -543,93 +630,22 @@ pub async fn create(
             public_ip: None,
             was_efs_configured: false,
             was_ssh_configured: false,
+            spot_max_price,
+            spot_request_type,
+            spot_interruption_behavior,
+            market_type: None,
+            efa_available: None,
+            availability_zone: None,
+            node_index: i as i64,
         });
         nodes_tracker.inc(1);
     }
     nodes_tracker.finish_with_message(&format!("Validated {} nodes", node_count));
 
     // Compute cost breakdown — fetch live prices from AWS Pricing API
-    let unique_instance_types: Vec<String> = nodes_to_insert
-        .iter()
-        .map(|n| n.instance_type.clone())
-        .collect::<std::collections::HashSet<_>>()
-        .into_iter()
-        .collect();
-    let instance_pricing_tracker = utils::ProgressTracker::new(
-        unique_instance_types.len() as u64,
-        Some("instance pricing"),
-    );
-    let live_instance_prices = cloud_interface
-        .fetch_prices(&region, &unique_instance_types, &instance_pricing_tracker)
-        .await?;
-    instance_pricing_tracker.finish_with_message("Instance pricing fetched");
-
-    let spot_instance_types: Vec<String> = nodes_to_insert
-        .iter()
-        .filter(|n| n.allocation_mode == "spot")
-        .map(|n| n.instance_type.clone())
-        .collect::<std::collections::HashSet<_>>()
-        .into_iter()
-        .collect();
-    let live_spot_prices = if !spot_instance_types.is_empty() {
-        cloud_interface.fetch_spot_prices(&region, &spot_instance_types, &zone).await?
-    } else {
-        std::collections::HashMap::new()
-    };
-
-    let unique_volume_types: std::collections::HashSet<String> =
-        nodes_to_insert.iter().map(|n| n.root_volume_type.clone()).collect();
-    let mut ebs_price_cache: std::collections::HashMap<String, crate::integrations::providers::aws::EbsPricing> =
-        std::collections::HashMap::new();
-    for vt in &unique_volume_types {
-        ebs_price_cache.insert(vt.clone(), cloud_interface.fetch_ebs_pricing(&region, vt).await?);
-    }
-
-    let mut cost_nodes: Vec<NodeCostBreakdown> = vec![];
-    for node in &nodes_to_insert {
-        let instance_cost_per_hour = if node.allocation_mode == "spot" {
-            *live_spot_prices.get(&node.instance_type).unwrap_or(
-                live_instance_prices.get(&node.instance_type).unwrap_or(&0.0),
-            )
-        } else {
-            *live_instance_prices.get(&node.instance_type).unwrap_or(&0.0)
-        };
-
-        let ebs_pricing = ebs_price_cache.get(&node.root_volume_type).unwrap();
-        let storage_cost = node.root_volume_gb as f64 * ebs_pricing.storage_per_gb_month / HOURS_PER_MONTH;
-        let extra_throughput = (GP3_PROVISIONED_THROUGHPUT_MBS - GP3_THROUGHPUT_BASELINE_MBS).max(0.0);
-        let throughput_cost = extra_throughput * ebs_pricing.throughput_per_mbs_month / HOURS_PER_MONTH;
-        let ebs_cost_per_hour = storage_cost + throughput_cost;
-
-        let iops_cost_per_hour = match (node.root_volume_iops, node.root_volume_type.as_str()) {
-            (Some(iops), "gp3") => {
-                let extra = (iops as f64 - GP3_IOPS_BASELINE).max(0.0);
-                extra * GP3_IOPS_PER_MONTH / HOURS_PER_MONTH
-            }
-            (Some(iops), "io1") | (Some(iops), "io2") => {
-                iops as f64 * IO1_IO2_IOPS_PER_MONTH / HOURS_PER_MONTH
-            }
-            _ => 0.0,
-        };
-
-        let node_total_per_hour = instance_cost_per_hour + ebs_cost_per_hour + iops_cost_per_hour + PUBLIC_IPV4_PER_HOUR;
-
-        cost_nodes.push(NodeCostBreakdown {
-            node_id: node.id.clone(),
-            instance_type: node.instance_type.clone(),
-            allocation_mode: node.allocation_mode.clone(),
-            instance_cost_per_hour,
-            ebs_cost_per_hour,
-            iops_cost_per_hour,
-            public_ip_cost_per_hour: PUBLIC_IPV4_PER_HOUR,
-            node_total_per_hour,
-        });
-    }
-    let total_per_hour = cost_nodes.iter().map(|n| n.node_total_per_hour).sum();
-    let cost_breakdown = serde_json::to_string(&CostBreakdown {
-        nodes: cost_nodes,
-        total_per_hour,
-    })?;
+    let cost = estimate_cost(&cloud_interface, &region, &zone, &nodes_to_insert).await?;
+    let total_per_hour = cost.total_per_hour;
+    let cost_breakdown = serde_json::to_string(&cost)?;
 
     // TODO: find a way to remove the code duplication here and in `database/models/cluster.rs`
     tracing::info!(
@@ -701,6 +717,16 @@ pub async fn create(
             }
             for type_name in &rn.preferred_instance_types {
                 match InstanceType::fetch_by_name_and_region(pool, type_name, &region).await? {
+                    // A replacement joins the same EFA fabric as the survivors, so
+                    // every fallback type has to support it, not just the first.
+                    Some(details)
+                        if cluster_yaml.use_elastic_fabric_adapters && !details.supports_efa =>
+                    {
+                        anyhow::bail!(
+                            "on_interruption.nodes[{}]: instance type '{}' does not support elastic fabric adapters",
+                            i, type_name
+                        )
+                    }
                     Some(_) => {}
                     None => anyhow::bail!(
                         "on_interruption.nodes[{}]: instance type '{}' is unavailable in region '{}'",
@@ -748,6 +774,7 @@ pub async fn create(
                     Some(commands) => Some(serde_json::to_string(commands)?),
                     None => None,
                 },
+                selected_instance_types: None,
             });
         }
         tracing::info!(
@@ -756,6 +783,96 @@ pub async fn create(
         );
     }
 
+    let selection_policy = cluster_yaml
+        .on_interruption
+        .as_ref()
+        .and_then(|policy| policy.selection_policy);
+    let max_price_per_hour = cluster_yaml
+        .on_interruption
+        .as_ref()
+        .and_then(|policy| policy.max_price_per_hour);
+    if let Some(max_price) = max_price_per_hour
+        && max_price <= 0.0
+    {
+        anyhow::bail!(
+            "on_interruption.max_price_per_hour must be positive (got {})",
+            max_price
+        );
+    }
+
+    let fallback_availability_zones = match cluster_yaml
+        .on_interruption
+        .as_ref()
+        .and_then(|policy| policy.fallback_availability_zones.as_ref())
+    {
+        Some(fallback_zones) if !fallback_zones.is_empty() => {
+            for (i, fallback_zone) in fallback_zones.iter().enumerate() {
+                if *fallback_zone == cluster_yaml.availability_zone {
+                    anyhow::bail!(
+                        "on_interruption.fallback_availability_zones[{}]: '{}' is the cluster's own zone",
+                        i,
+                        fallback_zone
+                    );
+                }
+                if !zones.contains(fallback_zone) {
+                    anyhow::bail!(
+                        "on_interruption.fallback_availability_zones[{}]: zone '{}' is not available. Possible options: {:?}",
+                        i,
+                        fallback_zone,
+                        zones
+                    );
+                }
+                if fallback_zones[..i].contains(fallback_zone) {
+                    anyhow::bail!(
+                        "on_interruption.fallback_availability_zones[{}]: '{}' is listed twice",
+                        i,
+                        fallback_zone
+                    );
+                }
+            }
+            tracing::warn!(
+                "A node moved to a fallback zone talks to the rest of the cluster across zones: \
+                MPI latency goes up and inter-zone traffic is billed per GB"
+            );
+            if cluster_yaml.use_elastic_file_system {
+                tracing::warn!(
+                    "Each fallback zone a node moves to gets an EFS mount target of its own, \
+                    kept until the cluster is terminated"
+                );
+            }
+            if cluster_yaml.use_node_affinity {
+                tracing::warn!(
+                    "Nodes moved to a fallback zone launch outside the cluster's placement group"
+                );
+            }
+            if cluster_yaml.use_elastic_fabric_adapters {
+                tracing::warn!(
+                    "EFA traffic does not cross zones: MPI reaches a node in a fallback zone over TCP"
+                );
+            }
+            Some(serde_json::to_string(fallback_zones)?)
+        }
+        _ => None,
+    };
+
+    let checkpoint_signal = match &cluster_yaml.checkpoint_signal {
+        Some(checkpoint_signal) => {
+            checkpoint_signal.validate()?;
+            Some(serde_json::to_string(checkpoint_signal)?)
+        }
+        None => None,
+    };
+
+    let health_probes = match &cluster_yaml.health_probes {
+        Some(health_probes) => {
+            for health_probe in health_probes {
+                health_probe.validate()?;
+            }
+            Some(serde_json::to_string(health_probes)?)
+        }
+        None => None,
+    };
+
     if !utils::user_confirmation(
         skip_confirmation,
         "Do you want to proceed creating this cluster?",
@@ -782,6 +899,12 @@ pub async fn create(
         state: ClusterState::Pending,
         cost_per_hour: total_per_hour,
         cost_breakdown,
+        checkpoint_signal,
+        scheduler: cluster_yaml.scheduler.map(|s| s.to_string()),
+        health_probes,
+        fallback_availability_zones,
+        recovery_selection_policy: selection_policy.map(|p| p.to_string()),
+        recovery_max_price_per_hour: max_price_per_hour,
     };
     cluster
         .insert(pool, nodes_to_insert, commands_to_insert, recovery_nodes_to_insert)
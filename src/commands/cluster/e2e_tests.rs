@@ -0,0 +1,935 @@
+//! create -> spawn -> watch -> restore -> terminate against the mock provider.
+
+use crate::commands::cluster::{WatchControl, create, spawn, terminate, watch, watch_request};
+use crate::database::models::{
+    Cluster, ClusterEvent, ClusterEventType, ClusterState, RecoveryIncident, RecoveryPhase,
+    TaskRun, TaskRunJob, WatchLock, slot_order,
+};
+use crate::integrations::CloudResourceManager;
+use crate::integrations::providers::mock::{
+    MOCK_FALLBACK_ZONE, MOCK_IMAGE, MOCK_REGION, MOCK_ZONE, MockInterface, MockScript, MockState,
+};
+use crate::testing::TestEnv;
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering::SeqCst};
+use tokio::time::{Duration, Instant, sleep};
+
+const NODE: &str = "  - instance_type: mock.small
+    allocation_mode: {allocation_mode}
+    image_id: {image}
+    init_commands: ['echo ready']
+";
+
+/// Writes a two-node cluster YAML for `mock` and runs `create` on it.
+async fn create_cluster(env: &TestEnv, mock: &MockInterface, allocation_mode: &str) -> String {
+    create_cluster_with(env, mock, allocation_mode, "").await
+}
+
+/// Like `create_cluster`, with `extra` appended to the YAML: top-level keys, or
+/// indented ones that continue the `on_interruption` block.
+async fn create_cluster_with(
+    env: &TestEnv,
+    mock: &MockInterface,
+    allocation_mode: &str,
+    extra: &str,
+) -> String {
+    let provider_config_id = env.add_mock_provider(mock).await;
+    let private_key = env.write_file("id_rsa", "private");
+    let public_key = env.write_file("id_rsa.pub", "public");
+    let node = NODE
+        .replace("{allocation_mode}", allocation_mode)
+        .replace("{image}", MOCK_IMAGE);
+    let yaml = format!(
+        "id: e2e
+display_name: e2e
+provider_config_id: {provider_config_id}
+private_ssh_key_path: {private_key}
+public_ssh_key_path: {public_key}
+region: {MOCK_REGION}
+availability_zone: {MOCK_ZONE}
+use_node_affinity: false
+use_elastic_fabric_adapters: false
+use_elastic_file_system: false
+nodes:
+{node}{node}on_interruption:
+  nodes:
+    - preferred_instance_types: [mock.large, mock.small]
+      image_id: {MOCK_IMAGE}
+    - preferred_instance_types: [mock.large, mock.small]
+      image_id: {MOCK_IMAGE}
+{extra}"
+    );
+    let yaml_path = env.write_file("cluster.yaml", &yaml);
+    create(&env.pool, &yaml_path, true).await.unwrap();
+    "e2e".to_string()
+}
+
+/// Runs `watch` on the cluster until `condition` holds, failing the test if the
+/// monitor stops first or nothing happens within a few seconds.
+async fn watch_until(
+    env: &TestEnv,
+    cluster_id: &str,
+    what: &str,
+    condition: impl AsyncFnMut() -> bool,
+) {
+    watch_until_with(env, cluster_id, false, what, condition).await
+}
+
+/// Like `watch_until`, with the monitor in proactive mode when `proactive`.
+async fn watch_until_with(
+    env: &TestEnv,
+    cluster_id: &str,
+    proactive: bool,
+    what: &str,
+    mut condition: impl AsyncFnMut() -> bool,
+) {
+    let wait = async {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !condition().await {
+            assert!(Instant::now() < deadline, "timed out waiting for {}", what);
+            sleep(Duration::from_millis(10)).await;
+        }
+    };
+    let control = WatchControl::new(cluster_id, None, None);
+    tokio::select! {
+        result = watch(&env.pool, cluster_id, 0, None, false, proactive, &control) => {
+            panic!("watch stopped before {}: {:?}", what, result)
+        }
+        _ = wait => {}
+    }
+}
+
+#[tokio::test]
+async fn watch_restores_a_dead_node_with_a_capacity_fallback() {
+    let env = TestEnv::new().await;
+    let mock = MockInterface::new(MockScript {
+        capacity_errors: HashMap::from([("mock.large".to_string(), 1)]),
+        instance_deaths: vec![(2, 1)],
+        eni_release_delay: Duration::from_millis(20),
+        ..Default::default()
+    });
+    let cluster_id = create_cluster(&env, &mock, "on-demand").await;
+
+    spawn(&env.pool, &cluster_id, true, 0, false).await.unwrap();
+    assert_eq!(mock.state().instances.len(), 2);
+
+    // The restore is over once watch closes its incident, the last thing it
+    // records, just after the cluster is back to running.
+    watch_until(&env, &cluster_id, "the incident to close", async || {
+        RecoveryIncident::fetch_all_by_cluster_id(&env.pool, &cluster_id)
+            .await
+            .unwrap()
+            .iter()
+            .any(|i| i.finished_at.is_some())
+    })
+    .await;
+    let cluster = Cluster::fetch_by_id(&env.pool, &cluster_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(cluster.state, ClusterState::Running);
+    assert_eq!(mock.state().instances.len(), 3);
+    assert!(mock.state().live_instance(&cluster_id, 1).is_some());
+
+    // mock.large had no capacity, so the replacement fell back to mock.small
+    // and the node row records what actually launched.
+    let nodes = cluster.get_nodes(&env.pool).await.unwrap();
+    assert_eq!(nodes[1].instance_type, "mock.small");
+    assert_eq!(nodes[1].private_ip.as_deref(), Some("10.0.0.11"));
+    assert_eq!(
+        mock.state().launch_attempts,
+        ["mock.small", "mock.small", "mock.large", "mock.small"]
+    );
+    assert_eq!(mock.state().eni_release_waits, 1);
+    let init_runs = mock
+        .state()
+        .commands
+        .iter()
+        .filter(|(_, script)| script.contains("echo ready"))
+        .count();
+    assert_eq!(init_runs, 3);
+
+    // The timeline tells the same story, in order.
+    let events = ClusterEvent::fetch_by_cluster_id(&env.pool, &cluster_id, None)
+        .await
+        .unwrap();
+    let timeline: Vec<ClusterEventType> = events
+        .iter()
+        .filter(|e| e.event_type != ClusterEventType::StateTransition)
+        .map(|e| e.event_type.clone())
+        .collect();
+    assert_eq!(
+        timeline,
+        [
+            ClusterEventType::NodeLaunched,
+            ClusterEventType::NodeLaunched,
+            ClusterEventType::NodeFailed,
+            ClusterEventType::RestoreStarted,
+            ClusterEventType::CapacityFallbackUsed,
+            ClusterEventType::NodeLaunched,
+            ClusterEventType::RestoreFinished,
+        ]
+    );
+    let fallback = events
+        .iter()
+        .find(|e| e.event_type == ClusterEventType::CapacityFallbackUsed)
+        .unwrap();
+    assert_eq!(fallback.node_id.as_deref(), Some(nodes[1].id.as_str()));
+    assert_eq!(fallback.details_json()["launched"], "mock.small");
+    let transitions: Vec<&str> = events
+        .iter()
+        .filter(|e| e.event_type == ClusterEventType::StateTransition)
+        .map(|e| e.message.as_str())
+        .collect();
+    assert_eq!(
+        transitions,
+        [
+            "pending -> spawning",
+            "spawning -> running",
+            "running -> restoring",
+            "restoring -> running",
+        ]
+    );
+
+    // One incident, with every phase of a replacement on a cluster without EFS,
+    // a notice or a job to relaunch.
+    let incidents = RecoveryIncident::fetch_all_by_cluster_id(&env.pool, &cluster_id)
+        .await
+        .unwrap();
+    assert_eq!(incidents.len(), 1);
+    let incident = &incidents[0];
+    assert_eq!(incident.mode, "replace");
+    assert_eq!(incident.outcome, "recovered");
+    assert_eq!(incident.failed_ips(), ["10.0.0.11"]);
+    let reached: Vec<RecoveryPhase> = RecoveryPhase::ALL
+        .into_iter()
+        .filter(|&p| incident.reached_at(p).is_some())
+        .collect();
+    assert_eq!(
+        reached,
+        [
+            RecoveryPhase::FailureDetected,
+            RecoveryPhase::EnisReleased,
+            RecoveryPhase::InstancesRequested,
+            RecoveryPhase::InstancesRunning,
+            RecoveryPhase::SshReady,
+            RecoveryPhase::InitDone,
+        ]
+    );
+    // The ENI wait is the one phase the mock makes take a while.
+    let durations = incident.phase_durations();
+    let (_, eni_secs) = durations[2];
+    assert!(eni_secs.unwrap() >= 0.02);
+    assert!(
+        durations
+            .iter()
+            .flat_map(|(_, secs)| secs)
+            .all(|&s| s >= 0.0)
+    );
+    assert!(incident.total_duration().unwrap() >= eni_secs.unwrap());
+
+    terminate(&env.pool, &cluster_id, true).await.unwrap();
+    let cluster = Cluster::fetch_by_id(&env.pool, &cluster_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(cluster.state, ClusterState::Pending);
+    assert!(mock.state().instances.iter().all(|i| !i.running));
+    let nodes = cluster.get_nodes(&env.pool).await.unwrap();
+    assert!(nodes.iter().all(|n| n.public_ip.is_none()));
+}
+
+#[tokio::test]
+async fn restore_moves_a_node_to_a_fallback_zone_when_its_zone_has_no_capacity() {
+    let env = TestEnv::new().await;
+    let mock = MockInterface::new(MockScript {
+        instance_deaths: vec![(2, 1)],
+        ..Default::default()
+    });
+    let fallback_zones = format!("  fallback_availability_zones: [{MOCK_FALLBACK_ZONE}]\n");
+    let cluster_id = create_cluster_with(&env, &mock, "on-demand", &fallback_zones).await;
+    spawn(&env.pool, &cluster_id, true, 0, false).await.unwrap();
+
+    // The cluster's zone runs dry once it is up.
+    mock.state()
+        .script
+        .exhausted_zones
+        .insert(MOCK_ZONE.to_string());
+    watch_until(&env, &cluster_id, "the restore to finish", async || {
+        ClusterEvent::fetch_by_cluster_id(&env.pool, &cluster_id, None)
+            .await
+            .unwrap()
+            .iter()
+            .any(|e| e.event_type == ClusterEventType::RestoreFinished)
+    })
+    .await;
+
+    // Both preferred types were tried at home before the move, and the
+    // replacement got the fallback zone's subnet.
+    assert_eq!(
+        mock.state().launch_attempts,
+        [
+            "mock.small",
+            "mock.small",
+            "mock.large",
+            "mock.small",
+            "mock.large"
+        ]
+    );
+    {
+        let state = mock.state();
+        let instance = state.live_instance(&cluster_id, 1).unwrap();
+        assert_eq!(instance.availability_zone, MOCK_FALLBACK_ZONE);
+        assert_eq!(instance.private_ip, "10.0.1.11");
+    }
+
+    let cluster = Cluster::fetch_by_id(&env.pool, &cluster_id)
+        .await
+        .unwrap()
+        .unwrap();
+    let nodes = cluster.get_nodes(&env.pool).await.unwrap();
+    assert_eq!(nodes[0].availability_zone, None);
+    assert_eq!(
+        nodes[1].availability_zone.as_deref(),
+        Some(MOCK_FALLBACK_ZONE)
+    );
+    assert_eq!(nodes[1].private_ip.as_deref(), Some("10.0.1.11"));
+
+    let events = ClusterEvent::fetch_by_cluster_id(&env.pool, &cluster_id, None)
+        .await
+        .unwrap();
+    let moved = events
+        .iter()
+        .find(|e| e.event_type == ClusterEventType::ZoneFallbackUsed)
+        .unwrap();
+    assert_eq!(moved.node_id.as_deref(), Some(nodes[1].id.as_str()));
+    assert_eq!(moved.details_json()["from"], MOCK_ZONE);
+    assert_eq!(moved.details_json()["to"], MOCK_FALLBACK_ZONE);
+    assert_eq!(moved.details_json()["private_ip"], "10.0.1.11");
+}
+
+#[tokio::test]
+async fn restore_picks_the_cheapest_type_per_vcpu_and_reprices_the_cluster() {
+    let env = TestEnv::new().await;
+    let mock = MockInterface::new(MockScript {
+        instance_deaths: vec![(2, 1)],
+        ..Default::default()
+    });
+    let policy = "  selection_policy: cheapest_per_vcpu\n  max_price_per_hour: 0.35\n";
+    let cluster_id = create_cluster_with(&env, &mock, "on-demand", policy).await;
+    spawn(&env.pool, &cluster_id, true, 0, false).await.unwrap();
+    let cluster = Cluster::fetch_by_id(&env.pool, &cluster_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!((cluster.cost_per_hour - 0.20).abs() < 1e-9);
+
+    watch_until(&env, &cluster_id, "the restore to finish", async || {
+        ClusterEvent::fetch_by_cluster_id(&env.pool, &cluster_id, None)
+            .await
+            .unwrap()
+            .iter()
+            .any(|e| e.event_type == ClusterEventType::RestoreFinished)
+    })
+    .await;
+
+    // mock.large costs $0.04 per vCPU against mock.small's $0.05, and is under
+    // the ceiling.
+    let events = ClusterEvent::fetch_by_cluster_id(&env.pool, &cluster_id, None)
+        .await
+        .unwrap();
+    let selected = events
+        .iter()
+        .find(|e| e.event_type == ClusterEventType::InstanceTypeSelected)
+        .unwrap();
+    let details = selected.details_json();
+    assert_eq!(details["slot_index"], 1);
+    assert_eq!(details["policy"], "cheapest_per_vcpu");
+    assert_eq!(details["instance_type"], "mock.large");
+    assert_eq!(details["price_per_hour"], 0.32);
+    assert_eq!(details["candidates"].as_array().unwrap().len(), 2);
+
+    let cluster = Cluster::fetch_by_id(&env.pool, &cluster_id)
+        .await
+        .unwrap()
+        .unwrap();
+    let nodes = cluster.get_nodes(&env.pool).await.unwrap();
+    assert_eq!(nodes[1].instance_type, "mock.large");
+    assert_eq!(
+        mock.state().launch_attempts,
+        ["mock.small", "mock.small", "mock.large"]
+    );
+    assert!((cluster.cost_per_hour - 0.42).abs() < 1e-9);
+    let breakdown: serde_json::Value = serde_json::from_str(&cluster.cost_breakdown).unwrap();
+    assert_eq!(breakdown["nodes"][1]["instance_type"], "mock.large");
+}
+
+#[tokio::test]
+async fn spot_notice_signals_the_running_jobs_through_their_launcher() {
+    let env = TestEnv::new().await;
+    let mock = MockInterface::new(MockScript {
+        spot_notices: vec![(1, 1)],
+        ..Default::default()
+    });
+    let cluster_id = create_cluster(&env, &mock, "spot").await;
+
+    spawn(&env.pool, &cluster_id, true, 0, false).await.unwrap();
+    assert!(mock.state().spot_queues.contains(&cluster_id));
+
+    // Hydra's ranks are signalled on each of the run's nodes, Open MPI's
+    // through mpirun on the head node.
+    let cluster = Cluster::fetch_by_id(&env.pool, &cluster_id)
+        .await
+        .unwrap()
+        .unwrap();
+    let node_ids: Vec<String> = cluster
+        .get_nodes(&env.pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|n| n.id)
+        .collect();
+    for (task_name, launcher) in [("solve", "mpich"), ("post", "openmpi")] {
+        let key = (task_name.to_string(), 1, BTreeMap::new());
+        let job = TaskRunJob {
+            node_ids: &node_ids,
+            launcher,
+            checkpoint_signal: None,
+        };
+        TaskRun::start(&env.pool, &cluster_id, "abc", &key, 1, &job)
+            .await
+            .unwrap();
+    }
+
+    let signalled = |state: &MockState| {
+        state
+            .commands
+            .iter()
+            .filter(|(_, script)| script.contains("pkill -USR1"))
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    watch_until(&env, &cluster_id, "the checkpoint signal", async || {
+        signalled(&mock.state()).len() == 3
+    })
+    .await;
+
+    let signalled = signalled(&mock.state());
+    let ips: Vec<&str> = signalled.iter().map(|(ip, _)| ip.as_str()).collect();
+    assert_eq!(ips, ["10.0.0.10", "10.0.0.11", "10.0.0.10"]);
+    assert!(signalled[0].1.contains("hydra_pmi_proxy"));
+    assert!(signalled[2].1.contains("mpirun"));
+    // A notice is only a warning: nothing was replaced.
+    assert_eq!(mock.state().instances.len(), 2);
+
+    terminate(&env.pool, &cluster_id, true).await.unwrap();
+    assert!(mock.state().spot_queues.is_empty());
+}
+
+#[tokio::test]
+async fn spot_notice_reports_which_nodes_acknowledged_the_checkpoint() {
+    let env = TestEnv::new().await;
+    let mock = MockInterface::new(MockScript {
+        spot_notices: vec![(1, 1)],
+        ..Default::default()
+    })
+    .with_command_handler(|ip, script| {
+        // Only the head node's application gets its checkpoint flushed in time.
+        if script.starts_with("test -e") {
+            let answer = if ip == "10.0.0.10" {
+                "acknowledged"
+            } else {
+                "pending"
+            };
+            return Ok(answer.to_string());
+        }
+        Ok(String::new())
+    });
+    let cluster_id = create_cluster(&env, &mock, "spot").await;
+    spawn(&env.pool, &cluster_id, true, 0, false).await.unwrap();
+
+    let cluster = Cluster::fetch_by_id(&env.pool, &cluster_id)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut nodes = cluster.get_nodes(&env.pool).await.unwrap();
+    slot_order(&mut nodes);
+    let node_ids: Vec<String> = nodes.iter().map(|n| n.id.clone()).collect();
+    let key = ("solve".to_string(), 1, BTreeMap::new());
+    let job = TaskRunJob {
+        node_ids: &node_ids,
+        launcher: "openmpi",
+        checkpoint_signal: Some(
+            r#"{"process":"starfwi-fwi","signal":"USR2","ack_file":"/tmp/ckpt.ack","ack_timeout_secs":1}"#,
+        ),
+    };
+    TaskRun::start(&env.pool, &cluster_id, "abc", &key, 1, &job)
+        .await
+        .unwrap();
+
+    let acks = async || {
+        ClusterEvent::fetch_by_cluster_id(&env.pool, &cluster_id, None)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|e| e.event_type == ClusterEventType::CheckpointAck)
+            .collect::<Vec<_>>()
+    };
+    watch_until(&env, &cluster_id, "the checkpoint acks", async || {
+        acks().await.len() == 2
+    })
+    .await;
+
+    // The declared process is signalled on each node instead of mpirun.
+    let signalled: Vec<String> = mock
+        .state()
+        .commands
+        .iter()
+        .filter(|(_, script)| script.contains("pkill"))
+        .map(|(ip, script)| format!("{} {}", ip, script))
+        .collect();
+    assert_eq!(
+        signalled,
+        [
+            "10.0.0.10 pkill -USR2 -x 'starfwi-fwi'",
+            "10.0.0.11 pkill -USR2 -x 'starfwi-fwi'"
+        ]
+    );
+
+    let acknowledged: Vec<(String, bool)> = acks()
+        .await
+        .iter()
+        .map(|e| {
+            let details: serde_json::Value =
+                serde_json::from_str(e.details.as_deref().unwrap()).unwrap();
+            (
+                e.node_id.clone().unwrap(),
+                details["acknowledged"].as_bool().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        acknowledged,
+        [(node_ids[0].clone(), true), (node_ids[1].clone(), false)]
+    );
+}
+
+#[tokio::test]
+async fn recovery_incident_records_the_notice_lead_time() {
+    let env = TestEnv::new().await;
+    let mock = MockInterface::new(MockScript {
+        spot_notices: vec![(1, 1)],
+        instance_deaths: vec![(3, 1)],
+        ..Default::default()
+    });
+    let cluster_id = create_cluster(&env, &mock, "spot").await;
+    spawn(&env.pool, &cluster_id, true, 0, false).await.unwrap();
+
+    watch_until(&env, &cluster_id, "the incident to close", async || {
+        RecoveryIncident::fetch_all_by_cluster_id(&env.pool, &cluster_id)
+            .await
+            .unwrap()
+            .iter()
+            .any(|i| i.finished_at.is_some())
+    })
+    .await;
+
+    let incidents = RecoveryIncident::fetch_all_by_cluster_id(&env.pool, &cluster_id)
+        .await
+        .unwrap();
+    let notice = incidents[0].notice_received_at.unwrap();
+    assert!(notice <= incidents[0].failure_detected_at);
+    let (phase, lead_time) = incidents[0].phase_durations()[1];
+    assert_eq!(phase, RecoveryPhase::FailureDetected);
+    assert!(lead_time.unwrap() >= 0.0);
+}
+
+#[tokio::test]
+async fn proactive_watch_replaces_a_noticed_node_before_it_dies() {
+    let env = TestEnv::new().await;
+    let mock = MockInterface::new(MockScript {
+        spot_notices: vec![(1, 1)],
+        instance_deaths: vec![(3, 1)],
+        eni_release_delay: Duration::from_millis(50),
+        ..Default::default()
+    });
+    let cluster_id = create_cluster(&env, &mock, "spot").await;
+    spawn(&env.pool, &cluster_id, true, 0, false).await.unwrap();
+
+    watch_until_with(
+        &env,
+        &cluster_id,
+        true,
+        "the incident to close",
+        async || {
+            RecoveryIncident::fetch_all_by_cluster_id(&env.pool, &cluster_id)
+                .await
+                .unwrap()
+                .iter()
+                .any(|i| i.finished_at.is_some())
+        },
+    )
+    .await;
+
+    // The replacement went up on a fresh address while the noticed node still
+    // ran, and the noticed node left the cluster once it died.
+    let cluster = Cluster::fetch_by_id(&env.pool, &cluster_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(cluster.state, ClusterState::Running);
+    let nodes = cluster.get_nodes(&env.pool).await.unwrap();
+    let ips: Vec<_> = nodes.iter().map(|n| n.private_ip.as_deref()).collect();
+    assert_eq!(ips, [Some("10.0.0.10"), Some("10.0.0.12")]);
+    assert_eq!(nodes[1].instance_type, "mock.large");
+    assert_eq!(mock.state().instances.len(), 3);
+    assert!(mock.state().live_instance(&cluster_id, 1).is_none());
+
+    // Retiring a node does not renumber the others: spawning again finds the
+    // replacement's instance under its own index instead of launching anew.
+    let indices: Vec<_> = nodes.iter().map(|n| n.node_index).collect();
+    assert_eq!(indices, [0, 2]);
+    mock.spawn_cluster(&env.pool, cluster, nodes).await.unwrap();
+    assert_eq!(mock.state().instances.len(), 3);
+
+    let events = ClusterEvent::fetch_by_cluster_id(&env.pool, &cluster_id, None)
+        .await
+        .unwrap();
+    let position = |event_type: ClusterEventType| {
+        events
+            .iter()
+            .rposition(|e| e.event_type == event_type)
+            .unwrap()
+    };
+    assert!(position(ClusterEventType::NodeLaunched) < position(ClusterEventType::NodeFailed));
+    assert!(position(ClusterEventType::NodeFailed) < position(ClusterEventType::NodeRetired));
+
+    // The job was back before the dead instance let go of its interface.
+    let incidents = RecoveryIncident::fetch_all_by_cluster_id(&env.pool, &cluster_id)
+        .await
+        .unwrap();
+    assert_eq!(incidents.len(), 1);
+    assert_eq!(incidents[0].mode, "proactive");
+    assert_eq!(incidents[0].outcome, "recovered");
+    assert!(incidents[0].notice_received_at.is_some());
+    assert!(incidents[0].enis_released_at.is_none());
+}
+
+#[tokio::test]
+async fn proactive_watch_restores_a_noticed_head_node_in_place() {
+    let env = TestEnv::new().await;
+    let mock = MockInterface::new(MockScript {
+        spot_notices: vec![(1, 0)],
+        instance_deaths: vec![(3, 0)],
+        eni_release_delay: Duration::from_millis(20),
+        ..Default::default()
+    });
+    let cluster_id = create_cluster(&env, &mock, "spot").await;
+    spawn(&env.pool, &cluster_id, true, 0, false).await.unwrap();
+
+    watch_until_with(
+        &env,
+        &cluster_id,
+        true,
+        "the incident to close",
+        async || {
+            RecoveryIncident::fetch_all_by_cluster_id(&env.pool, &cluster_id)
+                .await
+                .unwrap()
+                .iter()
+                .any(|i| i.finished_at.is_some())
+        },
+    )
+    .await;
+
+    // No replacement was launched for the head: it was restored into slot 0,
+    // public address included, once it died.
+    let cluster = Cluster::fetch_by_id(&env.pool, &cluster_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(cluster.state, ClusterState::Running);
+    let nodes = cluster.get_nodes(&env.pool).await.unwrap();
+    let indices: Vec<_> = nodes.iter().map(|n| n.node_index).collect();
+    assert_eq!(indices, [0, 1]);
+    assert!(nodes[0].public_ip.is_some());
+    assert_eq!(mock.state().instances.len(), 3);
+    assert!(mock.state().live_instance(&cluster_id, 0).is_some());
+
+    let events = ClusterEvent::fetch_by_cluster_id(&env.pool, &cluster_id, None)
+        .await
+        .unwrap();
+    assert!(
+        !events
+            .iter()
+            .any(|e| e.event_type == ClusterEventType::NodeRetired)
+    );
+}
+
+#[tokio::test]
+async fn failing_health_probe_restores_a_running_node() {
+    let env = TestEnv::new().await;
+    // The shared filesystem goes stale on node 1 for two passes.
+    let stale_checks = Arc::new(AtomicUsize::new(0));
+    let mock = MockInterface::new(MockScript::default()).with_command_handler({
+        let stale_checks = stale_checks.clone();
+        move |ip, script| {
+            if script.contains("mountpoint -q '/shared'") {
+                if ip == "10.0.0.11" && stale_checks.fetch_add(1, SeqCst) < 2 {
+                    return Ok("missing\n".to_string());
+                }
+                return Ok("mounted\n".to_string());
+            }
+            if script.contains("pgrep -x solver") {
+                return Ok("exit=0\n".to_string());
+            }
+            Ok(String::new())
+        }
+    });
+    let probes = "health_probes:
+  - kind: mountpoint
+    path: /shared
+    failure_threshold: 2
+  - kind: command
+    command: pgrep -x solver
+";
+    let cluster_id = create_cluster_with(&env, &mock, "on-demand", probes).await;
+    spawn(&env.pool, &cluster_id, true, 0, false).await.unwrap();
+
+    watch_until(&env, &cluster_id, "the restore to finish", async || {
+        ClusterEvent::fetch_by_cluster_id(&env.pool, &cluster_id, None)
+            .await
+            .unwrap()
+            .iter()
+            .any(|e| e.event_type == ClusterEventType::RestoreFinished)
+    })
+    .await;
+
+    // The instance was still running, so it was terminated and replaced.
+    assert_eq!(mock.state().instances.len(), 3);
+    assert!(mock.state().live_instance(&cluster_id, 1).is_some());
+    let events = ClusterEvent::fetch_by_cluster_id(&env.pool, &cluster_id, None)
+        .await
+        .unwrap();
+    let failed: Vec<_> = events
+        .iter()
+        .filter(|e| e.event_type == ClusterEventType::NodeFailed)
+        .collect();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].details_json()["private_ip"], "10.0.0.11");
+    assert_eq!(
+        failed[0].details_json()["reason"],
+        "'/shared' is not mounted or not responding"
+    );
+}
+
+#[tokio::test]
+async fn slurm_is_configured_on_spawn_and_again_after_a_restore() {
+    let env = TestEnv::new().await;
+    let mock = MockInterface::new(MockScript {
+        instance_deaths: vec![(1, 1)],
+        ..Default::default()
+    })
+    .with_command_handler(|_, script| {
+        let answer = match script {
+            "nproc" => "4",
+            _ if script.contains("base64 -w0") => "a2V5",
+            _ => "",
+        };
+        Ok(answer.to_string())
+    });
+    let cluster_id = create_cluster_with(&env, &mock, "spot", "scheduler: slurm\n").await;
+    spawn(&env.pool, &cluster_id, true, 0, false).await.unwrap();
+
+    let restarts = |daemon: &str| {
+        mock.state()
+            .commands
+            .iter()
+            .filter(|(_, script)| script.contains(&format!("systemctl restart {}", daemon)))
+            .map(|(ip, _)| ip.clone())
+            .collect::<Vec<_>>()
+    };
+    // The controller runs on the head node, slurmd everywhere.
+    assert_eq!(restarts("slurmctld"), ["10.0.0.10"]);
+    assert_eq!(restarts("slurmd"), ["10.0.0.10", "10.0.0.11"]);
+    let (_, setup) = mock
+        .state()
+        .commands
+        .iter()
+        .find(|(ip, script)| ip == "10.0.0.11" && script.contains("slurm.conf"))
+        .cloned()
+        .unwrap();
+    assert!(setup.contains("NodeName=hpcac-10-0-0-10 NodeAddr=10.0.0.10 CPUs=4"));
+    assert!(setup.contains("NodeName=hpcac-10-0-0-11 NodeAddr=10.0.0.11 CPUs=4"));
+    assert!(setup.contains("SLURMD_OPTIONS=\"-N hpcac-10-0-0-11\""));
+
+    // The replacement keeps its node's address and rejoins under its name.
+    watch_until(&env, &cluster_id, "the restore", async || {
+        restarts("slurmctld").len() == 2
+    })
+    .await;
+    assert_eq!(restarts("slurmd").len(), 4);
+}
+
+#[tokio::test]
+async fn spawn_fails_when_no_capacity_is_left() {
+    let env = TestEnv::new().await;
+    let mock = MockInterface::new(MockScript {
+        capacity_errors: HashMap::from([("mock.small".to_string(), 1)]),
+        ..Default::default()
+    });
+    let cluster_id = create_cluster(&env, &mock, "on-demand").await;
+
+    // A first spawn never substitutes types, so the only candidate is the
+    // declared one.
+    let error = spawn(&env.pool, &cluster_id, true, 0, false)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("InsufficientInstanceCapacity"));
+    assert!(mock.state().instances.is_empty());
+
+    // The error was consumed; a second attempt launches both nodes.
+    spawn(&env.pool, &cluster_id, true, 0, false).await.unwrap();
+    assert_eq!(mock.state().instances.len(), 2);
+}
+
+#[tokio::test]
+async fn spawn_fails_when_an_init_command_fails() {
+    let env = TestEnv::new().await;
+    let mock = MockInterface::new(MockScript::default()).with_command_handler(|ip, script| {
+        if ip == "10.0.0.11" && script.contains("echo ready") {
+            anyhow::bail!("Command failed with exit code 1")
+        }
+        Ok(String::new())
+    });
+    let cluster_id = create_cluster(&env, &mock, "on-demand").await;
+
+    let error = spawn(&env.pool, &cluster_id, true, 0, false)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("exit code 1"));
+    let cluster = Cluster::fetch_by_id(&env.pool, &cluster_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(cluster.state, ClusterState::Failed);
+}
+
+#[tokio::test]
+async fn resumed_spawn_skips_completed_steps() {
+    let env = TestEnv::new().await;
+    let failed_once = Arc::new(AtomicBool::new(false));
+    let mock = MockInterface::new(MockScript::default()).with_command_handler({
+        let failed_once = failed_once.clone();
+        move |ip, script| {
+            if ip == "10.0.0.11" && script.contains("echo ready") && !failed_once.swap(true, SeqCst)
+            {
+                anyhow::bail!("Command failed with exit code 1")
+            }
+            Ok(String::new())
+        }
+    });
+    let cluster_id = create_cluster(&env, &mock, "on-demand").await;
+
+    spawn(&env.pool, &cluster_id, true, 0, false)
+        .await
+        .unwrap_err();
+    spawn(&env.pool, &cluster_id, true, 0, true).await.unwrap();
+
+    let cluster = Cluster::fetch_by_id(&env.pool, &cluster_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(cluster.state, ClusterState::Running);
+    assert_eq!(mock.state().instances.len(), 2);
+
+    // Node 0 finished its init before the failure and is left alone; only the
+    // node that failed runs it again.
+    let init_runs = |ip: &str| {
+        mock.state()
+            .commands
+            .iter()
+            .filter(|(cmd_ip, script)| cmd_ip == ip && script.contains("echo ready"))
+            .count()
+    };
+    assert_eq!(init_runs("10.0.0.10"), 1);
+    assert_eq!(init_runs("10.0.0.11"), 2);
+
+    let launches = ClusterEvent::fetch_by_cluster_id(&env.pool, &cluster_id, None)
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|e| e.event_type == ClusterEventType::NodeLaunched)
+        .count();
+    assert_eq!(launches, 2);
+
+    // Nothing is left to resume once the cluster is up.
+    let error = spawn(&env.pool, &cluster_id, true, 0, true)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("Run 'cluster terminate' first"));
+}
+
+#[tokio::test]
+async fn watch_takes_the_lock_and_answers_on_its_control_socket() {
+    let env = TestEnv::new().await;
+    let mock = MockInterface::new(MockScript::default());
+    let cluster_id = create_cluster(&env, &mock, "on-demand").await;
+    spawn(&env.pool, &cluster_id, true, 0, false).await.unwrap();
+
+    // A live watcher in another process keeps this one out.
+    let mut other = std::process::Command::new("sleep")
+        .arg("60")
+        .spawn()
+        .unwrap();
+    let lock = WatchLock {
+        cluster_id: cluster_id.clone(),
+        pid: other.id() as i64,
+        socket_path: None,
+        log_path: None,
+        started_at: chrono::Utc::now().naive_utc(),
+    };
+    assert!(lock.acquire(&env.pool).await.unwrap());
+    let control = WatchControl::new(&cluster_id, None, None);
+    let err = watch(&env.pool, &cluster_id, 0, None, false, false, &control)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("already watched"), "{}", err);
+
+    // Once it is gone, its lock is taken over.
+    other.kill().unwrap();
+    other.wait().unwrap();
+    let socket_path = env.dir.join("watch.sock").to_string_lossy().to_string();
+    let control = WatchControl::new(&cluster_id, Some(socket_path.clone()), None);
+    let watcher = watch(&env.pool, &cluster_id, 0, None, false, false, &control);
+    let client = async {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let status = loop {
+            if let Ok(status) = watch_request(&socket_path, "status").await
+                && status["last_check"].is_string()
+            {
+                break status;
+            }
+            assert!(
+                Instant::now() < deadline,
+                "timed out waiting for a health check"
+            );
+            sleep(Duration::from_millis(10)).await;
+        };
+        assert_eq!(status["pid"], std::process::id());
+        assert_eq!(status["last_check"], "all nodes healthy");
+        assert_eq!(status["tasks"], "none");
+        let reply = watch_request(&socket_path, "stop").await.unwrap();
+        assert_eq!(reply["stopping"], true);
+    };
+    let (result, _) = tokio::join!(watcher, client);
+    result.unwrap();
+
+    let lock = WatchLock::fetch_by_cluster_id(&env.pool, &cluster_id)
+        .await
+        .unwrap();
+    assert!(lock.is_none());
+    assert!(!std::path::Path::new(&socket_path).exists());
+}
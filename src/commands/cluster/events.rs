@@ -0,0 +1,92 @@
+use crate::database::models::{Cluster, ClusterEvent};
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use sqlx::sqlite::SqlitePool;
+use tabled::{Table, Tabled, settings::Style};
+
+#[derive(Tabled)]
+struct ClusterEventDisplay {
+    #[tabled(rename = "Time (UTC)")]
+    created_at: String,
+    #[tabled(rename = "Event")]
+    event_type: String,
+    #[tabled(rename = "Node")]
+    node_id: String,
+    #[tabled(rename = "Message")]
+    message: String,
+}
+
+/// Accepts an RFC 3339 timestamp, or a UTC date and time as `YYYY-MM-DD HH:MM[:SS]`
+/// or `YYYY-MM-DD`.
+fn parse_since(since: &str) -> Result<NaiveDateTime> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(since) {
+        return Ok(datetime.naive_utc());
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(since, format) {
+            return Ok(datetime);
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(since, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap());
+    }
+    anyhow::bail!(
+        "Invalid --since '{}': expected RFC 3339 or 'YYYY-MM-DD[ HH:MM[:SS]]' (UTC)",
+        since
+    )
+}
+
+pub async fn events(
+    pool: &SqlitePool,
+    cluster_id: &str,
+    since: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    let since = since.map(parse_since).transpose()?;
+
+    if Cluster::fetch_by_id(pool, cluster_id).await?.is_none() {
+        anyhow::bail!("Cluster (id='{}') not found", cluster_id);
+    }
+
+    let events = ClusterEvent::fetch_by_cluster_id(pool, cluster_id, since).await?;
+
+    // JSON goes straight to stdout so it can be piped into other tools.
+    if json {
+        let entries: Vec<serde_json::Value> = events
+            .iter()
+            .map(|event| {
+                serde_json::json!({
+                    "id": event.id,
+                    "created_at": event.created_at.and_utc().to_rfc3339(),
+                    "event_type": event.event_type,
+                    "node_id": event.node_id,
+                    "message": event.message,
+                    "details": event.details_json(),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    if events.is_empty() {
+        tracing::info!("No events recorded for Cluster '{}'.", cluster_id);
+        return Ok(());
+    }
+
+    let table_rows: Vec<ClusterEventDisplay> = events
+        .into_iter()
+        .map(|event| ClusterEventDisplay {
+            created_at: event.created_at.format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+            event_type: event.event_type.to_string(),
+            node_id: event.node_id.unwrap_or_default(),
+            message: event.message,
+        })
+        .collect();
+
+    let mut table = Table::new(table_rows);
+    table.with(Style::rounded());
+    tracing::info!("\nEvents of Cluster '{}':\n{}", cluster_id, table);
+    Ok(())
+}
@@ -1,19 +1,32 @@
+mod attach;
+mod cost;
 mod create;
 mod delete;
+#[cfg(test)]
+mod e2e_tests;
+mod events;
 mod list;
+mod recovery_report;
 mod simulate_spot_notice;
 mod spawn;
+mod task_runs;
 mod tasks;
 mod terminate;
 mod test_failure;
 mod watch;
+mod watch_control;
 
+pub use attach::*;
 pub use create::*;
 pub use delete::*;
+pub use events::*;
 pub use list::*;
+pub use recovery_report::*;
 pub use simulate_spot_notice::*;
 pub use spawn::*;
+pub use task_runs::*;
 pub use tasks::*;
 pub use terminate::*;
 pub use test_failure::*;
 pub use watch::*;
+pub use watch_control::*;
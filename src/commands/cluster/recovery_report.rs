@@ -0,0 +1,157 @@
+use crate::database::models::{Cluster, RecoveryIncident, RecoveryPhase};
+
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use sqlx::sqlite::SqlitePool;
+
+fn format_timestamp(timestamp: Option<NaiveDateTime>) -> Option<String> {
+    timestamp.map(|t| t.and_utc().to_rfc3339())
+}
+
+/// Phases with a duration worth reporting. The first phase has nothing before it.
+fn timed_phases() -> impl Iterator<Item = RecoveryPhase> {
+    RecoveryPhase::ALL.into_iter().skip(1)
+}
+
+/// Quotes a CSV field when it contains a delimiter, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn to_csv(incidents: &[RecoveryIncident]) -> String {
+    let mut header: Vec<String> = ["id", "cluster_id", "mode", "failed_ips", "outcome", "error"]
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+    header.extend(RecoveryPhase::ALL.iter().map(|p| format!("{}_at", p)));
+    header.push("finished_at".to_string());
+    header.extend(timed_phases().map(|p| format!("{}_secs", p)));
+    header.push("total_secs".to_string());
+
+    let mut lines = vec![header.join(",")];
+    for incident in incidents {
+        let mut row: Vec<String> = vec![
+            incident.id.to_string(),
+            incident.cluster_id.clone(),
+            incident.mode.clone(),
+            incident.failed_ips().join(" "),
+            incident.outcome.clone(),
+            incident.error.clone().unwrap_or_default(),
+        ];
+        row.extend(
+            RecoveryPhase::ALL
+                .iter()
+                .map(|&p| format_timestamp(incident.reached_at(p)).unwrap_or_default()),
+        );
+        row.push(format_timestamp(incident.finished_at).unwrap_or_default());
+        let durations = incident.phase_durations();
+        row.extend(
+            durations
+                .iter()
+                .skip(1)
+                .map(|(_, secs)| secs.map(|s| format!("{:.3}", s)).unwrap_or_default()),
+        );
+        row.push(
+            incident
+                .total_duration()
+                .map(|s| format!("{:.3}", s))
+                .unwrap_or_default(),
+        );
+        lines.push(
+            row.iter()
+                .map(|v| csv_field(v))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+    }
+    lines.join("\n") + "\n"
+}
+
+fn to_json(incidents: &[RecoveryIncident]) -> Result<String> {
+    let entries: Vec<serde_json::Value> = incidents
+        .iter()
+        .map(|incident| {
+            let reached_at: serde_json::Map<String, serde_json::Value> = RecoveryPhase::ALL
+                .iter()
+                .map(|&p| {
+                    (
+                        p.to_string(),
+                        format_timestamp(incident.reached_at(p)).into(),
+                    )
+                })
+                .collect();
+            let durations: serde_json::Map<String, serde_json::Value> = incident
+                .phase_durations()
+                .into_iter()
+                .skip(1)
+                .map(|(p, secs)| (p.to_string(), secs.into()))
+                .collect();
+            serde_json::json!({
+                "id": incident.id,
+                "cluster_id": incident.cluster_id,
+                "mode": incident.mode,
+                "failed_ips": incident.failed_ips(),
+                "outcome": incident.outcome,
+                "error": incident.error,
+                "reached_at": reached_at,
+                "finished_at": format_timestamp(incident.finished_at),
+                "phase_secs": durations,
+                "total_secs": incident.total_duration(),
+            })
+        })
+        .collect();
+    Ok(serde_json::to_string_pretty(&entries)?)
+}
+
+pub async fn recovery_report(
+    pool: &SqlitePool,
+    cluster_id: &str,
+    json: bool,
+    output: Option<&str>,
+) -> Result<()> {
+    if Cluster::fetch_by_id(pool, cluster_id).await?.is_none() {
+        anyhow::bail!("Cluster (id='{}') not found", cluster_id);
+    }
+
+    let incidents = RecoveryIncident::fetch_all_by_cluster_id(pool, cluster_id).await?;
+    let report = if json {
+        to_json(&incidents)?
+    } else {
+        to_csv(&incidents)
+    };
+
+    match output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, &report) {
+                tracing::error!("{:?}", e);
+                anyhow::bail!("Failed to write recovery report to '{}': {}", path, e);
+            }
+            tracing::info!(
+                "Wrote {} recovery incident(s) of Cluster '{}' to '{}'",
+                incidents.len(),
+                cluster_id,
+                path
+            );
+        }
+        // Straight to stdout so the report can be piped into other tools.
+        None => print!("{}", report),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields_are_quoted_only_when_needed() {
+        assert_eq!(csv_field("recovered"), "recovered");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"no\""), "\"say \"\"no\"\"\"");
+    }
+}
@@ -1,5 +1,5 @@
 use crate::database::models::{Cluster, ClusterState, ProviderConfig};
-use crate::integrations::providers::aws::AwsInterface;
+use crate::integrations::cloud_interface::{CloudProvider, SpotInterruptionQueue};
 use crate::utils;
 
 use anyhow::Result;
@@ -51,13 +51,12 @@ pub async fn simulate_spot_notice(
             }
         };
 
-    let config_vars = provider_config.get_config_vars(pool).await?;
-    let provider_id = provider_config.provider_id.clone();
-    let cloud_interface = match provider_id.as_str() {
-        "aws" => AwsInterface { config_vars },
-        _ => {
-            anyhow::bail!("Provider '{}' is currently not supported.", &provider_id)
-        }
+    let cloud_interface = CloudProvider::from_provider_config(pool, &provider_config).await?;
+    let Some(spot_queue) = cloud_interface.spot_interruption_queue() else {
+        anyhow::bail!(
+            "Provider '{}' does not deliver spot interruption notices.",
+            provider_config.provider_id
+        )
     };
 
     if !(utils::user_confirmation(
@@ -67,7 +66,7 @@ pub async fn simulate_spot_notice(
         return Ok(());
     }
 
-    cloud_interface
+    spot_queue
         .send_simulated_spot_interruption(&cluster, node_private_ip)
         .await?;
 
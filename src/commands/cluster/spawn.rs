@@ -1,14 +1,19 @@
-use crate::database::models::{Cluster, ProviderConfig};
-use crate::integrations::{
-    cloud_interface::{CloudInfoProvider, CloudResourceManager},
-    providers::aws::AwsInterface,
+use crate::database::models::{Cluster, ClusterState, ProviderConfig, SpawnProgress};
+use crate::integrations::cloud_interface::{
+    BlockStoragePricing, CloudInfoProvider, CloudProvider, CloudResourceManager, SpotPricing,
 };
 use crate::utils;
 
 use anyhow::Result;
 use sqlx::sqlite::SqlitePool;
 
-pub async fn spawn(pool: &SqlitePool, cluster_id: &str, skip_confirmation: bool, retry: u32) -> Result<()> {
+pub async fn spawn(
+    pool: &SqlitePool,
+    cluster_id: &str,
+    skip_confirmation: bool,
+    retry: u32,
+    resume: bool,
+) -> Result<()> {
     let cluster = match Cluster::fetch_by_id(pool, cluster_id).await? {
         Some(cluster) => cluster,
         None => {
@@ -27,21 +32,10 @@ pub async fn spawn(pool: &SqlitePool, cluster_id: &str, skip_confirmation: bool,
             }
         };
 
-    let config_vars = provider_config.get_config_vars(pool).await?;
-    let provider_id = provider_config.provider_id.clone();
-    let cloud_interface = match provider_id.as_str() {
-        "aws" => AwsInterface { config_vars },
-        _ => {
-            anyhow::bail!(
-                "Provider (id='{}') is currently not supported.",
-                &provider_id
-            )
-        }
-    };
+    let cloud_interface = CloudProvider::from_provider_config(pool, &provider_config).await?;
 
     match cluster.state {
-        crate::database::models::ClusterState::Running
-        | crate::database::models::ClusterState::Terminating => {
+        ClusterState::Running | ClusterState::Terminating => {
             anyhow::bail!(
                 "Cluster '{}' is currently {}. Run 'cluster terminate' first.",
                 cluster.display_name,
@@ -51,6 +45,17 @@ pub async fn spawn(pool: &SqlitePool, cluster_id: &str, skip_confirmation: bool,
         _ => {}
     }
 
+    // Only a spawn that never finished has progress worth resuming. Any other
+    // state means the recorded resources were torn down since, or never existed.
+    let interrupted = matches!(cluster.state, ClusterState::Spawning | ClusterState::Failed);
+    if resume && !interrupted {
+        anyhow::bail!(
+            "Cluster '{}' is {}, there is no interrupted spawn to resume. Run 'cluster spawn' without --resume.",
+            cluster.display_name,
+            cluster.state
+        );
+    }
+
     let nodes = cluster.get_nodes(pool).await?;
     cluster.print_details(pool).await?;
 
@@ -77,22 +82,27 @@ pub async fn spawn(pool: &SqlitePool, cluster_id: &str, skip_confirmation: bool,
         .collect::<std::collections::HashSet<_>>()
         .into_iter()
         .collect();
-    let live_spot_prices = if !spot_instance_types.is_empty() {
-        cloud_interface
-            .fetch_spot_prices(&cluster.region, &spot_instance_types, &cluster.availability_zone)
-            .await?
-    } else {
-        std::collections::HashMap::new()
+    let live_spot_prices = match cloud_interface.spot_pricing() {
+        Some(spot_pricing) if !spot_instance_types.is_empty() => {
+            spot_pricing
+                .fetch_spot_prices(&cluster.region, &spot_instance_types, &cluster.availability_zone)
+                .await?
+        }
+        _ => std::collections::HashMap::new(),
     };
 
+    // Providers without separately billed block storage include the disk and
+    // the public IPv4 address in the instance price.
     let unique_volume_types: std::collections::HashSet<String> =
         nodes.iter().map(|n| n.root_volume_type.clone()).collect();
     let mut ebs_price_cache = std::collections::HashMap::new();
-    for vt in &unique_volume_types {
-        ebs_price_cache.insert(
-            vt.clone(),
-            cloud_interface.fetch_ebs_pricing(&cluster.region, vt).await?,
-        );
+    if let Some(storage_pricing) = cloud_interface.block_storage_pricing() {
+        for vt in &unique_volume_types {
+            ebs_price_cache.insert(
+                vt.clone(),
+                storage_pricing.fetch_ebs_pricing(&cluster.region, vt).await?,
+            );
+        }
     }
 
     const GP3_THROUGHPUT_BASELINE_MBS: f64 = 125.0;
@@ -111,7 +121,9 @@ pub async fn spawn(pool: &SqlitePool, cluster_id: &str, skip_confirmation: bool,
         } else {
             *live_instance_prices.get(&node.instance_type).unwrap_or(&0.0)
         };
-        let ebs = ebs_price_cache.get(&node.root_volume_type).unwrap();
+        let Some(ebs) = ebs_price_cache.get(&node.root_volume_type) else {
+            return instance_cost;
+        };
         let storage_cost = node.root_volume_gb as f64 * ebs.storage_per_gb_month / HOURS_PER_MONTH;
         let extra_throughput = (GP3_PROVISIONED_THROUGHPUT_MBS - GP3_THROUGHPUT_BASELINE_MBS).max(0.0);
         let throughput_cost = extra_throughput * ebs.throughput_per_mbs_month / HOURS_PER_MONTH;
@@ -136,6 +148,31 @@ pub async fn spawn(pool: &SqlitePool, cluster_id: &str, skip_confirmation: bool,
         return Ok(());
     }
 
+    if resume {
+        let progress = SpawnProgress::load(pool, cluster_id).await?;
+        if progress.is_empty() {
+            tracing::warn!(
+                "No completed steps recorded for Cluster '{}', resuming from the start",
+                cluster.display_name
+            );
+        } else {
+            tracing::info!(
+                "Resuming the spawn of Cluster '{}': {} step(s) already completed",
+                cluster.display_name,
+                progress.len()
+            );
+        }
+    } else {
+        if interrupted {
+            tracing::warn!(
+                "Cluster '{}' is {} from an earlier spawn, starting over. Use --resume to continue it instead.",
+                cluster.display_name,
+                cluster.state
+            );
+        }
+        SpawnProgress::clear(pool, cluster_id).await?;
+    }
+
     let mut attempt = 0u32;
     loop {
         // Re-fetch cluster and nodes each attempt: spawn_cluster takes ownership
@@ -148,8 +185,8 @@ pub async fn spawn(pool: &SqlitePool, cluster_id: &str, skip_confirmation: bool,
             Err(e) => {
                 let msg = e.to_string();
                 // Transient conditions worth another pass. Re-running spawn is cheap
-                // because every resource step is idempotent, so a retry skips
-                // straight back to whatever failed.
+                // because completed steps are recorded, so a retry skips straight
+                // back to whatever failed.
                 //
                 // The SSH cases matter as much as the capacity ones: sshd on a fresh
                 // instance is not ready the moment the API reports the instance as
@@ -173,6 +210,9 @@ pub async fn spawn(pool: &SqlitePool, cluster_id: &str, skip_confirmation: bool,
                     );
                     tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
                 } else {
+                    // Keep the progress: 'cluster spawn --resume' picks up from here.
+                    let cluster = Cluster::fetch_by_id(pool, cluster_id).await?.unwrap();
+                    cluster.update_state(pool, ClusterState::Failed).await?;
                     return Err(e);
                 }
             }
@@ -0,0 +1,117 @@
+use crate::database::models::{Cluster, TaskRun};
+
+use anyhow::Result;
+use sqlx::sqlite::SqlitePool;
+use std::collections::BTreeMap;
+use tabled::{Table, Tabled, settings::Style};
+
+#[derive(Tabled)]
+struct TaskRunDisplay {
+    #[tabled(rename = "ID")]
+    id: i64,
+    #[tabled(rename = "Started (UTC)")]
+    started_at: String,
+    #[tabled(rename = "Task")]
+    task_name: String,
+    #[tabled(rename = "Run")]
+    run_index: i64,
+    #[tabled(rename = "Params")]
+    params: String,
+    #[tabled(rename = "Attempt")]
+    attempt: i64,
+    #[tabled(rename = "Status")]
+    status: String,
+    #[tabled(rename = "Exit Code")]
+    exit_code: String,
+    #[tabled(rename = "Wall Time")]
+    wall_time: String,
+}
+
+/// Renders a run's matrix parameters as `k=4, storage=disk`.
+fn format_params(params: Option<&str>) -> String {
+    let params: BTreeMap<String, String> = params
+        .and_then(|p| serde_json::from_str(p).ok())
+        .unwrap_or_default();
+    params
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn format_wall_time(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}",
+        secs / 3600,
+        (secs % 3600) / 60,
+        secs % 60
+    )
+}
+
+pub async fn task_runs(pool: &SqlitePool, cluster_id: &str) -> Result<()> {
+    if Cluster::fetch_by_id(pool, cluster_id).await?.is_none() {
+        anyhow::bail!("Cluster (id='{}') not found", cluster_id);
+    }
+
+    let runs = TaskRun::fetch_by_cluster_id(pool, cluster_id).await?;
+    if runs.is_empty() {
+        tracing::info!("No task runs recorded for Cluster '{}'.", cluster_id);
+        return Ok(());
+    }
+
+    let table_rows: Vec<TaskRunDisplay> = runs
+        .iter()
+        .map(|run| TaskRunDisplay {
+            id: run.id,
+            started_at: run.started_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            task_name: run.task_name.clone(),
+            run_index: run.run_index,
+            params: format_params(run.params.as_deref()),
+            attempt: run.attempt,
+            status: run.status.clone(),
+            exit_code: run.exit_code.map(|c| c.to_string()).unwrap_or_default(),
+            wall_time: run.wall_secs.map(format_wall_time).unwrap_or_default(),
+        })
+        .collect();
+
+    let mut table = Table::new(table_rows);
+    table.with(Style::rounded());
+    tracing::info!("\nTask runs of Cluster '{}':\n{}", cluster_id, table);
+
+    for run in runs
+        .iter()
+        .filter(|run| run.status == "failed" || run.status == "timeout")
+    {
+        tracing::info!(
+            "\nTask run {} ('{}' run {}, attempt {}) {}. Full log: {}\n{}",
+            run.id,
+            run.task_name,
+            run.run_index,
+            run.attempt,
+            run.status,
+            run.log_path.as_deref().unwrap_or("not downloaded"),
+            run.log_tail
+                .as_deref()
+                .unwrap_or("(no log recorded)")
+                .trim_end()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn params_and_wall_times_are_readable() {
+        assert_eq!(format_params(None), "");
+        assert_eq!(
+            format_params(Some(r#"{"storage":"disk","k":"4"}"#)),
+            "k=4, storage=disk"
+        );
+        assert_eq!(format_wall_time(3725.4), "01:02:05");
+    }
+}
@@ -1,20 +1,54 @@
-use crate::database::models::{Cluster, ClusterState, TaskRun};
+use crate::database::models::{
+    Cluster, ClusterState, Node, TaskRun, TaskRunJob, TaskRunKey, TaskRunOutput, slot_order,
+};
+use crate::utils::launcher::{CheckpointSignal, Launcher};
+use crate::utils::slurm;
 use crate::utils::{self, ssh::SshSession};
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use sqlx::sqlite::SqlitePool;
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use tokio::task::JoinSet;
 use tokio::time::Duration;
 
 const MPI_HOSTFILE_PATH: &str = "/tmp/hpcac_hostfile";
+/// Where every node mounts the cluster's shared filesystem.
+const SHARED_DIR: &str = "/shared";
+/// Lines of a run's log kept in the database.
+const LOG_TAIL_LINES: usize = 50;
 
+/// Nodes an upload is sent to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum UploadTarget {
+    /// The head node only.
+    #[default]
+    Head,
+    /// Once, through the head node, to a path under `SHARED_DIR`, which every
+    /// node mounts.
+    Shared,
+    /// Every node, workers through the head node, for node-local data.
+    AllNodes,
+}
+
+/// A local file, directory or glob to upload before the first task runs.
 #[derive(Debug, Deserialize, Serialize)]
 struct UploadSpec {
     local: String,
     remote: String,
+    #[serde(default)]
+    to: UploadTarget,
+}
+
+/// A path collected from every node into `results_local_dir/node_<slot>/`.
+#[derive(Debug, Deserialize, Serialize)]
+struct DownloadSpec {
+    remote: String,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -24,18 +58,396 @@ struct TasksYaml {
     results_local_dir: String,
     #[serde(default)]
     uploads: Vec<UploadSpec>,
+    #[serde(default)]
+    downloads: Vec<DownloadSpec>,
+    /// Overrides the cluster's for the runs of this file.
+    checkpoint_signal: Option<CheckpointSignal>,
     tasks: Vec<TaskYaml>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// Nodes a task runs on: a number of whichever nodes are free, or a list of
+/// slots into the cluster's nodes sorted by private IP (slot 0 is the head).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+enum NodeSelection {
+    Count(usize),
+    Slots(Vec<usize>),
+}
+
+/// A value in a task's `matrix:`, exported to the script as text.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+enum MatrixValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl std::fmt::Display for MatrixValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MatrixValue::Bool(b) => write!(f, "{}", b),
+            MatrixValue::Int(i) => write!(f, "{}", i),
+            MatrixValue::Float(x) => write!(f, "{}", x),
+            MatrixValue::Text(t) => write!(f, "{}", t),
+        }
+    }
+}
+
+/// A task's time limit: seconds, or text with a unit ("90s", "30m", "2h").
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+enum TaskTimeout {
+    Secs(u64),
+    Text(String),
+}
+
+impl TaskTimeout {
+    fn duration(&self) -> Result<Duration> {
+        let secs = match self {
+            TaskTimeout::Secs(secs) => *secs,
+            TaskTimeout::Text(text) => {
+                let text = text.trim();
+                let unit_start = text
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(text.len());
+                let (number, unit) = text.split_at(unit_start);
+                let multiplier = match unit.trim() {
+                    "" | "s" => 1,
+                    "m" => 60,
+                    "h" => 3600,
+                    "d" => 86400,
+                    _ => anyhow::bail!("Invalid timeout '{}', expected e.g. 90s, 30m or 2h", text),
+                };
+                match number.parse::<u64>() {
+                    Ok(number) => number * multiplier,
+                    Err(_) => {
+                        anyhow::bail!("Invalid timeout '{}', expected e.g. 90s, 30m or 2h", text)
+                    }
+                }
+            }
+        };
+        if secs == 0 {
+            anyhow::bail!("Timeout must be greater than zero");
+        }
+        Ok(Duration::from_secs(secs))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 struct TaskYaml {
     name: String,
     mpi_slots_per_host: u32,
     repeat: u32,
     script: String,
+    #[serde(default)]
+    depends_on: Vec<String>,
+    /// Every node of the cluster when omitted.
+    #[serde(default)]
+    nodes: Option<NodeSelection>,
+    /// Parameter name -> values. The task repeats for every combination.
+    #[serde(default)]
+    matrix: BTreeMap<String, Vec<MatrixValue>>,
+    /// Limit for each attempt of a run. None waits for as long as it takes.
+    #[serde(default)]
+    timeout: Option<TaskTimeout>,
+    /// Times a failed or timed-out run is attempted again.
+    #[serde(default)]
+    retries: u32,
+    /// When a run fails for good, carry on with the task's other runs and with
+    /// the tasks that don't depend on it, instead of stopping the pipeline.
+    #[serde(default)]
+    continue_on_failure: bool,
+    /// Decides the hostfile syntax and the `HPCAC_MPIRUN` prefix.
+    #[serde(default)]
+    launcher: Launcher,
+}
+
+impl TaskYaml {
+    /// (run_index, params) of every run: `repeat` runs for each combination of
+    /// the matrix values, or just `repeat` runs without params when there is no
+    /// matrix.
+    fn runs(&self) -> Vec<(i64, BTreeMap<String, String>)> {
+        let mut combinations = vec![BTreeMap::new()];
+        for (name, values) in &self.matrix {
+            combinations = combinations
+                .into_iter()
+                .flat_map(|combination| {
+                    values.iter().map(move |value| {
+                        let mut combination = combination.clone();
+                        combination.insert(name.clone(), value.to_string());
+                        combination
+                    })
+                })
+                .collect();
+        }
+        combinations
+            .into_iter()
+            .flat_map(|params| (1..=self.repeat as i64).map(move |run| (run, params.clone())))
+            .collect()
+    }
+}
+
+/// Environment variable a matrix parameter is exported as, e.g. `k` as
+/// `HPCAC_PARAM_K`.
+fn param_env_var(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("HPCAC_PARAM_{}", name)
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// e.g. "run 2/3 [k=4, storage=disk]"
+fn describe_run(task: &TaskYaml, run: i64, params: &BTreeMap<String, String>) -> String {
+    let mut description = format!("run {}/{}", run, task.repeat);
+    if !params.is_empty() {
+        let params: Vec<String> = params.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        description.push_str(&format!(" [{}]", params.join(", ")));
+    }
+    description
+}
+
+/// State shared by the tasks of a pipeline while they run.
+struct Pipeline {
+    pool: SqlitePool,
+    head_ssh: SshSession,
+    /// A session to every node, by slot.
+    node_ssh: Vec<SshSession>,
+    cluster_id: String,
+    tasks_file_hash: String,
+    results_local_dir: String,
+    /// Stream the log of every run while it goes.
+    follow: bool,
+    /// The tasks file's `checkpoint_signal`, as JSON.
+    checkpoint_signal: Option<String>,
+    /// Runs a resumed pipeline skips.
+    succeeded: HashSet<TaskRunKey>,
+    /// tmux sessions currently being waited on, so Ctrl+C can kill them.
+    active_sessions: Mutex<HashSet<String>>,
+}
+
+/// Rejects a pipeline that cannot run on `node_count` nodes: repeated task
+/// names, unknown or circular dependencies, and node selections that don't fit.
+fn validate_tasks(tasks: &[TaskYaml], node_count: usize) -> Result<()> {
+    let mut names: HashSet<&str> = HashSet::new();
+    for task in tasks {
+        if !names.insert(&task.name) {
+            anyhow::bail!("Task name '{}' is used more than once", task.name);
+        }
+    }
+
+    for task in tasks {
+        if let Some(dependency) = task.depends_on.iter().find(|d| !names.contains(d.as_str())) {
+            anyhow::bail!(
+                "Task '{}' depends on unknown task '{}'",
+                task.name,
+                dependency
+            );
+        }
+        match &task.nodes {
+            Some(NodeSelection::Count(count)) if *count == 0 || *count > node_count => {
+                anyhow::bail!(
+                    "Task '{}' asks for {} node(s), but the cluster has {}",
+                    task.name,
+                    count,
+                    node_count
+                );
+            }
+            Some(NodeSelection::Slots(slots)) => {
+                if slots.is_empty() {
+                    anyhow::bail!("Task '{}' lists no node slots", task.name);
+                }
+                if let Some(slot) = slots.iter().find(|&&slot| slot >= node_count) {
+                    anyhow::bail!(
+                        "Task '{}' uses node slot {}, but the cluster only has slots 0 to {}",
+                        task.name,
+                        slot,
+                        node_count - 1
+                    );
+                }
+                if slots.iter().collect::<HashSet<_>>().len() != slots.len() {
+                    anyhow::bail!("Task '{}' lists a node slot more than once", task.name);
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(timeout) = &task.timeout
+            && let Err(e) = timeout.duration()
+        {
+            anyhow::bail!("Task '{}': {}", task.name, e);
+        }
+
+        let mut env_vars: HashSet<String> = HashSet::new();
+        for (name, values) in &task.matrix {
+            if values.is_empty() {
+                anyhow::bail!(
+                    "Task '{}' has no values for matrix key '{}'",
+                    task.name,
+                    name
+                );
+            }
+            if !env_vars.insert(param_env_var(name)) {
+                anyhow::bail!(
+                    "Task '{}' has matrix keys that are all exported as {}",
+                    task.name,
+                    param_env_var(name)
+                );
+            }
+        }
+    }
+
+    // Peel off tasks whose dependencies are all peeled; what is left is a cycle.
+    let mut ordered: HashSet<&str> = HashSet::new();
+    let mut remaining: Vec<&TaskYaml> = tasks.iter().collect();
+    while !remaining.is_empty() {
+        let before = remaining.len();
+        remaining.retain(|task| {
+            if task.depends_on.iter().all(|d| ordered.contains(d.as_str())) {
+                ordered.insert(&task.name);
+                false
+            } else {
+                true
+            }
+        });
+        if remaining.len() == before {
+            let names: Vec<&str> = remaining.iter().map(|t| t.name.as_str()).collect();
+            anyhow::bail!("Tasks {:?} depend on each other in a cycle", names);
+        }
+    }
+
+    Ok(())
+}
+
+/// The slots `selection` would run on, or None while any of them is busy.
+fn pick_nodes(
+    selection: Option<&NodeSelection>,
+    node_count: usize,
+    busy: &HashSet<usize>,
+) -> Option<Vec<usize>> {
+    let slots: Vec<usize> = match selection {
+        None => (0..node_count).collect(),
+        Some(NodeSelection::Slots(slots)) => slots.clone(),
+        Some(NodeSelection::Count(count)) => {
+            let free: Vec<usize> = (0..node_count)
+                .filter(|slot| !busy.contains(slot))
+                .take(*count)
+                .collect();
+            return (free.len() == *count).then_some(free);
+        }
+    };
+    slots
+        .iter()
+        .all(|slot| !busy.contains(slot))
+        .then_some(slots)
+}
+
+fn task_slug(task_name: &str) -> String {
+    task_name.replace(['-', ' '], "_")
+}
+
+/// Name of the tmux session running the `seq`th run of a task. Sessions are
+/// numbered across matrix combinations, so each run has its own.
+fn run_session_name(task_name: &str, seq: usize) -> String {
+    format!("hpcac_{}_run{}", task_slug(task_name), seq)
 }
 
-pub async fn tasks(pool: &SqlitePool, yaml_file_path: &str) -> Result<()> {
+/// Whether tmux session `session_name` runs a task, or a run of `task_name`
+/// when given.
+pub fn is_run_session(session_name: &str, task_name: Option<&str>) -> bool {
+    let Some((prefix, seq)) = session_name.rsplit_once("_run") else {
+        return false;
+    };
+    if seq.is_empty() || !seq.chars().all(|c| c.is_ascii_digit()) {
+        return false;
+    }
+    match task_name {
+        Some(name) => prefix == format!("hpcac_{}", task_slug(name)),
+        None => prefix.starts_with("hpcac_"),
+    }
+}
+
+/// The log `run_in_tmux` writes the session's output to.
+pub fn session_log(session_name: &str) -> String {
+    format!("/tmp/hpcac_{}.log", session_name)
+}
+
+/// The script, wrapper, log and exit-code files `run_in_tmux` leaves in /tmp.
+fn session_files(session_name: &str) -> String {
+    format!(
+        "/tmp/hpcac_{0}.sh /tmp/hpcac_{0}_wrapper.sh /tmp/hpcac_{0}.log /tmp/hpcac_{0}.exit",
+        session_name
+    )
+}
+
+/// What rsync is given for an upload: the local sources, the remote directory
+/// to create first and the destination. A directory's contents go into
+/// `remote`, files matched by a glob go into `remote` as a directory, and a
+/// single file is copied to `remote`.
+fn resolve_upload(upload: &UploadSpec) -> Result<(Vec<String>, String, String)> {
+    let local = utils::expand_tilde(&upload.local);
+    let remote = upload.remote.trim_end_matches('/').to_string();
+    if upload.to == UploadTarget::Shared && !Path::new(&remote).starts_with(SHARED_DIR) {
+        anyhow::bail!(
+            "Upload '{}' goes to the shared filesystem, so its remote path '{}' must be under {} (or set `to: head` or `to: all_nodes`)",
+            upload.local,
+            upload.remote,
+            SHARED_DIR
+        );
+    }
+    if local.contains(['*', '?', '[']) {
+        let sources: Vec<String> = match glob::glob(&local) {
+            Ok(paths) => paths
+                .filter_map(|path| path.ok())
+                .map(|path| path.to_string_lossy().to_string())
+                .collect(),
+            Err(e) => anyhow::bail!("Invalid upload pattern '{}': {}", upload.local, e),
+        };
+        if sources.is_empty() {
+            anyhow::bail!("Upload pattern '{}' matches no files", upload.local);
+        }
+        return Ok((sources, remote.clone(), format!("{}/", remote)));
+    }
+
+    let path = Path::new(&local);
+    if path.is_dir() {
+        let source = format!("{}/", local.trim_end_matches('/'));
+        Ok((vec![source], remote.clone(), remote))
+    } else if path.is_file() {
+        let remote_dir = Path::new(&remote)
+            .parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Ok((vec![local], remote_dir, remote))
+    } else {
+        anyhow::bail!("Upload source '{}' not found", upload.local)
+    }
+}
+
+/// Identifies a tasks file by content, so runs recorded for it are not mistaken
+/// for runs of an edited copy at the same path.
+fn tasks_file_hash(yaml_str: &str) -> String {
+    format!("{:x}", Sha256::digest(yaml_str.as_bytes()))
+}
+
+pub async fn tasks(
+    pool: &SqlitePool,
+    yaml_file_path: &str,
+    resume: bool,
+    follow: bool,
+) -> Result<()> {
     let path = Path::new(yaml_file_path);
     let yaml_str = match fs::read_to_string(path) {
         Ok(s) => s,
@@ -64,8 +476,8 @@ pub async fn tasks(pool: &SqlitePool, yaml_file_path: &str) -> Result<()> {
         anyhow::bail!("Cluster '{}' has no nodes", cluster.id);
     }
 
-    // Sort by private_ip for consistent ordering; node 0 = head node (mpirun is called here)
-    nodes.sort_by(|a, b| a.private_ip.cmp(&b.private_ip));
+    // Slot order for consistent ordering; node 0 = head node (mpirun is called here)
+    slot_order(&mut nodes);
 
     let head_ip = match &nodes[0].public_ip {
         Some(ip) => ip.clone(),
@@ -73,33 +485,128 @@ pub async fn tasks(pool: &SqlitePool, yaml_file_path: &str) -> Result<()> {
     };
 
     let private_key_path = utils::expand_tilde(&cluster.private_ssh_key_path);
-    let head_ssh = SshSession::for_aws(&head_ip, &private_key_path);
+    let head_ssh = SshSession::for_cluster_node(
+        &cluster.provider_id,
+        nodes[0].private_ip.as_deref().unwrap_or_default(),
+        Some(&head_ip),
+        &head_ip,
+        &private_key_path,
+    );
+    let node_ssh: Vec<SshSession> = nodes
+        .iter()
+        .map(|node| {
+            SshSession::for_cluster_node(
+                &cluster.provider_id,
+                node.private_ip.as_deref().unwrap_or_default(),
+                node.public_ip.as_deref(),
+                &head_ip,
+                &private_key_path,
+            )
+        })
+        .collect();
+
+    validate_tasks(&tasks_yaml.tasks, nodes.len())?;
+    if let Some(task) = tasks_yaml
+        .tasks
+        .iter()
+        .find(|t| t.launcher == Launcher::Sbatch)
+        && cluster.scheduler.as_deref() != Some("slurm")
+    {
+        anyhow::bail!(
+            "Task '{}' uses launcher 'sbatch', but Cluster '{}' was not spawned with 'scheduler: slurm'",
+            task.name,
+            cluster.id
+        );
+    }
+    let checkpoint_signal = match &tasks_yaml.checkpoint_signal {
+        Some(checkpoint_signal) => {
+            checkpoint_signal.validate()?;
+            Some(serde_json::to_string(checkpoint_signal)?)
+        }
+        None => None,
+    };
 
     let results_local_dir = utils::expand_tilde(&tasks_yaml.results_local_dir);
     let total_tasks = tasks_yaml.tasks.len();
 
-    if !tasks_yaml.uploads.is_empty() {
+    let file_hash = tasks_file_hash(&yaml_str);
+    let succeeded = if resume {
+        let succeeded = TaskRun::fetch_succeeded(pool, &cluster.id, &file_hash).await?;
+        // Nothing to skip while the cluster's latest run came from another file
+        // most likely means this one was edited since, which starts it over.
+        if succeeded.is_empty()
+            && let Some(latest) = TaskRun::fetch_by_cluster_id(pool, &cluster.id).await?.pop()
+            && latest.tasks_file_hash.as_deref() != Some(file_hash.as_str())
+        {
+            tracing::warn!(
+                "'{}' changed since the last run on cluster '{}' (task '{}', {}): no run is resumed",
+                yaml_file_path,
+                cluster.id,
+                latest.task_name,
+                match latest.finished_at {
+                    Some(finished_at) => format!("finished at {}", finished_at),
+                    None => "unfinished".to_string(),
+                }
+            );
+        }
         tracing::info!(
-            "Uploading {} file(s) to cluster '{}' (head node: {})",
-            tasks_yaml.uploads.len(),
+            "Resuming '{}': {} run(s) already succeeded and will be skipped",
+            yaml_file_path,
+            succeeded.len()
+        );
+        succeeded
+    } else {
+        HashSet::new()
+    };
+
+    // Resolve every upload before sending any, so a typo fails fast.
+    let uploads = tasks_yaml
+        .uploads
+        .iter()
+        .map(|upload| Ok((upload.to, resolve_upload(upload)?)))
+        .collect::<Result<Vec<_>>>()?;
+    if !uploads.is_empty() {
+        tracing::info!(
+            "Uploading {} path(s) to cluster '{}' (head node: {})",
+            uploads.len(),
             cluster.id,
             head_ip
         );
-        for upload in &tasks_yaml.uploads {
-            let local = utils::expand_tilde(&upload.local);
-            let remote_dir = Path::new(&upload.remote)
-                .parent()
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_default();
+    }
+    // Without the shared filesystem, a shared upload would only reach the head
+    // node's disk.
+    if uploads
+        .iter()
+        .any(|(target, _)| *target == UploadTarget::Shared)
+        && head_ssh
+            .run_command(&format!(
+                "mountpoint -q {} && echo mounted; true",
+                SHARED_DIR
+            ))
+            .await?
+            .trim()
+            != "mounted"
+    {
+        anyhow::bail!(
+            "{} is not mounted on the head node of cluster '{}': upload with `to: head` or `to: all_nodes` instead",
+            SHARED_DIR,
+            cluster.id
+        );
+    }
+    for (target, (sources, remote_dir, dest)) in &uploads {
+        let targets = match target {
+            UploadTarget::Shared | UploadTarget::Head => &node_ssh[..1],
+            UploadTarget::AllNodes => &node_ssh[..],
+        };
+        for ssh in targets {
             if !remote_dir.is_empty() {
-                head_ssh
-                    .run_command(&format!(
-                        "sudo mkdir -p {0} && sudo chown $(id -u):$(id -g) {0}",
-                        remote_dir
-                    ))
-                    .await?;
+                ssh.run_command(&format!(
+                    "sudo mkdir -p {0} && sudo chown $(id -u):$(id -g) {0}",
+                    remote_dir
+                ))
+                .await?;
             }
-            head_ssh.upload_file_binary(&local, &upload.remote).await?;
+            ssh.upload_paths(sources, dest).await?;
         }
     }
 
@@ -110,21 +617,31 @@ pub async fn tasks(pool: &SqlitePool, yaml_file_path: &str) -> Result<()> {
         head_ip
     );
 
-    // Tracks the tmux session currently being waited on, so Ctrl+C can kill it.
-    let active_session: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+    let pipeline = Arc::new(Pipeline {
+        pool: pool.clone(),
+        head_ssh,
+        node_ssh,
+        cluster_id: cluster.id.clone(),
+        tasks_file_hash: file_hash,
+        results_local_dir,
+        follow,
+        checkpoint_signal,
+        succeeded,
+        active_sessions: Mutex::new(HashSet::new()),
+    });
 
+    // Dropping the loop on Ctrl+C aborts the tasks it spawned, but not their
+    // tmux sessions on the head node.
     let result = tokio::select! {
-        r = run_task_loop(
-            pool, &head_ssh, &tasks_yaml, &nodes,
-            &results_local_dir, total_tasks, &cluster.id,
-            active_session.clone(),
-        ) => r,
+        r = run_task_loop(pipeline.clone(), &tasks_yaml, &nodes) => r,
         _ = tokio::signal::ctrl_c() => {
             tracing::info!("Interrupted (Ctrl+C). Cleaning up...");
-            let session = active_session.lock().unwrap().take();
-            if let Some(session) = session {
+            let sessions: Vec<String> =
+                pipeline.active_sessions.lock().unwrap().drain().collect();
+            for session in sessions {
                 tracing::info!("Killing remote tmux session '{}'...", session);
-                let _ = head_ssh
+                let _ = pipeline
+                    .head_ssh
                     .run_command(&format!("tmux kill-session -t {} 2>/dev/null; true", session))
                     .await;
             }
@@ -135,111 +652,694 @@ pub async fn tasks(pool: &SqlitePool, yaml_file_path: &str) -> Result<()> {
     result
 }
 
+/// Runs the pipeline's tasks, each once its dependencies completed and its
+/// nodes are free. Tasks are considered in file order, so a file without
+/// `depends_on` or `nodes` runs one task after another on the whole cluster.
+/// A failed task stops the pipeline, unless it has `continue_on_failure`: then
+/// only the tasks that depend on it are skipped.
 async fn run_task_loop(
-    pool: &SqlitePool,
-    head_ssh: &SshSession,
+    pipeline: Arc<Pipeline>,
     tasks_yaml: &TasksYaml,
-    nodes: &[crate::database::models::Node],
-    results_local_dir: &str,
-    total_tasks: usize,
-    cluster_id: &str,
-    active_session: Arc<Mutex<Option<String>>>,
+    nodes: &[Node],
 ) -> Result<()> {
+    let total_tasks = tasks_yaml.tasks.len();
+    let mut completed: HashSet<&str> = HashSet::new();
+    let mut pending: Vec<usize> = Vec::new();
     for (task_idx, task) in tasks_yaml.tasks.iter().enumerate() {
-        tracing::info!(
-            "[{}/{}] Task '{}': {} repeat(s), {} slot(s)/host",
-            task_idx + 1,
-            total_tasks,
-            task.name,
-            task.repeat,
-            task.mpi_slots_per_host
-        );
+        // Its results were collected when the last run finished.
+        let runs = task.runs();
+        let all_succeeded = runs.iter().all(|(run, params)| {
+            pipeline
+                .succeeded
+                .contains(&(task.name.clone(), *run, params.clone()))
+        });
+        if all_succeeded {
+            tracing::info!(
+                "[{}/{}] Task '{}': all {} run(s) already succeeded, skipping",
+                task_idx + 1,
+                total_tasks,
+                task.name,
+                runs.len()
+            );
+            completed.insert(&task.name);
+        } else {
+            pending.push(task_idx);
+        }
+    }
 
-        // Build MPI hostfile content for this task
-        let hostfile_content: String = nodes
-            .iter()
-            .filter_map(|n| n.private_ip.as_deref())
-            .map(|ip| format!("{} slots={}\n", ip, task.mpi_slots_per_host))
-            .collect();
+    let mut busy: HashSet<usize> = HashSet::new();
+    let mut running: JoinSet<(usize, Vec<usize>, Result<()>)> = JoinSet::new();
+    let mut failure: Option<anyhow::Error> = None;
+    // Tasks that failed with continue_on_failure, and those skipped because
+    // they depend on one, directly or not.
+    let mut failed: Vec<&str> = Vec::new();
+    let mut skipped: Vec<&str> = Vec::new();
+    loop {
+        loop {
+            let before = pending.len();
+            pending.retain(|&task_idx| {
+                let task = &tasks_yaml.tasks[task_idx];
+                let blocked_by = task
+                    .depends_on
+                    .iter()
+                    .find(|d| failed.contains(&d.as_str()) || skipped.contains(&d.as_str()));
+                match blocked_by {
+                    Some(dependency) => {
+                        tracing::warn!(
+                            "Skipping task '{}': it depends on '{}', which did not complete",
+                            task.name,
+                            dependency
+                        );
+                        skipped.push(&task.name);
+                        false
+                    }
+                    None => true,
+                }
+            });
+            if pending.len() == before {
+                break;
+            }
+        }
 
-        for run in 1..=task.repeat {
-            tracing::info!("  Run {}/{}", run, task.repeat);
+        // After a failure, only wait for what is already running.
+        if failure.is_none() {
+            pending.retain(|&task_idx| {
+                let task = &tasks_yaml.tasks[task_idx];
+                if !task
+                    .depends_on
+                    .iter()
+                    .all(|d| completed.contains(d.as_str()))
+                {
+                    return true;
+                }
+                let Some(slots) = pick_nodes(task.nodes.as_ref(), nodes.len(), &busy) else {
+                    return true;
+                };
+                busy.extend(&slots);
 
-            // Clean up leftover task scripts and logs from previous runs to avoid
-            // filling /tmp and causing upload failures.
-            head_ssh
-                .run_command("rm -f /tmp/hpcac_*.sh /tmp/hpcac_*.log /tmp/hpcac_*.exit")
-                .await?;
+                let group: Vec<(String, String)> = slots
+                    .iter()
+                    .map(|&slot| {
+                        let node = &nodes[slot];
+                        (node.id.clone(), node.private_ip.clone().unwrap_or_default())
+                    })
+                    .collect();
+                tracing::info!(
+                    "[{}/{}] Task '{}': {} run(s), {} slot(s)/host on node(s) {:?}",
+                    task_idx + 1,
+                    total_tasks,
+                    task.name,
+                    task.runs().len(),
+                    task.mpi_slots_per_host,
+                    slots
+                );
+                let pipeline = pipeline.clone();
+                let task = task.clone();
+                running.spawn(async move {
+                    let result = run_task(&pipeline, &task, &group).await;
+                    (task_idx, slots, result)
+                });
+                false
+            });
+        }
 
-            head_ssh
-                .upload_file(MPI_HOSTFILE_PATH, &hostfile_content)
-                .await?;
+        let (task_idx, slots, result) = match running.join_next().await {
+            Some(Ok(joined)) => joined,
+            Some(Err(e)) => {
+                failure.get_or_insert(anyhow::anyhow!("Task aborted: {}", e));
+                continue;
+            }
+            None => break,
+        };
+        for slot in &slots {
+            busy.remove(slot);
+        }
 
-            let session_name = format!("hpcac_{}_run{}", task.name.replace(['-', ' '], "_"), run);
-            let wrapped_script = format!(
-                "export HPCAC_RUN_INDEX={}\nexport HPCAC_HOSTFILE={}\n{}",
-                run, MPI_HOSTFILE_PATH, task.script
-            );
+        let task = &tasks_yaml.tasks[task_idx];
+        // Other tasks may still be writing to the remote results dir, so it is
+        // only cleared when nothing else runs.
+        let error = match result {
+            Ok(()) => match collect_results(&pipeline, tasks_yaml, running.is_empty()).await {
+                Ok(()) => {
+                    tracing::info!("  Task '{}' complete.", task.name);
+                    completed.insert(&task.name);
+                    None
+                }
+                Err(e) => Some(e),
+            },
+            Err(e) if task.continue_on_failure => {
+                tracing::error!("{}", e);
+                failed.push(&task.name);
+                // The runs that did succeed are worth keeping.
+                collect_results(&pipeline, tasks_yaml, running.is_empty())
+                    .await
+                    .err()
+            }
+            Err(e) => Some(e),
+        };
+        if let Some(e) = error {
+            if !running.is_empty() {
+                tracing::warn!(
+                    "Task '{}' failed, waiting for the {} task(s) still running",
+                    task.name,
+                    running.len()
+                );
+            }
+            failure.get_or_insert(e);
+        }
+    }
+
+    if let Some(e) = failure {
+        return Err(e);
+    }
+    if !failed.is_empty() {
+        let mut message = format!("{} task(s) failed: {}", failed.len(), failed.join(", "));
+        if !skipped.is_empty() {
+            message.push_str(&format!(
+                "; {} dependent task(s) skipped: {}",
+                skipped.len(),
+                skipped.join(", ")
+            ));
+        }
+        anyhow::bail!(message);
+    }
+    tracing::info!("All tasks complete.");
+    Ok(())
+}
 
-            let task_run = TaskRun::start(pool, cluster_id, &task.name, run as i64).await?;
+/// Runs the runs of `task` not yet succeeded, with a hostfile for its launcher
+/// listing `group`, its nodes as (node id, private IP). Each run is attempted
+/// up to `retries` more times.
+async fn run_task(pipeline: &Pipeline, task: &TaskYaml, group: &[(String, String)]) -> Result<()> {
+    let head_ssh = &pipeline.head_ssh;
+    let task_slug = task_slug(&task.name);
+    let timeout = task.timeout.as_ref().map(|t| t.duration()).transpose()?;
 
-            let launch_result = head_ssh.run_in_tmux(&session_name, &wrapped_script).await;
-            if launch_result.is_err() {
-                task_run.finish(pool, "failed").await?;
-                launch_result?;
-            }
+    // Tasks running side by side each need their own hostfile.
+    let hostfile_path = format!("{}_{}", MPI_HOSTFILE_PATH, task_slug);
+    let ips: Vec<String> = group.iter().map(|(_, ip)| ip.clone()).collect();
+    let hostfile_content = task.launcher.hostfile(&ips, task.mpi_slots_per_host);
+    let node_ids: Vec<String> = group.iter().map(|(id, _)| id.clone()).collect();
+    let np = group.len() as u32 * task.mpi_slots_per_host;
+    let mpirun_export = match task.launcher.mpirun_prefix(&hostfile_path, np) {
+        Some(prefix) => format!("export HPCAC_MPIRUN={}\n", shell_quote(&prefix)),
+        None => String::new(),
+    };
 
-            *active_session.lock().unwrap() = Some(session_name.clone());
+    let mut failed_runs: Vec<String> = Vec::new();
+    for (seq, (run, params)) in (1usize..).zip(task.runs()) {
+        let run_description = describe_run(task, run, &params);
+        let key = (task.name.clone(), run, params);
+        if pipeline.succeeded.contains(&key) {
+            tracing::info!(
+                "  Task '{}' {} already succeeded, skipping",
+                task.name,
+                run_description
+            );
+            continue;
+        }
+        tracing::info!("  Task '{}' {}", task.name, run_description);
 
-            let wait_result = head_ssh
-                .wait_for_tmux(&session_name, Duration::from_secs(300))
-                .await;
+        // Clean up leftover task scripts and logs from previous runs to avoid
+        // filling /tmp and causing upload failures. Only this task's own: other
+        // tasks may be running. The last run's files stay for inspection.
+        let session_name = run_session_name(&task.name, seq);
+        let previous_session = run_session_name(&task.name, seq - 1);
+        head_ssh
+            .run_command(&format!(
+                "rm -f {} {}",
+                session_files(&previous_session),
+                session_files(&session_name)
+            ))
+            .await?;
 
-            *active_session.lock().unwrap() = None;
+        head_ssh
+            .upload_file(&hostfile_path, &hostfile_content)
+            .await?;
 
-            if let Err(e) = wait_result {
-                task_run.finish(pool, "failed").await?;
-                anyhow::bail!(
-                    "Task '{}' run {}/{} failed (SSH error): {}",
-                    task.name, run, task.repeat, e
-                );
+        let param_exports: String = key
+            .2
+            .iter()
+            .map(|(name, value)| format!("export {}={}\n", param_env_var(name), shell_quote(value)))
+            .collect();
+        // An sbatch job inherits the variables exported before its submission.
+        let job_script = match task.launcher {
+            Launcher::Sbatch => {
+                slurm::sbatch_submission(&session_name, &ips, task.mpi_slots_per_host, &task.script)
             }
+            _ => task.script.clone(),
+        };
+        let run_spec = RunSpec {
+            script: format!(
+                "export HPCAC_RUN_INDEX={}\nexport HPCAC_HOSTFILE={}\n{}{}{}",
+                run, hostfile_path, mpirun_export, param_exports, job_script
+            ),
+            key,
+            node_ids: &node_ids,
+            session_name,
+            timeout,
+            launcher: task.launcher,
+            log_dir: Path::new(&pipeline.results_local_dir)
+                .join(&task.name)
+                .join(format!("run_{}", seq)),
+        };
 
-            let exit_code = head_ssh.tmux_exit_code(&session_name).await?;
-            if exit_code != 0 {
-                task_run.finish(pool, "failed").await?;
-                anyhow::bail!(
-                    "Task '{}' run {}/{} failed with exit code {}. Check log: /tmp/hpcac_{}.log",
-                    task.name, run, task.repeat, exit_code, session_name
+        let mut attempt: i64 = 1;
+        loop {
+            let Err(e) = run_attempt(pipeline, &run_spec, attempt).await else {
+                break;
+            };
+            if attempt <= task.retries as i64 {
+                tracing::warn!(
+                    "  Task '{}' {} attempt {} failed ({}), retrying...",
+                    task.name,
+                    run_description,
+                    attempt,
+                    e
                 );
+                attempt += 1;
+                continue;
             }
 
-            task_run.finish(pool, "success").await?;
+            let error = anyhow::anyhow!(
+                "Task '{}' {} failed after {} attempt(s): {}",
+                task.name,
+                run_description,
+                attempt,
+                e
+            );
+            if !task.continue_on_failure {
+                return Err(error);
+            }
+            tracing::error!("{}", error);
+            failed_runs.push(run_description);
+            break;
         }
+    }
 
-        // Collect results after all repeats of this task complete.
-        // Download contents of remote results dir directly into results_local_dir
-        // (scripts already organize output by task name inside that dir).
-        // Then clear the remote so the next task starts with a clean slate.
-        fs::create_dir_all(results_local_dir)?;
-        tracing::info!(
-            "  Collecting results: '{}' -> '{}'",
-            tasks_yaml.results_remote_dir,
-            results_local_dir
+    if !failed_runs.is_empty() {
+        anyhow::bail!(
+            "Task '{}' had {} failed run(s): {}",
+            task.name,
+            failed_runs.len(),
+            failed_runs.join("; ")
         );
-        head_ssh
-            .run_command(&format!("mkdir -p {}", tasks_yaml.results_remote_dir))
-            .await?;
-        head_ssh
-            .download_dir(&tasks_yaml.results_remote_dir, results_local_dir)
+    }
+    Ok(())
+}
+
+/// A run of a task, shared by the attempts at it.
+struct RunSpec<'a> {
+    key: TaskRunKey,
+    node_ids: &'a [String],
+    session_name: String,
+    script: String,
+    timeout: Option<Duration>,
+    launcher: Launcher,
+    /// Local directory the run's logs are downloaded to.
+    log_dir: PathBuf,
+}
+
+/// Runs one attempt of a run in its tmux session and records it as a task run,
+/// along with its exit code and log. The error says how the attempt failed.
+async fn run_attempt(pipeline: &Pipeline, run: &RunSpec<'_>, attempt: i64) -> Result<()> {
+    let head_ssh = &pipeline.head_ssh;
+    let session_name = run.session_name.as_str();
+    let task_run = TaskRun::start(
+        &pipeline.pool,
+        &pipeline.cluster_id,
+        &pipeline.tasks_file_hash,
+        &run.key,
+        attempt,
+        &TaskRunJob {
+            node_ids: run.node_ids,
+            launcher: &run.launcher.to_string(),
+            checkpoint_signal: pipeline.checkpoint_signal.as_deref(),
+        },
+    )
+    .await?;
+
+    if let Err(e) = head_ssh.run_in_tmux(session_name, &run.script).await {
+        task_run
+            .finish(&pipeline.pool, "failed", &TaskRunOutput::default())
             .await?;
+        return Err(e);
+    }
+
+    pipeline
+        .active_sessions
+        .lock()
+        .unwrap()
+        .insert(session_name.to_string());
+
+    let wait = async {
+        let tmux = head_ssh.wait_for_tmux(session_name, Duration::from_secs(300));
+        tokio::pin!(tmux);
+        if pipeline.follow {
+            tokio::select! {
+                result = &mut tmux => return result,
+                followed = follow_log(head_ssh, session_name) => {
+                    // Losing the stream doesn't affect the run: keep waiting.
+                    if let Err(e) = followed {
+                        tracing::warn!("{}", e);
+                    }
+                }
+            }
+        }
+        tmux.await
+    };
+    let wait_result = match run.timeout {
+        Some(limit) => tokio::time::timeout(limit, wait).await,
+        None => Ok(wait.await),
+    };
+
+    pipeline
+        .active_sessions
+        .lock()
+        .unwrap()
+        .remove(session_name);
+
+    let wait_result = match wait_result {
+        Ok(result) => result,
+        Err(_) => {
+            let _ = head_ssh
+                .run_command(&format!(
+                    "tmux kill-session -t {} 2>/dev/null; true",
+                    session_name
+                ))
+                .await;
+            let output = collect_log(head_ssh, run, attempt).await;
+            task_run.finish(&pipeline.pool, "timeout", &output).await?;
+            anyhow::bail!(
+                "timed out after {}s. Check log: {}",
+                run.timeout.unwrap_or_default().as_secs(),
+                output.log_path.as_deref().unwrap_or("unavailable")
+            );
+        }
+    };
+
+    if let Err(e) = wait_result {
+        let output = collect_log(head_ssh, run, attempt).await;
+        task_run.finish(&pipeline.pool, "failed", &output).await?;
+        anyhow::bail!("SSH error: {}", e);
+    }
+
+    let exit_code = head_ssh.tmux_exit_code(session_name).await?;
+    let output = TaskRunOutput {
+        exit_code: Some(exit_code as i64),
+        ..collect_log(head_ssh, run, attempt).await
+    };
+    if exit_code != 0 {
+        task_run.finish(&pipeline.pool, "failed", &output).await?;
+        anyhow::bail!(
+            "exit code {}. Check log: {}",
+            exit_code,
+            output.log_path.as_deref().unwrap_or("unavailable")
+        );
+    }
+
+    task_run.finish(&pipeline.pool, "success", &output).await
+}
+
+/// Streams the log of tmux session `session_name` until the session ends. The
+/// error says why the stream was lost; the run itself goes on regardless.
+pub async fn follow_log(head_ssh: &SshSession, session_name: &str) -> Result<()> {
+    head_ssh
+        .follow_file(&session_log(session_name), session_name)
+        .await
+}
+
+/// Keeps the tail of an attempt's log and downloads the whole log into the
+/// run's log dir, before the cleanup of a later run removes it from the head
+/// node. Either is left out, with a warning, when it cannot be fetched.
+async fn collect_log(head_ssh: &SshSession, run: &RunSpec<'_>, attempt: i64) -> TaskRunOutput {
+    let remote_log = session_log(&run.session_name);
+    let log_tail = match head_ssh
+        .run_command(&format!("tail -n {} {}", LOG_TAIL_LINES, remote_log))
+        .await
+    {
+        Ok(tail) => Some(tail),
+        Err(e) => {
+            tracing::warn!("Could not read the log '{}': {}", remote_log, e);
+            None
+        }
+    };
+
+    // Retries keep the logs of the attempts before them.
+    let file_name = match attempt {
+        1 => "hpcac.log".to_string(),
+        n => format!("hpcac.attempt{}.log", n),
+    };
+    let local_log = run.log_dir.join(file_name).to_string_lossy().to_string();
+    let downloaded = match fs::create_dir_all(&run.log_dir) {
+        Ok(()) => head_ssh.download_file_binary(&remote_log, &local_log).await,
+        Err(e) => Err(e.into()),
+    };
+    let log_path = match downloaded {
+        Ok(()) => Some(local_log),
+        Err(e) => {
+            tracing::warn!("Could not download the log '{}': {}", remote_log, e);
+            None
+        }
+    };
+
+    TaskRunOutput {
+        exit_code: None,
+        log_tail,
+        log_path,
+    }
+}
+
+/// Collects results after all repeats of a task complete.
+/// Downloads contents of the remote results dir directly into results_local_dir
+/// (scripts already organize output by task name inside that dir). Then, when
+/// `clear_remote` says no other task is still writing there, clears the remote
+/// so the next task starts with a clean slate. The `downloads:` paths are then
+/// collected from every node into `node_<slot>/`. A node may not have them, if
+/// no task that writes them ran there yet, so failing to collect only warns.
+async fn collect_results(
+    pipeline: &Pipeline,
+    tasks_yaml: &TasksYaml,
+    clear_remote: bool,
+) -> Result<()> {
+    let head_ssh = &pipeline.head_ssh;
+    let results_local_dir = &pipeline.results_local_dir;
+    fs::create_dir_all(results_local_dir)?;
+    tracing::info!(
+        "  Collecting results: '{}' -> '{}'",
+        tasks_yaml.results_remote_dir,
+        results_local_dir
+    );
+    head_ssh
+        .run_command(&format!("mkdir -p {}", tasks_yaml.results_remote_dir))
+        .await?;
+    head_ssh
+        .download_dir(&tasks_yaml.results_remote_dir, results_local_dir)
+        .await?;
+    if clear_remote {
         head_ssh
             .run_command(&format!("rm -rf {}/*", tasks_yaml.results_remote_dir))
             .await?;
-
-        tracing::info!("  Task '{}' complete.", task.name);
     }
 
-    tracing::info!("All tasks complete.");
+    for download in &tasks_yaml.downloads {
+        for (slot, ssh) in pipeline.node_ssh.iter().enumerate() {
+            let local_dir = Path::new(results_local_dir).join(format!("node_{}", slot));
+            fs::create_dir_all(&local_dir)?;
+            if let Err(e) = ssh
+                .download_path(&download.remote, &local_dir.to_string_lossy())
+                .await
+            {
+                tracing::warn!(
+                    "  Could not collect '{}' from node slot {}: {}",
+                    download.remote,
+                    slot,
+                    e
+                );
+            }
+        }
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(name: &str, depends_on: &[&str], nodes: Option<NodeSelection>) -> TaskYaml {
+        TaskYaml {
+            name: name.to_string(),
+            mpi_slots_per_host: 1,
+            repeat: 1,
+            script: String::new(),
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+            nodes,
+            matrix: BTreeMap::new(),
+            timeout: None,
+            retries: 0,
+            continue_on_failure: false,
+            launcher: Launcher::default(),
+        }
+    }
+
+    #[test]
+    fn validate_tasks_rejects_graphs_that_cannot_run() {
+        let fits = [
+            task("setup", &[], None),
+            task("a", &["setup"], Some(NodeSelection::Count(2))),
+            task("b", &["setup"], Some(NodeSelection::Slots(vec![2, 3]))),
+        ];
+        validate_tasks(&fits, 4).unwrap();
+
+        let error = |tasks: &[TaskYaml]| validate_tasks(tasks, 4).unwrap_err().to_string();
+        assert!(error(&[task("a", &[], None), task("a", &[], None)]).contains("more than once"));
+        assert!(error(&[task("a", &["b"], None)]).contains("unknown task 'b'"));
+        assert!(error(&[task("a", &["b"], None), task("b", &["a"], None)]).contains("cycle"));
+        assert!(error(&[task("a", &[], Some(NodeSelection::Count(5)))]).contains("5 node(s)"));
+        assert!(error(&[task("a", &[], Some(NodeSelection::Slots(vec![4])))]).contains("slot 4"));
+    }
+
+    #[test]
+    fn pick_nodes_waits_for_busy_slots() {
+        let busy = HashSet::from([0, 1]);
+        assert_eq!(pick_nodes(None, 4, &busy), None);
+        assert_eq!(
+            pick_nodes(Some(&NodeSelection::Count(2)), 4, &busy),
+            Some(vec![2, 3])
+        );
+        assert_eq!(pick_nodes(Some(&NodeSelection::Count(3)), 4, &busy), None);
+        assert_eq!(
+            pick_nodes(Some(&NodeSelection::Slots(vec![3])), 4, &busy),
+            Some(vec![3])
+        );
+        assert_eq!(
+            pick_nodes(Some(&NodeSelection::Slots(vec![1, 2])), 4, &busy),
+            None
+        );
+    }
+
+    #[test]
+    fn node_selection_accepts_a_count_or_a_list_of_slots() {
+        let count: TaskYaml = serde_yaml::from_str(
+            "{name: a, mpi_slots_per_host: 1, repeat: 1, script: x, nodes: 2}",
+        )
+        .unwrap();
+        assert!(matches!(count.nodes, Some(NodeSelection::Count(2))));
+        let slots: TaskYaml = serde_yaml::from_str(
+            "{name: a, mpi_slots_per_host: 1, repeat: 1, script: x, nodes: [0, 3]}",
+        )
+        .unwrap();
+        assert!(matches!(slots.nodes, Some(NodeSelection::Slots(s)) if s == vec![0, 3]));
+    }
+
+    #[test]
+    fn matrix_expands_into_every_combination() {
+        let sweep: TaskYaml = serde_yaml::from_str(
+            "{name: sweep, mpi_slots_per_host: 1, repeat: 2, script: x,
+              matrix: {k: [2, 4, 8], storage: [memory, disk]}}",
+        )
+        .unwrap();
+        let runs = sweep.runs();
+        assert_eq!(runs.len(), 12);
+        assert_eq!(runs[0].0, 1);
+        assert_eq!(runs[1].0, 2);
+        assert_eq!(runs[0].1["k"], "2");
+        assert_eq!(runs[0].1["storage"], "memory");
+        assert_eq!(runs[11].1["k"], "8");
+        assert_eq!(runs[11].1["storage"], "disk");
+
+        let plain = task("a", &[], None);
+        assert_eq!(plain.runs(), vec![(1, BTreeMap::new())]);
+    }
+
+    #[test]
+    fn matrix_params_are_exported_as_shell_variables() {
+        assert_eq!(param_env_var("k"), "HPCAC_PARAM_K");
+        assert_eq!(
+            param_env_var("wavefield-storage"),
+            "HPCAC_PARAM_WAVEFIELD_STORAGE"
+        );
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    }
+
+    #[test]
+    fn timeouts_take_seconds_or_a_unit() {
+        let secs = |timeout: TaskTimeout| timeout.duration().map(|d| d.as_secs());
+        assert_eq!(secs(TaskTimeout::Secs(90)).unwrap(), 90);
+        assert_eq!(secs(TaskTimeout::Text("45".into())).unwrap(), 45);
+        assert_eq!(secs(TaskTimeout::Text("30m".into())).unwrap(), 1800);
+        assert_eq!(secs(TaskTimeout::Text("2h".into())).unwrap(), 7200);
+        assert!(secs(TaskTimeout::Text("2 weeks".into())).is_err());
+        assert!(secs(TaskTimeout::Secs(0)).is_err());
+
+        let mut slow = task("slow", &[], None);
+        slow.timeout = Some(TaskTimeout::Text("soon".into()));
+        let error = validate_tasks(&[slow], 1).unwrap_err().to_string();
+        assert!(error.contains("Task 'slow': Invalid timeout 'soon'"));
+    }
+
+    #[test]
+    fn run_sessions_are_found_by_task_name() {
+        let session = run_session_name("fwi-solve", 3);
+        assert_eq!(session, "hpcac_fwi_solve_run3");
+        assert!(is_run_session(&session, None));
+        assert!(is_run_session(&session, Some("fwi-solve")));
+        assert!(!is_run_session(&session, Some("fwi")));
+        assert!(!is_run_session(
+            &run_session_name("fwi_run", 1),
+            Some("fwi")
+        ));
+        assert!(!is_run_session("hpcac_fwi_solve_runs", None));
+        assert!(!is_run_session("build_run1", None));
+    }
+
+    #[test]
+    fn uploads_resolve_files_directories_and_globs() {
+        let dir = std::env::temp_dir().join(format!("hpcac_uploads_{}", std::process::id()));
+        fs::create_dir_all(dir.join("model")).unwrap();
+        fs::write(dir.join("model/a.segy"), "a").unwrap();
+        fs::write(dir.join("model/b.segy"), "b").unwrap();
+        let local = |path: &str| dir.join(path).to_string_lossy().to_string();
+        let upload_to = |path: &str, remote: &str, to: UploadTarget| UploadSpec {
+            local: local(path),
+            remote: remote.to_string(),
+            to,
+        };
+        let upload = |path: &str, remote: &str| upload_to(path, remote, UploadTarget::Head);
+
+        let (sources, remote_dir, dest) = resolve_upload(&upload_to(
+            "model/a.segy",
+            "/shared/in/a.segy",
+            UploadTarget::Shared,
+        ))
+        .unwrap();
+        assert_eq!(sources, [local("model/a.segy")]);
+        assert_eq!(
+            (remote_dir.as_str(), dest.as_str()),
+            ("/shared/in", "/shared/in/a.segy")
+        );
+
+        let (sources, remote_dir, dest) =
+            resolve_upload(&upload("model", "/shared/model/")).unwrap();
+        assert_eq!(sources, [format!("{}/", local("model"))]);
+        assert_eq!(
+            (remote_dir.as_str(), dest.as_str()),
+            ("/shared/model", "/shared/model")
+        );
+
+        let (mut sources, remote_dir, dest) =
+            resolve_upload(&upload("model/*.segy", "/scratch/in")).unwrap();
+        sources.sort();
+        assert_eq!(sources, [local("model/a.segy"), local("model/b.segy")]);
+        assert_eq!(
+            (remote_dir.as_str(), dest.as_str()),
+            ("/scratch/in", "/scratch/in/")
+        );
+
+        assert!(resolve_upload(&upload("model/*.su", "/scratch/in")).is_err());
+        assert!(resolve_upload(&upload_to("model", "/scratch/in", UploadTarget::Shared)).is_err());
+        assert!(resolve_upload(&upload_to("model", "/sharedx", UploadTarget::Shared)).is_err());
+        assert!(resolve_upload(&upload("missing", "/scratch/in")).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
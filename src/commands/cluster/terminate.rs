@@ -1,5 +1,5 @@
 use crate::database::models::{Cluster, ClusterState, ProviderConfig};
-use crate::integrations::{cloud_interface::CloudResourceManager, providers::aws::AwsInterface};
+use crate::integrations::cloud_interface::{CloudProvider, CloudResourceManager};
 use crate::utils;
 
 use anyhow::Result;
@@ -34,14 +34,7 @@ pub async fn terminate(pool: &SqlitePool, cluster_id: &str, skip_confirmation: b
             }
         };
 
-    let config_vars = provider_config.get_config_vars(pool).await?;
-    let provider_id = provider_config.provider_id.clone();
-    let cloud_interface = match provider_id.as_str() {
-        "aws" => AwsInterface { config_vars },
-        _ => {
-            anyhow::bail!("Provider '{}' is currently not supported.", &provider_id)
-        }
-    };
+    let cloud_interface = CloudProvider::from_provider_config(pool, &provider_config).await?;
 
     if !(utils::user_confirmation(
         skip_confirmation,
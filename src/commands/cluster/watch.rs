@@ -1,23 +1,63 @@
+use crate::commands::cluster::cost::estimate_cost;
 use crate::commands::cluster::tasks::tasks;
+use crate::commands::cluster::watch_control::{WatchControl, run_locked};
 use crate::database::models::{
-    Cluster, ClusterState, Node, ProviderConfig, RecoveryNode, ShellCommand,
+    Cluster, ClusterEvent, ClusterEventType, ClusterState, InstanceSpec, InstanceType, Node,
+    PricedInstanceType, ProviderConfig, RecoveryIncident, RecoveryNode, RecoveryPhase,
+    SelectionPolicy, ShellCommand, SpawnProgress, TaskRun, slot_order,
 };
-use crate::integrations::{cloud_interface::CloudResourceManager, providers::aws::AwsInterface};
-use crate::utils::{self, ssh::SshSession};
+use crate::integrations::cloud_interface::{
+    CloudProvider, CloudResourceManager, NetworkInterfaceRelease, SpotInterruptionQueue,
+    SpotPricing,
+};
+use crate::utils;
+use crate::utils::command_runner::CommandRunner;
+use crate::utils::health_probe::HealthProbe;
+use crate::utils::launcher::{CheckpointSignal, Launcher, SignalScope};
+use crate::utils::slurm;
 
 use anyhow::Result;
-use chrono::Utc;
+use chrono::{NaiveDateTime, Utc};
 use sqlx::sqlite::SqlitePool;
+use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::task::JoinHandle;
-use tokio::time::{Duration, sleep};
+use tokio::task::{JoinHandle, JoinSet};
+use tokio::time::{Duration, Instant, sleep};
 
+/// Monitors a cluster until it is terminated or `control` is asked to stop,
+/// holding the cluster's watch lock meanwhile.
 pub async fn watch(
     pool: &SqlitePool,
     cluster_id: &str,
     interval_secs: u64,
     tasks_yaml: Option<&str>,
     no_replace: bool,
+    proactive: bool,
+    control: &WatchControl,
+) -> Result<()> {
+    if Cluster::fetch_by_id(pool, cluster_id).await?.is_none() {
+        anyhow::bail!("Cluster (id='{}') not found", cluster_id);
+    }
+    let monitor = monitor(
+        pool,
+        cluster_id,
+        interval_secs,
+        tasks_yaml,
+        no_replace,
+        proactive,
+        control,
+    );
+    run_locked(pool, cluster_id, control, monitor).await
+}
+
+async fn monitor(
+    pool: &SqlitePool,
+    cluster_id: &str,
+    interval_secs: u64,
+    tasks_yaml: Option<&str>,
+    no_replace: bool,
+    proactive: bool,
+    control: &WatchControl,
 ) -> Result<()> {
     let cluster = match Cluster::fetch_by_id(pool, cluster_id).await? {
         Some(c) => c,
@@ -30,20 +70,19 @@ pub async fn watch(
             None => anyhow::bail!("ProviderConfig not found for Cluster (id='{}')", cluster_id),
         };
 
-    let config_vars = provider_config.get_config_vars(pool).await?;
-    let cloud_interface = match provider_config.provider_id.as_str() {
-        "aws" => AwsInterface { config_vars },
-        _ => anyhow::bail!(
-            "Provider '{}' is currently not supported.",
-            &provider_config.provider_id
-        ),
-    };
+    let cloud_interface = CloudProvider::from_provider_config(pool, &provider_config).await?;
+    let spot_queue = cloud_interface.spot_interruption_queue();
 
     // Discover the spot interruption queue created at spawn time (None if no spot nodes)
-    let queue_url = match cloud_interface
-        .get_spot_interruption_queue_url(cluster_id, &cluster.region)
-        .await
-    {
+    let queue_url = match spot_queue {
+        Some(spot_queue) => {
+            spot_queue
+                .get_spot_interruption_queue_url(cluster_id, &cluster.region)
+                .await
+        }
+        None => Ok(None),
+    };
+    let queue_url = match queue_url {
         Ok(Some(url)) => {
             tracing::info!("Spot interruption alerts active.");
             Some(url)
@@ -58,12 +97,26 @@ pub async fn watch(
         }
     };
 
+    let health_probes: Vec<HealthProbe> = match &cluster.health_probes {
+        Some(health_probes) => serde_json::from_str(health_probes)?,
+        None => Vec::new(),
+    };
+    if !health_probes.is_empty() {
+        let described: Vec<String> = health_probes.iter().map(|p| p.describe()).collect();
+        tracing::info!("Health probes: {}", described.join(", "));
+    }
+
     if tasks_yaml.is_some() {
         tracing::info!("Automatic relaunch enabled after restore.");
     }
     if no_replace {
         tracing::info!("Scale-down mode: failed nodes will be removed and job relaunched on remaining nodes.");
     }
+    if proactive {
+        tracing::info!(
+            "Proactive mode: noticed nodes are replaced right away and retired when they die."
+        );
+    }
 
     tracing::info!(
         "Monitoring Cluster '{}' every {}s. Press Ctrl+C to stop.",
@@ -71,12 +124,22 @@ pub async fn watch(
         interval_secs
     );
 
+    let interval = Duration::from_secs(interval_secs);
     let private_key_path = utils::expand_tilde(&cluster.private_ssh_key_path);
     let pool = Arc::new(pool.clone());
-    let tasks_yaml: Option<Arc<str>> = tasks_yaml.map(|s| Arc::from(s));
+    let tasks_yaml: Option<Arc<str>> = tasks_yaml.map(Arc::from);
 
     // Tracks any in-flight tasks run so we can abort it before triggering a new restore
     let mut tasks_handle: Option<JoinHandle<()>> = None;
+    // When each node got its interruption notice, so the incident opened when the
+    // node fails can say how much warning there was.
+    let mut spot_notices: HashMap<String, NaiveDateTime> = HashMap::new();
+    // Proactive mode: the node launched in place of each noticed node, by the
+    // noticed node's private IP. Kept in memory only, so a monitor started after
+    // a replacement went up restores the noticed node like any other.
+    let mut replaced_by: HashMap<String, String> = HashMap::new();
+    // Consecutive failures of each (node private IP, probe index).
+    let mut probe_failures: HashMap<(String, usize), u32> = HashMap::new();
 
     loop {
         let cluster = match Cluster::fetch_by_id(&pool, cluster_id).await? {
@@ -101,7 +164,9 @@ pub async fn watch(
                     cluster_id,
                     cluster.state
                 );
-                sleep(Duration::from_secs(interval_secs)).await;
+                if control.stop_requested_within(interval).await {
+                    break;
+                }
                 continue;
             }
             ClusterState::Running => {}
@@ -112,14 +177,18 @@ pub async fn watch(
                     cluster_id,
                     other
                 );
-                sleep(Duration::from_secs(interval_secs)).await;
+                if control.stop_requested_within(interval).await {
+                    break;
+                }
                 continue;
             }
         }
 
         // Check for incoming spot interruption notices before the reactive health check
-        if let Some(url) = &queue_url {
-            match cloud_interface
+        if let Some(url) = &queue_url
+            && let Some(spot_queue) = spot_queue
+        {
+            match spot_queue
                 .poll_spot_interruption_queue(cluster_id, &cluster.region, url)
                 .await
             {
@@ -130,7 +199,47 @@ pub async fn watch(
                         interrupted_ips.len(),
                         interrupted_ips
                     );
-                    signal_mpi_checkpoint(&pool, &cluster, &private_key_path).await;
+                    for ip in &interrupted_ips {
+                        spot_notices
+                            .entry(ip.clone())
+                            .or_insert_with(|| Utc::now().naive_utc());
+                        let node = Node::fetch_by_private_ip(&pool, &cluster.id, ip).await?;
+                        ClusterEvent::record(
+                            &*pool,
+                            &cluster.id,
+                            ClusterEventType::SpotNoticeReceived,
+                            node.as_ref().map(|n| n.id.as_str()),
+                            &format!("Spot interruption notice for '{}'", ip),
+                            Some(serde_json::json!({ "private_ip": ip })),
+                        )
+                        .await?;
+                    }
+                    signal_mpi_checkpoint(&pool, &cloud_interface, &cluster, &private_key_path).await;
+                    if proactive {
+                        let noticed_ips: Vec<String> = interrupted_ips
+                            .into_iter()
+                            .filter(|ip| !replaced_by.contains_key(ip))
+                            .collect();
+                        if !noticed_ips.is_empty() {
+                            control.set_restore(Some("proactive"));
+                            match provision_replacements(
+                                &pool,
+                                &cloud_interface,
+                                cluster_id,
+                                &noticed_ips,
+                            )
+                            .await
+                            {
+                                Ok(replacements) => replaced_by.extend(replacements),
+                                Err(e) => tracing::warn!(
+                                    "[{}] Could not launch replacement(s) ahead of the interruption, the noticed node(s) will be restored when they fail: {}",
+                                    Utc::now().format("%H:%M:%S"),
+                                    e
+                                ),
+                            }
+                            control.set_restore(None);
+                        }
+                    }
                 }
                 Ok(_) => {}
                 Err(e) => {
@@ -143,8 +252,51 @@ pub async fn watch(
             }
         }
 
-        match cloud_interface.check_cluster_health(&pool, &cluster).await {
+        // Why each failed node failed, for nodes that failed a health probe.
+        let mut failure_reasons: HashMap<String, String> = HashMap::new();
+        let health = match cloud_interface.check_cluster_health(&pool, &cluster).await {
+            Ok(mut failed_ips) if !health_probes.is_empty() => {
+                let unhealthy = run_health_probes(
+                    &pool,
+                    &cloud_interface,
+                    &cluster,
+                    &health_probes,
+                    &failed_ips,
+                    &private_key_path,
+                    &mut probe_failures,
+                )
+                .await?;
+                for (ip, reason) in unhealthy {
+                    tracing::warn!(
+                        "[{}] Node '{}' failed a health probe: {} — terminating it for a restore...",
+                        Utc::now().format("%H:%M:%S"),
+                        ip,
+                        reason
+                    );
+                    // The instance is still running, so the restore would find it
+                    // and launch nothing. Terminated, it is replaced like one the
+                    // provider reclaimed.
+                    let Some(cluster) = Cluster::fetch_by_id(&pool, cluster_id).await? else {
+                        break;
+                    };
+                    if let Err(e) = cloud_interface
+                        .simulate_cluster_failure(&pool, cluster, &ip)
+                        .await
+                    {
+                        tracing::warn!("Could not terminate unhealthy node '{}': {}", ip, e);
+                        continue;
+                    }
+                    failed_ips.push(ip.clone());
+                    failure_reasons.insert(ip, reason);
+                }
+                Ok(failed_ips)
+            }
+            other => other,
+        };
+
+        match health {
             Ok(failed_ips) if failed_ips.is_empty() => {
+                control.record_health_check("all nodes healthy");
                 tracing::info!(
                     "[{}] Cluster '{}' — all nodes healthy.",
                     Utc::now().format("%H:%M:%S"),
@@ -152,6 +304,88 @@ pub async fn watch(
                 );
             }
             Ok(failed_ips) => {
+                control.record_health_check(&format!(
+                    "{} failed node(s): {}",
+                    failed_ips.len(),
+                    failed_ips.join(", ")
+                ));
+                // Noticed nodes whose replacement is already up are retired
+                // instead of restored.
+                let (retired_ips, failed_ips): (Vec<String>, Vec<String>) = failed_ips
+                    .into_iter()
+                    .partition(|ip| replaced_by.contains_key(ip));
+                if !retired_ips.is_empty() {
+                    tracing::warn!(
+                        "[{}] Noticed node(s) {:?} died, retiring them in favour of their replacement(s)...",
+                        Utc::now().format("%H:%M:%S"),
+                        retired_ips
+                    );
+                    control.set_restore(Some("proactive"));
+                    if let Some(handle) = tasks_handle.take() {
+                        handle.abort();
+                        control.track_tasks(None);
+                        tracing::info!("Aborted previous tasks run.");
+                    }
+                    retire_nodes(&pool, &cluster, &retired_ips, &replaced_by).await?;
+                    for ip in &retired_ips {
+                        replaced_by.remove(ip);
+                    }
+                    refresh_cost(&pool, &cloud_interface, &cluster.id).await;
+
+                    if failed_ips.is_empty() {
+                        let notice_received_at = retired_ips
+                            .iter()
+                            .filter_map(|ip| spot_notices.remove(ip))
+                            .min();
+                        RecoveryIncident::open(
+                            &pool,
+                            &cluster.id,
+                            "proactive",
+                            &retired_ips,
+                            notice_received_at,
+                        )
+                        .await?;
+                        if cluster.scheduler.as_deref() == Some("slurm") {
+                            reconfigure_slurm(&pool, &cloud_interface, &cluster, &private_key_path)
+                                .await;
+                        }
+                        if let Some(yaml_path) = tasks_yaml.clone() {
+                            tasks_handle =
+                                Some(relaunch_tasks(&pool, &cluster.id, yaml_path, control).await?);
+                        }
+                        RecoveryIncident::finish(&pool, &cluster.id, "recovered", None).await?;
+                    }
+
+                    // The job already runs again, so waiting on the dead
+                    // instances' network interfaces delays nothing but the
+                    // cleanup.
+                    if let Some(eni_release) = cloud_interface.network_interface_release() {
+                        eni_release
+                            .wait_for_enis_released(
+                                &cluster.region,
+                                &retired_ips,
+                                Duration::from_secs(180),
+                            )
+                            .await;
+                        for ip in &retired_ips {
+                            if let Err(e) = eni_release
+                                .delete_detached_eni_by_private_ip(&cluster.region, ip)
+                                .await
+                            {
+                                tracing::warn!("Could not delete ENI for node '{}': {}", ip, e);
+                            }
+                        }
+                    }
+
+                    if failed_ips.is_empty() {
+                        control.set_restore(None);
+                        if control.stop_requested_within(interval).await {
+                            break;
+                        }
+                        continue;
+                    }
+                }
+
                 tracing::warn!(
                     "[{}] Detected {} failed node(s): {:?} — triggering restore...",
                     Utc::now().format("%H:%M:%S"),
@@ -162,13 +396,31 @@ pub async fn watch(
                 // Abort any in-flight tasks run — the MPI job is already dead
                 if let Some(handle) = tasks_handle.take() {
                     handle.abort();
+                    control.track_tasks(None);
                     tracing::info!("Aborted previous tasks run.");
                 }
 
                 for ip in &failed_ips {
-                    match Node::fetch_by_private_ip(&pool, &cluster.id, ip).await? {
+                    let node = Node::fetch_by_private_ip(&pool, &cluster.id, ip).await?;
+                    let reason = failure_reasons
+                        .get(ip)
+                        .map(|reason| reason.as_str())
+                        .unwrap_or("instance is no longer running");
+                    ClusterEvent::record(
+                        &*pool,
+                        &cluster.id,
+                        ClusterEventType::NodeFailed,
+                        node.as_ref().map(|n| n.id.as_str()),
+                        &format!("Node '{}' failed its health check: {}", ip, reason),
+                        Some(serde_json::json!({ "private_ip": ip, "reason": reason })),
+                    )
+                    .await?;
+                    match node {
                         Some(node) => {
                             node.set_efs_configuration_state(&pool, false).await?;
+                            // Its replacement must be set up from scratch.
+                            SpawnProgress::clear_instance_steps(&pool, &cluster.id, &node.id)
+                                .await?;
                             tracing::info!(
                                 "Marked node (private_ip='{}') for EFS re-configuration",
                                 ip
@@ -183,6 +435,28 @@ pub async fn watch(
                     }
                 }
 
+                let mode = if no_replace { "scale_down" } else { "replace" };
+                control.set_restore(Some(mode));
+                ClusterEvent::record(
+                    &*pool,
+                    &cluster.id,
+                    ClusterEventType::RestoreStarted,
+                    None,
+                    &format!(
+                        "Restore started ({}) for {} failed node(s)",
+                        mode,
+                        failed_ips.len()
+                    ),
+                    Some(serde_json::json!({ "mode": mode, "failed_ips": failed_ips })),
+                )
+                .await?;
+                let notice_received_at = failed_ips
+                    .iter()
+                    .filter_map(|ip| spot_notices.remove(ip))
+                    .min();
+                RecoveryIncident::open(&pool, &cluster.id, mode, &failed_ips, notice_received_at)
+                    .await?;
+
                 // A replacement reuses the failed node's network interface, so it
                 // cannot launch until the terminating instance releases it. Poll
                 // for that rather than sleeping a fixed interval: the detach is
@@ -197,9 +471,13 @@ pub async fn watch(
                     "[{}] Waiting for failed instance(s) to release network interfaces...",
                     Utc::now().format("%H:%M:%S")
                 );
-                cloud_interface
-                    .wait_for_enis_released(&cluster.region, &failed_ips, Duration::from_secs(180))
-                    .await;
+                if let Some(eni_release) = cloud_interface.network_interface_release() {
+                    eni_release
+                        .wait_for_enis_released(&cluster.region, &failed_ips, Duration::from_secs(180))
+                        .await;
+                    RecoveryIncident::record_phase(&pool, &cluster.id, RecoveryPhase::EnisReleased)
+                        .await?;
+                }
 
                 if no_replace {
                     // Scale-down: remove failed nodes from DB and relaunch on survivors.
@@ -207,9 +485,10 @@ pub async fn watch(
                     // delete_by_private_ip, but the standalone ENI persists in AWS and
                     // blocks security-group/subnet deletion on cluster termination.
                     for ip in &failed_ips {
-                        if let Err(e) = cloud_interface
-                            .delete_detached_eni_by_private_ip(&cluster.region, ip)
-                            .await
+                        if let Some(eni_release) = cloud_interface.network_interface_release()
+                            && let Err(e) = eni_release
+                                .delete_detached_eni_by_private_ip(&cluster.region, ip)
+                                .await
                         {
                             tracing::warn!("Could not delete ENI for node '{}': {}", ip, e);
                         }
@@ -220,46 +499,90 @@ pub async fn watch(
                         }
                     }
                     cluster.update_state(&pool, ClusterState::Running).await?;
+                    // A restore reconfigures Slurm as part of the spawn; a
+                    // scale-down has to drop the removed nodes from it here.
+                    if cluster.scheduler.as_deref() == Some("slurm") {
+                        reconfigure_slurm(&pool, &cloud_interface, &cluster, &private_key_path)
+                            .await;
+                    }
+                    refresh_cost(&pool, &cloud_interface, &cluster.id).await;
+                    let remaining = cluster.get_nodes(&pool).await?.len();
+                    ClusterEvent::record(
+                        &*pool,
+                        &cluster.id,
+                        ClusterEventType::RestoreFinished,
+                        None,
+                        &format!("Scale-down complete, {} node(s) remaining", remaining),
+                        Some(serde_json::json!({ "mode": mode, "node_count": remaining })),
+                    )
+                    .await?;
                     tracing::info!(
                         "[{}] Scale-down complete — {} node(s) remaining. Relaunching job...",
                         Utc::now().format("%H:%M:%S"),
-                        cluster.get_nodes(&pool).await?.len()
+                        remaining
                     );
                     if let Some(yaml_path) = tasks_yaml.clone() {
-                        let pool_clone = Arc::clone(&pool);
-                        tasks_handle = Some(tokio::spawn(async move {
-                            tracing::info!("Relaunching MPI job from '{}'...", yaml_path);
-                            if let Err(e) = tasks(&pool_clone, &yaml_path).await {
-                                tracing::warn!("Tasks relaunch ended: {}", e);
-                            }
-                        }));
+                        tasks_handle =
+                            Some(relaunch_tasks(&pool, &cluster.id, yaml_path, control).await?);
                     }
+                    RecoveryIncident::finish(&pool, &cluster.id, "recovered", None).await?;
                 } else {
-                    let nodes = apply_recovery_policy(
+                    // A restore whose slots have no type within the price
+                    // ceiling fails like one that cannot launch; the next pass
+                    // tries again at the prices of then.
+                    let nodes = cluster.get_nodes(&pool).await?;
+                    let restore = match apply_recovery_policy(
                         &pool,
+                        &cloud_interface,
                         &cluster,
-                        cluster.get_nodes(&pool).await?,
+                        nodes,
                         &failed_ips,
                     )
-                    .await?;
-
-                    match cloud_interface.spawn_cluster(&pool, cluster, nodes).await {
+                    .await
+                    {
+                        Ok(nodes) => cloud_interface.spawn_cluster(&pool, cluster, nodes).await,
+                        Err(e) => Err(e),
+                    };
+                    match restore {
                         Ok(()) => {
+                            refresh_cost(&pool, &cloud_interface, cluster_id).await;
+                            ClusterEvent::record(
+                                &*pool,
+                                cluster_id,
+                                ClusterEventType::RestoreFinished,
+                                None,
+                                "Restore completed",
+                                Some(serde_json::json!({ "mode": mode })),
+                            )
+                            .await?;
                             tracing::info!(
                                 "[{}] Restore completed successfully.",
                                 Utc::now().format("%H:%M:%S")
                             );
                             if let Some(yaml_path) = tasks_yaml.clone() {
-                                let pool_clone = Arc::clone(&pool);
-                                tasks_handle = Some(tokio::spawn(async move {
-                                    tracing::info!("Relaunching MPI job from '{}'...", yaml_path);
-                                    if let Err(e) = tasks(&pool_clone, &yaml_path).await {
-                                        tracing::warn!("Tasks relaunch ended: {}", e);
-                                    }
-                                }));
+                                tasks_handle = Some(
+                                    relaunch_tasks(&pool, cluster_id, yaml_path, control).await?,
+                                );
                             }
+                            RecoveryIncident::finish(&pool, cluster_id, "recovered", None).await?;
                         }
                         Err(e) => {
+                            ClusterEvent::record(
+                                &*pool,
+                                cluster_id,
+                                ClusterEventType::RestoreFailed,
+                                None,
+                                &format!("Restore failed: {}", e),
+                                Some(serde_json::json!({ "mode": mode, "error": e.to_string() })),
+                            )
+                            .await?;
+                            RecoveryIncident::finish(
+                                &pool,
+                                cluster_id,
+                                "failed",
+                                Some(&e.to_string()),
+                            )
+                            .await?;
                             tracing::error!(
                                 "[{}] Restore failed: {}",
                                 Utc::now().format("%H:%M:%S"),
@@ -270,6 +593,7 @@ pub async fn watch(
                 }
             }
             Err(e) => {
+                control.record_health_check(&format!("error: {}", e));
                 tracing::warn!(
                     "[{}] Health check error (will retry): {}",
                     Utc::now().format("%H:%M:%S"),
@@ -277,18 +601,377 @@ pub async fn watch(
                 );
             }
         }
+        control.set_restore(None);
 
-        sleep(Duration::from_secs(interval_secs)).await;
+        if control.stop_requested_within(interval).await {
+            break;
+        }
     }
 
     Ok(())
 }
 
+/// Runs the cluster's health probes on every node not in `failed_ips` and
+/// returns the nodes that just reached a probe's failure threshold, with why.
+/// `failures` carries each (node, probe) failure streak across passes.
+///
+/// Workers are reached through the head node, so while the head does not
+/// answer their probes would fail for the head's sake. Only the head's own
+/// failures count then.
+async fn run_health_probes(
+    pool: &SqlitePool,
+    cloud_interface: &CloudProvider,
+    cluster: &Cluster,
+    health_probes: &[HealthProbe],
+    failed_ips: &[String],
+    private_key_path: &str,
+    failures: &mut HashMap<(String, usize), u32>,
+) -> Result<Vec<(String, String)>> {
+    let mut nodes = cluster.get_nodes(pool).await?;
+    slot_order(&mut nodes);
+    let Some(head_public_ip) = nodes.first().and_then(|n| n.public_ip.clone()) else {
+        return Ok(Vec::new());
+    };
+
+    let mut probes = JoinSet::new();
+    for node in &nodes {
+        let Some(private_ip) = node.private_ip.clone() else {
+            continue;
+        };
+        if private_ip.is_empty() || failed_ips.contains(&private_ip) {
+            continue;
+        }
+        let runner = cloud_interface.command_runner(
+            &private_ip,
+            node.public_ip.as_deref(),
+            &head_public_ip,
+            private_key_path,
+        );
+        let health_probes = health_probes.to_vec();
+        probes.spawn(async move {
+            let mut results = Vec::new();
+            for health_probe in &health_probes {
+                results.push(health_probe.run(&runner).await);
+            }
+            (private_ip, results)
+        });
+    }
+    let mut results: HashMap<String, Vec<Result<(), String>>> = HashMap::new();
+    while let Some(joined) = probes.join_next().await {
+        match joined {
+            Ok((private_ip, node_results)) => {
+                results.insert(private_ip, node_results);
+            }
+            Err(e) => tracing::warn!("Health probe task failed: {}", e),
+        }
+    }
+
+    let head_ip = nodes.first().and_then(|n| n.private_ip.clone());
+    let head_unreachable = head_ip
+        .as_ref()
+        .and_then(|ip| results.get(ip))
+        .is_some_and(|head_results| head_results.iter().all(|r| r.is_err()));
+    if head_unreachable {
+        tracing::warn!("Head node fails every health probe, skipping the workers' results.");
+        results.retain(|ip, _| Some(ip) == head_ip.as_ref());
+    }
+
+    let mut unhealthy = Vec::new();
+    for (private_ip, node_results) in results {
+        let mut reason = None;
+        for (index, result) in node_results.into_iter().enumerate() {
+            let key = (private_ip.clone(), index);
+            match result {
+                Ok(()) => {
+                    failures.remove(&key);
+                }
+                Err(e) => {
+                    let streak = failures.entry(key).or_insert(0);
+                    *streak += 1;
+                    let threshold = health_probes[index].failure_threshold();
+                    tracing::warn!(
+                        "Health probe failed on '{}' ({}/{}): {}",
+                        private_ip,
+                        streak,
+                        threshold,
+                        e
+                    );
+                    if *streak >= threshold && reason.is_none() {
+                        reason = Some(e);
+                    }
+                }
+            }
+        }
+        if let Some(reason) = reason {
+            // Its replacement starts over.
+            failures.retain(|(ip, _), _| *ip != private_ip);
+            unhealthy.push((private_ip, reason));
+        }
+    }
+    Ok(unhealthy)
+}
+
+/// Starts the tasks of `yaml_path` again in the background, recording the
+/// relaunch on the cluster's open recovery incident.
+async fn relaunch_tasks(
+    pool: &Arc<SqlitePool>,
+    cluster_id: &str,
+    yaml_path: Arc<str>,
+    control: &WatchControl,
+) -> Result<JoinHandle<()>> {
+    RecoveryIncident::record_phase(pool, cluster_id, RecoveryPhase::TaskRelaunched).await?;
+    let pool_clone = Arc::clone(pool);
+    let handle = tokio::spawn(async move {
+        tracing::info!("Relaunching MPI job from '{}'...", yaml_path);
+        if let Err(e) = tasks(&pool_clone, &yaml_path, true, false).await {
+            tracing::warn!("Tasks relaunch ended: {}", e);
+        }
+    });
+    control.track_tasks(Some(&handle));
+    Ok(handle)
+}
+
+/// Proactive mode: launches a replacement for each of the `noticed_ips` as a
+/// new node of the cluster while the noticed nodes still run the job.
+///
+/// A replacement is spawned into a network interface and private IP of its
+/// own, so unlike a restore it does not wait for the reclaimed instance to let
+/// go of its address. Its spec comes from the noticed node's recovery slot when
+/// one is declared, and is otherwise a copy of the noticed node. Returns
+/// (noticed IP, replacement node id) pairs.
+///
+/// The head node is left to the restore: the cluster's public address belongs
+/// to slot 0, which a replacement under a new index would not take over.
+async fn provision_replacements(
+    pool: &SqlitePool,
+    cloud_interface: &CloudProvider,
+    cluster_id: &str,
+    noticed_ips: &[String],
+) -> Result<Vec<(String, String)>> {
+    let cluster = match Cluster::fetch_by_id(pool, cluster_id).await? {
+        Some(c) => c,
+        None => anyhow::bail!("Cluster (id='{}') not found", cluster_id),
+    };
+    let recovery_nodes = RecoveryNode::fetch_all_by_cluster_id(pool, cluster_id).await?;
+    let mut nodes = cluster.get_nodes(pool).await?;
+    slot_order(&mut nodes);
+
+    // (noticed IP, replacement, its init commands)
+    let mut replacements: Vec<(String, Node, Vec<String>)> = Vec::new();
+    let mut node_index = next_node_index(&nodes);
+    for (slot_index, node) in nodes.iter().enumerate() {
+        let Some(ip) = node.private_ip.clone() else {
+            continue;
+        };
+        if !noticed_ips.contains(&ip) {
+            continue;
+        }
+        if slot_index == 0 {
+            tracing::info!(
+                "Noticed node '{}' is the head node, it will be restored when it fails",
+                ip
+            );
+            continue;
+        }
+        let recovery = match recovery_nodes.get(slot_index) {
+            Some(recovery) if recovery.primary_instance_type().is_some() => {
+                let launch_order = select_instance_types(
+                    pool,
+                    cloud_interface,
+                    &cluster,
+                    slot_index,
+                    &node.id,
+                    recovery,
+                )
+                .await?;
+                Some((recovery, launch_order[0].clone()))
+            }
+            _ => None,
+        };
+        let (replacement, declared) = match recovery {
+            Some((recovery, instance_type)) => (
+                Node {
+                    id: utils::generate_id(),
+                    cluster_id: cluster.id.clone(),
+                    instance_type,
+                    allocation_mode: recovery.allocation_mode.clone(),
+                    burstable_mode: recovery.burstable_mode.clone(),
+                    image_id: recovery.image_id.clone(),
+                    root_volume_gb: recovery.root_volume_gb,
+                    root_volume_type: recovery.root_volume_type.clone(),
+                    root_volume_iops: recovery.root_volume_iops,
+                    private_ip: None,
+                    public_ip: None,
+                    was_efs_configured: false,
+                    was_ssh_configured: false,
+                    // Recovery slots carry no spot options.
+                    spot_max_price: None,
+                    spot_request_type: None,
+                    spot_interruption_behavior: None,
+                    market_type: None,
+                    efa_available: None,
+                    availability_zone: None,
+                    node_index,
+                },
+                recovery.declared_init_commands(),
+            ),
+            None => (
+                Node {
+                    id: utils::generate_id(),
+                    cluster_id: cluster.id.clone(),
+                    instance_type: node.instance_type.clone(),
+                    allocation_mode: node.allocation_mode.clone(),
+                    burstable_mode: node.burstable_mode.clone(),
+                    image_id: node.image_id.clone(),
+                    root_volume_gb: node.root_volume_gb,
+                    root_volume_type: node.root_volume_type.clone(),
+                    root_volume_iops: node.root_volume_iops,
+                    private_ip: None,
+                    public_ip: None,
+                    was_efs_configured: false,
+                    was_ssh_configured: false,
+                    spot_max_price: node.spot_max_price,
+                    spot_request_type: node.spot_request_type.clone(),
+                    spot_interruption_behavior: node.spot_interruption_behavior.clone(),
+                    market_type: None,
+                    efa_available: None,
+                    availability_zone: None,
+                    node_index,
+                },
+                None,
+            ),
+        };
+        node_index += 1;
+        let init_commands = match declared {
+            Some(commands) => commands,
+            None => node.get_init_commands(pool).await?,
+        };
+        tracing::info!(
+            "Launching a replacement for noticed node '{}' (slot {}): {} / {}",
+            ip,
+            slot_index,
+            replacement.instance_type,
+            replacement.allocation_mode
+        );
+        replacements.push((ip, replacement, init_commands));
+    }
+    if replacements.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut tx = pool.begin().await?;
+    for (_, replacement, init_commands) in &replacements {
+        replacement.insert(&mut tx).await?;
+        if !init_commands.is_empty() {
+            ShellCommand::replace_all_for_node(&mut tx, &replacement.id, init_commands).await?;
+        }
+    }
+    tx.commit().await?;
+
+    let noticed: Vec<&str> = replacements.iter().map(|(ip, _, _)| ip.as_str()).collect();
+    ClusterEvent::record(
+        pool,
+        &cluster.id,
+        ClusterEventType::RestoreStarted,
+        None,
+        &format!(
+            "Proactive replacement started for {} noticed node(s)",
+            noticed.len()
+        ),
+        Some(serde_json::json!({ "mode": "proactive", "noticed_ips": noticed })),
+    )
+    .await?;
+
+    let nodes = cluster.get_nodes(pool).await?;
+    match cloud_interface.spawn_cluster(pool, cluster, nodes).await {
+        Ok(()) => {
+            refresh_cost(pool, cloud_interface, cluster_id).await;
+            ClusterEvent::record(
+                pool,
+                cluster_id,
+                ClusterEventType::RestoreFinished,
+                None,
+                "Proactive replacement completed",
+                Some(serde_json::json!({ "mode": "proactive" })),
+            )
+            .await?;
+            Ok(replacements
+                .into_iter()
+                .map(|(ip, replacement, _)| (ip, replacement.id))
+                .collect())
+        }
+        Err(e) => {
+            ClusterEvent::record(
+                pool,
+                cluster_id,
+                ClusterEventType::RestoreFailed,
+                None,
+                &format!("Proactive replacement failed: {}", e),
+                Some(serde_json::json!({ "mode": "proactive", "error": e.to_string() })),
+            )
+            .await?;
+            // The noticed nodes still run the job, so the cluster goes back to
+            // Running without the replacements and restores them as usual.
+            for (_, replacement, _) in &replacements {
+                if let Err(e) = replacement.delete(pool).await {
+                    tracing::warn!("Could not remove replacement '{}': {}", replacement.id, e);
+                }
+            }
+            if let Some(cluster) = Cluster::fetch_by_id(pool, cluster_id).await? {
+                cluster.update_state(pool, ClusterState::Running).await?;
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Proactive mode: removes noticed nodes that died from the cluster, their
+/// replacements having taken their place. The network interfaces they leave
+/// are cleaned up by the caller once the job runs again.
+async fn retire_nodes(
+    pool: &SqlitePool,
+    cluster: &Cluster,
+    retired_ips: &[String],
+    replaced_by: &HashMap<String, String>,
+) -> Result<()> {
+    for ip in retired_ips {
+        let node = Node::fetch_by_private_ip(pool, &cluster.id, ip).await?;
+        ClusterEvent::record(
+            pool,
+            &cluster.id,
+            ClusterEventType::NodeFailed,
+            node.as_ref().map(|n| n.id.as_str()),
+            &format!("Node '{}' failed its health check", ip),
+            Some(serde_json::json!({ "private_ip": ip })),
+        )
+        .await?;
+        let Some(node) = node else {
+            tracing::warn!("No node record found for private_ip='{}', skipping.", ip);
+            continue;
+        };
+        node.delete(pool).await?;
+        ClusterEvent::record(
+            pool,
+            &cluster.id,
+            ClusterEventType::NodeRetired,
+            Some(&node.id),
+            &format!("Node '{}' retired, its replacement took over", ip),
+            Some(serde_json::json!({
+                "private_ip": ip,
+                "replacement_node_id": replaced_by.get(ip),
+            })),
+        )
+        .await?;
+        tracing::info!("Retired noticed node '{}' from cluster.", ip);
+    }
+    Ok(())
+}
+
 /// Applies the cluster's recovery policy to failed node slots.
 ///
-/// Nodes are sorted by private IP (consistent with tasks.rs) to establish stable
-/// slot indices. For each failed node, the corresponding recovery node slot (by
-/// index) is used to update the node's instance type and allocation mode in the DB.
+/// Nodes are put in slot order to establish stable slot indices. For each
+/// failed node, the corresponding recovery node slot (by index) is used to
+/// update the node's instance type and allocation mode in the DB.
 ///
 /// If a recovery slot specifies `count > 1`, additional Node rows are inserted with
 /// the same replacement spec, expanding the cluster on restart (scale-up recovery).
@@ -298,6 +981,7 @@ pub async fn watch(
 /// Returns the refreshed node list to pass to spawn_cluster.
 async fn apply_recovery_policy(
     pool: &SqlitePool,
+    cloud_interface: &CloudProvider,
     cluster: &Cluster,
     mut nodes: Vec<Node>,
     failed_ips: &[String],
@@ -307,21 +991,19 @@ async fn apply_recovery_policy(
         return Ok(nodes);
     }
 
-    nodes.sort_by(|a, b| a.private_ip.cmp(&b.private_ip));
+    slot_order(&mut nodes);
 
     // Snapshot node identity we need across the loop (avoids borrow issues when we
     // later start a transaction to insert additional Node rows).
     #[derive(Clone)]
     struct NodeSlot {
         id: String,
-        cluster_id: String,
         private_ip: Option<String>,
     }
     let node_slots: Vec<NodeSlot> = nodes
         .iter()
         .map(|n| NodeSlot {
             id: n.id.clone(),
-            cluster_id: n.cluster_id.clone(),
             private_ip: n.private_ip.clone(),
         })
         .collect();
@@ -357,17 +1039,27 @@ async fn apply_recovery_policy(
             );
             continue;
         };
-        let Some(instance_type) = recovery.primary_instance_type() else {
+        if recovery.primary_instance_type().is_none() {
             tracing::warn!(
                 "Recovery slot {} has no preferred_instance_types, keeping original spec",
                 slot_index
             );
             continue;
-        };
-        // The spec written below is the preferred type. Any remaining entries are
-        // capacity fallbacks, applied by spawn_cluster at launch time if AWS has
-        // no capacity for the preferred one.
-        let fallbacks: Vec<String> = recovery.instance_types().into_iter().skip(1).collect();
+        }
+        // The spec written below is the type the selection policy ranks first.
+        // The others are capacity fallbacks, applied by spawn_cluster at launch
+        // time, in the same order, if AWS has no capacity for the first one.
+        let launch_order = select_instance_types(
+            pool,
+            cloud_interface,
+            cluster,
+            slot_index,
+            &node.id,
+            recovery,
+        )
+        .await?;
+        let instance_type = launch_order[0].clone();
+        let fallbacks: Vec<String> = launch_order.into_iter().skip(1).collect();
         tracing::info!(
             "Applying recovery policy to slot {} (ip='{}'): {} / {} (count={}){}",
             slot_index,
@@ -391,13 +1083,15 @@ async fn apply_recovery_policy(
         target
             .update_instance_spec(
                 pool,
-                &instance_type,
-                &recovery.allocation_mode,
-                &recovery.image_id,
-                recovery.burstable_mode.as_deref(),
-                recovery.root_volume_gb,
-                &recovery.root_volume_type,
-                recovery.root_volume_iops,
+                &InstanceSpec {
+                    instance_type: &instance_type,
+                    allocation_mode: &recovery.allocation_mode,
+                    image_id: &recovery.image_id,
+                    burstable_mode: recovery.burstable_mode.as_deref(),
+                    root_volume_gb: recovery.root_volume_gb,
+                    root_volume_type: &recovery.root_volume_type,
+                    root_volume_iops: recovery.root_volume_iops,
+                },
             )
             .await?;
 
@@ -459,6 +1153,7 @@ async fn apply_recovery_policy(
     if !fanouts.is_empty() {
         let mut tx = pool.begin().await?;
         let mut total_added = 0i64;
+        let mut node_index = next_node_index(&nodes);
         for fo in &fanouts {
             for _ in 0..fo.extra {
                 let new_node = Node {
@@ -475,8 +1170,18 @@ async fn apply_recovery_policy(
                     public_ip: None,
                     was_efs_configured: false,
                     was_ssh_configured: false,
+                    // Recovery slots carry no spot options; a spot replacement
+                    // launches with AWS's defaults (one-time, capped at on-demand).
+                    spot_max_price: None,
+                    spot_request_type: None,
+                    spot_interruption_behavior: None,
+                    market_type: None,
+                    efa_available: None,
+                    availability_zone: None,
+                    node_index,
                 };
                 new_node.insert(&mut tx).await?;
+                node_index += 1;
                 if !fo.init_commands.is_empty() {
                     ShellCommand::replace_all_for_node(&mut tx, &new_node.id, &fo.init_commands)
                         .await?;
@@ -501,9 +1206,204 @@ async fn apply_recovery_policy(
     Ok(cluster.get_nodes(pool).await?)
 }
 
-/// SSH to the head node and send SIGUSR1 to the running mpirun process so the
-/// application can flush a checkpoint before the spot instance is reclaimed.
-async fn signal_mpi_checkpoint(pool: &SqlitePool, cluster: &Cluster, private_key_path: &str) {
+/// Index for a node added to a cluster of `nodes`: past every index in use,
+/// so it gets a network interface and private IP of its own.
+fn next_node_index(nodes: &[Node]) -> i64 {
+    nodes
+        .iter()
+        .map(|n| n.node_index)
+        .max()
+        .map_or(0, |i| i + 1)
+}
+
+/// Orders a recovery slot's preferred instance types by the cluster's
+/// selection policy, at live spot prices for a spot slot and catalog on-demand
+/// prices otherwise, without those above its price ceiling. The order is kept
+/// on the slot for spawn_cluster and the pick recorded as an event. An
+/// `ordered` policy without a ceiling keeps the declared order unpriced.
+async fn select_instance_types(
+    pool: &SqlitePool,
+    cloud_interface: &CloudProvider,
+    cluster: &Cluster,
+    slot_index: usize,
+    node_id: &str,
+    recovery: &RecoveryNode,
+) -> Result<Vec<String>> {
+    let declared = recovery.instance_types();
+    let policy = cluster.selection_policy();
+    let max_price_per_hour = cluster.recovery_max_price_per_hour;
+    if policy == SelectionPolicy::Ordered && max_price_per_hour.is_none() {
+        return Ok(declared);
+    }
+
+    let is_spot = recovery.allocation_mode == "spot";
+    let live_spot_prices = match cloud_interface.spot_pricing() {
+        Some(spot_pricing) if is_spot => {
+            spot_pricing
+                .fetch_spot_prices(&cluster.region, &declared, &cluster.availability_zone)
+                .await?
+        }
+        _ => HashMap::new(),
+    };
+    let mut candidates = Vec::new();
+    for name in &declared {
+        let details = InstanceType::fetch_by_name_and_region(pool, name, &cluster.region).await?;
+        let on_demand_price = details.as_ref().and_then(|d| d.on_demand_price_per_hour);
+        let price_per_hour = if is_spot {
+            live_spot_prices
+                .get(name)
+                .copied()
+                .or(details.as_ref().and_then(|d| d.spot_price_per_hour))
+                .or(on_demand_price)
+        } else {
+            on_demand_price
+        };
+        candidates.push(PricedInstanceType {
+            name: name.clone(),
+            vcpus: details.map_or(0, |d| d.vcpus),
+            price_per_hour,
+        });
+    }
+
+    let ranked = policy.rank(candidates.clone(), max_price_per_hour);
+    let Some(chosen) = ranked.first() else {
+        anyhow::bail!(
+            "No instance type of recovery slot {} ({}) is priced within max_price_per_hour (${:.4})",
+            slot_index,
+            declared.join(", "),
+            max_price_per_hour.unwrap_or_default()
+        );
+    };
+    let price = chosen
+        .price_per_hour
+        .map_or("an unknown price".to_string(), |p| {
+            format!("${:.4}/hour", p)
+        });
+    tracing::info!(
+        "Recovery slot {} picks '{}' at {} ({})",
+        slot_index,
+        chosen.name,
+        price,
+        policy
+    );
+    ClusterEvent::record(
+        pool,
+        &cluster.id,
+        ClusterEventType::InstanceTypeSelected,
+        Some(node_id),
+        &format!(
+            "Slot {} selected '{}' at {} ({})",
+            slot_index, chosen.name, price, policy
+        ),
+        Some(serde_json::json!({
+            "slot_index": slot_index,
+            "policy": policy.to_string(),
+            "max_price_per_hour": max_price_per_hour,
+            "instance_type": chosen.name,
+            "price_per_hour": chosen.price_per_hour,
+            "candidates": candidates,
+        })),
+    )
+    .await?;
+
+    let launch_order: Vec<String> = ranked.into_iter().map(|c| c.name).collect();
+    recovery
+        .set_selected_instance_types(pool, &launch_order)
+        .await?;
+    Ok(launch_order)
+}
+
+/// Re-prices the cluster once a restore changed its nodes, so cost_per_hour
+/// and cost_breakdown describe what runs now. A failure only leaves the
+/// previous estimate in place.
+async fn refresh_cost(pool: &SqlitePool, cloud_interface: &CloudProvider, cluster_id: &str) {
+    let refreshed = async {
+        let Some(cluster) = Cluster::fetch_by_id(pool, cluster_id).await? else {
+            return anyhow::Ok(());
+        };
+        let nodes = cluster.get_nodes(pool).await?;
+        let cost = estimate_cost(
+            cloud_interface,
+            &cluster.region,
+            &cluster.availability_zone,
+            &nodes,
+        )
+        .await?;
+        cluster
+            .update_cost(pool, cost.total_per_hour, &serde_json::to_string(&cost)?)
+            .await?;
+        tracing::info!(
+            "Cluster cost is now ${:.4}/hour (was ${:.4}/hour)",
+            cost.total_per_hour,
+            cluster.cost_per_hour
+        );
+        anyhow::Ok(())
+    };
+    if let Err(e) = refreshed.await {
+        tracing::warn!("Could not recompute the cluster's cost: {}", e);
+    }
+}
+
+/// Rewrites the Slurm configuration of every node from the current Node rows
+/// and restarts the daemons. Failing leaves Slurm on the old node list, which
+/// only matters to `sbatch` tasks, so it is reported rather than fatal.
+async fn reconfigure_slurm(
+    pool: &SqlitePool,
+    cloud_interface: &CloudProvider,
+    cluster: &Cluster,
+    private_key_path: &str,
+) {
+    let mut nodes = match cluster.get_nodes(pool).await {
+        Ok(n) => n,
+        Err(e) => {
+            tracing::warn!("Could not fetch nodes to reconfigure Slurm: {}", e);
+            return;
+        }
+    };
+    slot_order(&mut nodes);
+    let Some((head_ip, head_public_ip)) = nodes
+        .first()
+        .and_then(|head| head.private_ip.clone().zip(head.public_ip.clone()))
+    else {
+        tracing::warn!("Head node has no public IP, cannot reconfigure Slurm.");
+        return;
+    };
+    let slurm_nodes: Vec<(String, CommandRunner)> = nodes
+        .iter()
+        .filter_map(|node| {
+            let private_ip = node.private_ip.clone()?;
+            let runner = cloud_interface.command_runner(
+                &private_ip,
+                node.public_ip.as_deref(),
+                &head_public_ip,
+                private_key_path,
+            );
+            Some((private_ip, runner))
+        })
+        .collect();
+    if let Err(e) = slurm::configure_slurm(&cluster.id, &head_ip, &slurm_nodes).await {
+        tracing::warn!(
+            "Could not reconfigure Slurm on Cluster '{}': {}",
+            cluster.id,
+            e
+        );
+    }
+}
+
+/// How often a node is asked whether the application acknowledged a checkpoint.
+const CHECKPOINT_ACK_POLL: Duration = Duration::from_secs(2);
+
+/// Ask the application of every task run in progress to flush a checkpoint
+/// before the spot instance is reclaimed. The run's `checkpoint_signal`, else
+/// the cluster's, says how; without either the ranks get SIGUSR1 through the
+/// launcher the run was started with. When an `ack_file` is declared, wait for
+/// the application to create it and report per node whether it did.
+async fn signal_mpi_checkpoint(
+    pool: &SqlitePool,
+    cloud_interface: &CloudProvider,
+    cluster: &Cluster,
+    private_key_path: &str,
+) {
     let mut nodes = match cluster.get_nodes(pool).await {
         Ok(n) => n,
         Err(e) => {
@@ -512,7 +1412,7 @@ async fn signal_mpi_checkpoint(pool: &SqlitePool, cluster: &Cluster, private_key
         }
     };
 
-    nodes.sort_by(|a, b| a.private_ip.cmp(&b.private_ip));
+    slot_order(&mut nodes);
 
     let head_public_ip = match nodes.first().and_then(|n| n.public_ip.as_deref()) {
         Some(ip) => ip.to_string(),
@@ -522,59 +1422,241 @@ async fn signal_mpi_checkpoint(pool: &SqlitePool, cluster: &Cluster, private_key
         }
     };
 
-    // Signal the application ranks directly on every node, rather than the
-    // launcher on the head node.
-    //
-    // The previous approach ran `pkill -USR1 -f mpirun` on the head and relied on
-    // the MPI launcher forwarding the signal to its ranks. Whether it does is
-    // implementation and version dependent (PRRTE exposes an explicit
-    // --forward-signals option, so forwarding is not something to count on), and
-    // the `|| true` meant a pkill that matched nothing still reported success.
-    // The result was a preemptive checkpoint path that logged "signal sent" on
-    // every interruption while the application never saw SIGUSR1 and never
-    // flushed.
-    //
-    // Signalling the ranks removes the launcher from the path entirely. The
-    // application's check is collective, so reaching any one rank is sufficient,
-    // but we signal all of them because a node may be reclaimed mid-sweep.
-    const SIGNAL_CMD: &str = "pkill -USR1 -x starfwi-fwi";
-
-    let mut signalled = 0usize;
-    let mut unreachable = 0usize;
-    for (index, node) in nodes.iter().enumerate() {
-        let Some(private_ip) = node.private_ip.as_deref() else {
+    let runs = match TaskRun::fetch_running(pool, &cluster.id).await {
+        Ok(runs) => runs,
+        Err(e) => {
+            tracing::warn!("Could not fetch task runs for checkpoint signal: {}", e);
+            return;
+        }
+    };
+    if runs.is_empty() {
+        tracing::warn!(
+            "No task run in progress on Cluster '{}', there is no job to checkpoint.",
+            cluster.id
+        );
+        return;
+    }
+
+    let cluster_signal: Option<CheckpointSignal> = cluster
+        .checkpoint_signal
+        .as_deref()
+        .and_then(|c| serde_json::from_str(c).ok());
+
+    // The signal must reach the application ranks. An earlier version ran
+    // `pkill -USR1 -f mpirun || true` whatever the launcher, and the `|| true`
+    // meant a pkill that matched nothing still reported success: the checkpoint
+    // path logged "signal sent" on every interruption while the application
+    // never saw SIGUSR1 and never flushed. Each launcher now says where its
+    // signal command runs, and that command fails when it reached no process.
+    for run in &runs {
+        // Runs recorded before the launcher was kept used Open MPI.
+        let launcher: Launcher = run
+            .launcher
+            .as_deref()
+            .and_then(|l| l.parse().ok())
+            .unwrap_or_default();
+        let checkpoint_signal: CheckpointSignal = run
+            .checkpoint_signal
+            .as_deref()
+            .and_then(|c| serde_json::from_str(c).ok())
+            .or_else(|| cluster_signal.clone())
+            .unwrap_or_default();
+        let Some((scope, command)) = checkpoint_signal.signal_command(launcher) else {
+            tracing::warn!(
+                "Task '{}' run {} uses launcher '{}', its processes cannot be told apart to signal them. \
+                 Declare a checkpoint_signal with a process or a command.",
+                run.task_name,
+                run.run_index,
+                launcher
+            );
             continue;
         };
-        let ssh = if index == 0 {
-            SshSession::for_aws(&head_public_ip, private_key_path)
-        } else {
-            SshSession::for_aws_worker(private_ip, &head_public_ip, private_key_path)
+        let signal = format!("SIG{}", checkpoint_signal.signal());
+
+        // A run recorded without its nodes ran on all of them.
+        let run_node_ids: Vec<String> = run
+            .node_ids
+            .as_deref()
+            .and_then(|ids| serde_json::from_str(ids).ok())
+            .unwrap_or_default();
+        let job_nodes: Vec<&Node> = nodes
+            .iter()
+            .filter(|n| run_node_ids.is_empty() || run_node_ids.contains(&n.id))
+            .collect();
+        let targets: Vec<&Node> = match scope {
+            SignalScope::Head => nodes.iter().take(1).collect(),
+            SignalScope::JobNodes => job_nodes.clone(),
+        };
+        let runner_for = |node: &Node| {
+            node.private_ip.as_deref().map(|private_ip| {
+                cloud_interface.command_runner(
+                    private_ip,
+                    node.public_ip.as_deref(),
+                    &head_public_ip,
+                    private_key_path,
+                )
+            })
         };
-        // pkill exits 1 when nothing matched, which is a real outcome worth
-        // distinguishing from a node we could not reach at all.
-        match ssh.run_command(SIGNAL_CMD).await {
-            Ok(_) => {
-                signalled += 1;
-                tracing::debug!("SIGUSR1 delivered to ranks on '{}'", private_ip);
+
+        // An acknowledgement left over from an earlier notice must not count.
+        if let Some(ack_file) = &checkpoint_signal.ack_file {
+            for node in &job_nodes {
+                if let Some(runner) = runner_for(node) {
+                    let _ = runner.run_command(&format!("rm -f '{}'", ack_file)).await;
+                }
             }
-            Err(e) => {
-                unreachable += 1;
-                tracing::debug!("Could not signal ranks on '{}': {}", private_ip, e);
+        }
+
+        let mut signalled = 0usize;
+        let mut missed = 0usize;
+        for node in &targets {
+            let Some(runner) = runner_for(node) else {
+                continue;
+            };
+            // Nothing matched and node unreachable both fail; either way the
+            // signal did not get there.
+            match runner.run_command(&command).await {
+                Ok(_) => {
+                    signalled += 1;
+                    tracing::debug!("{} delivered on '{}'", signal, node.id);
+                }
+                Err(e) => {
+                    missed += 1;
+                    tracing::debug!("Could not signal '{}': {}", node.id, e);
+                }
             }
         }
-    }
 
-    if signalled > 0 {
+        if signalled == 0 {
+            tracing::warn!(
+                "Preemptive checkpoint signal reached no process of task '{}' run {} ({} node(s) unreachable or no matching process). \
+                 The job will not flush before reclamation and will resume from the last periodic checkpoint.",
+                run.task_name,
+                run.run_index,
+                missed
+            );
+            continue;
+        }
         tracing::info!(
-            "SIGUSR1 delivered to application ranks on {} of {} node(s)",
+            "{} delivered to task '{}' run {} on {} of {} node(s)",
+            signal,
+            run.task_name,
+            run.run_index,
             signalled,
-            nodes.len()
-        );
-    } else {
-        tracing::warn!(
-            "Preemptive checkpoint signal reached no ranks ({} node(s) unreachable or no matching process). \
-             The job will not flush before reclamation and will resume from the last periodic checkpoint.",
-            unreachable
+            targets.len()
         );
+
+        // The notice comes two minutes ahead of the reclaim, so waiting here
+        // holds up nothing the watch loop could usefully do in the meantime.
+        if let Some(ack_file) = &checkpoint_signal.ack_file {
+            let job_runners: Vec<_> = job_nodes
+                .iter()
+                .filter_map(|node| runner_for(node).map(|runner| (*node, runner)))
+                .collect();
+            wait_for_checkpoint_ack(
+                pool,
+                run,
+                &job_runners,
+                ack_file,
+                checkpoint_signal.ack_timeout(),
+            )
+            .await;
+        }
+    }
+}
+
+/// Polls each of the run's nodes for `ack_file` until all of them have it or
+/// `timeout` passes, then reports per node whether the application
+/// acknowledged the checkpoint. A node that stopped answering was most likely
+/// reclaimed first.
+async fn wait_for_checkpoint_ack(
+    pool: &SqlitePool,
+    run: &TaskRun,
+    job_runners: &[(&Node, CommandRunner)],
+    ack_file: &str,
+    timeout: Duration,
+) {
+    let started = Instant::now();
+    let check = format!(
+        "test -e '{}' && echo acknowledged || echo pending",
+        ack_file
+    );
+    let mut acked_after: Vec<Option<Duration>> = vec![None; job_runners.len()];
+    let mut unreachable = vec![false; job_runners.len()];
+    loop {
+        for (i, (_, runner)) in job_runners.iter().enumerate() {
+            if acked_after[i].is_some() {
+                continue;
+            }
+            match runner.run_command(&check).await {
+                Ok(output) => {
+                    unreachable[i] = false;
+                    if output.trim() == "acknowledged" {
+                        acked_after[i] = Some(started.elapsed());
+                    }
+                }
+                Err(_) => unreachable[i] = true,
+            }
+        }
+        if acked_after.iter().all(Option::is_some) || started.elapsed() >= timeout {
+            break;
+        }
+        sleep(CHECKPOINT_ACK_POLL).await;
+    }
+
+    for (i, (node, _)) in job_runners.iter().enumerate() {
+        let message = match acked_after[i] {
+            Some(after) => {
+                tracing::info!(
+                    "Node '{}' acknowledged the checkpoint of task '{}' run {} after {:.0}s",
+                    node.id,
+                    run.task_name,
+                    run.run_index,
+                    after.as_secs_f64()
+                );
+                format!("Checkpoint acknowledged on '{}'", node.id)
+            }
+            None if unreachable[i] => {
+                tracing::warn!(
+                    "Node '{}' stopped answering before acknowledging the checkpoint of task '{}' run {}, it was likely reclaimed first",
+                    node.id,
+                    run.task_name,
+                    run.run_index
+                );
+                format!(
+                    "Node '{}' unreachable before acknowledging the checkpoint",
+                    node.id
+                )
+            }
+            None => {
+                tracing::warn!(
+                    "Node '{}' did not acknowledge the checkpoint of task '{}' run {} within {}s",
+                    node.id,
+                    run.task_name,
+                    run.run_index,
+                    timeout.as_secs()
+                );
+                format!("Checkpoint not acknowledged on '{}'", node.id)
+            }
+        };
+        let details = serde_json::json!({
+            "task_name": run.task_name,
+            "run_index": run.run_index,
+            "acknowledged": acked_after[i].is_some(),
+            "ack_secs": acked_after[i].map(|after| after.as_secs_f64()),
+            "reachable": !unreachable[i],
+        });
+        if let Err(e) = ClusterEvent::record(
+            pool,
+            &run.cluster_id,
+            ClusterEventType::CheckpointAck,
+            Some(&node.id),
+            &message,
+            Some(details),
+        )
+        .await
+        {
+            tracing::warn!("Could not record the checkpoint acknowledgement: {}", e);
+        }
     }
 }
@@ -0,0 +1,471 @@
+use crate::commands::cluster::watch::watch;
+use crate::database::models::{Cluster, WatchLock};
+use crate::utils;
+
+use anyhow::Result;
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePool;
+use std::ffi::OsString;
+use std::fs::OpenOptions;
+use std::os::unix::process::CommandExt;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::sync::Notify;
+use tokio::task::{AbortHandle, JoinHandle};
+use tokio::time::{Duration, Instant, sleep, timeout};
+
+/// What a watcher reports to `cluster watch-status`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchStatus {
+    pub cluster_id: String,
+    pub pid: u32,
+    pub started_at: NaiveDateTime,
+    pub last_check_at: Option<NaiveDateTime>,
+    /// Outcome of the last health check, e.g. "all nodes healthy".
+    pub last_check: Option<String>,
+    /// Mode of the restore in progress, if any.
+    pub restore: Option<String>,
+    pub restore_started_at: Option<NaiveDateTime>,
+    /// State of the tasks relaunched after the last restore: "none", "running"
+    /// or "finished".
+    pub tasks: String,
+    pub stopping: bool,
+}
+
+struct Shared {
+    status: Mutex<WatchStatus>,
+    tasks: Mutex<Option<AbortHandle>>,
+    stop: Notify,
+}
+
+/// A watcher's side of its control socket: the status it serves and the stop
+/// requests it receives. Without a socket path nothing is served, but the
+/// status is kept all the same.
+pub struct WatchControl {
+    pub socket_path: Option<String>,
+    pub log_path: Option<String>,
+    shared: Arc<Shared>,
+}
+
+impl WatchControl {
+    pub fn new(cluster_id: &str, socket_path: Option<String>, log_path: Option<String>) -> Self {
+        let status = WatchStatus {
+            cluster_id: cluster_id.to_string(),
+            pid: std::process::id(),
+            started_at: Utc::now().naive_utc(),
+            last_check_at: None,
+            last_check: None,
+            restore: None,
+            restore_started_at: None,
+            tasks: "none".to_string(),
+            stopping: false,
+        };
+        Self {
+            socket_path,
+            log_path,
+            shared: Arc::new(Shared {
+                status: Mutex::new(status),
+                tasks: Mutex::new(None),
+                stop: Notify::new(),
+            }),
+        }
+    }
+
+    pub fn record_health_check(&self, outcome: &str) {
+        let mut status = self.shared.status.lock().unwrap();
+        status.last_check_at = Some(Utc::now().naive_utc());
+        status.last_check = Some(outcome.to_string());
+    }
+
+    /// Marks a restore in `mode` as in progress, or none with None.
+    pub fn set_restore(&self, mode: Option<&str>) {
+        let mut status = self.shared.status.lock().unwrap();
+        status.restore = mode.map(|mode| mode.to_string());
+        status.restore_started_at = mode.map(|_| Utc::now().naive_utc());
+    }
+
+    /// Tracks the relaunched tasks run behind `handle`, or none with None.
+    pub fn track_tasks(&self, handle: Option<&JoinHandle<()>>) {
+        *self.shared.tasks.lock().unwrap() = handle.map(|handle| handle.abort_handle());
+    }
+
+    /// Sleeps for `duration` unless a stop is requested. Returns whether one was.
+    pub async fn stop_requested_within(&self, duration: Duration) -> bool {
+        tokio::select! {
+            biased;
+            _ = self.shared.stop.notified() => {
+                tracing::info!("Stop requested, leaving the monitor.");
+                true
+            }
+            _ = sleep(duration) => false,
+        }
+    }
+
+    /// Starts answering requests on the socket, if there is one.
+    fn serve(&self) -> Result<Option<JoinHandle<()>>> {
+        let Some(socket_path) = &self.socket_path else {
+            return Ok(None);
+        };
+        // Left behind by a watcher that was killed; the lock is ours now.
+        let _ = std::fs::remove_file(socket_path);
+        let listener = UnixListener::bind(socket_path)?;
+        let shared = Arc::clone(&self.shared);
+        Ok(Some(tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                if let Err(e) = shared.answer(stream).await {
+                    tracing::warn!("Control socket request failed: {}", e);
+                }
+            }
+        })))
+    }
+}
+
+impl Shared {
+    fn request_stop(&self) {
+        self.status.lock().unwrap().stopping = true;
+        // Stored as a permit when the watcher is busy, so the next wait sees it.
+        self.stop.notify_one();
+    }
+
+    fn status(&self) -> WatchStatus {
+        let mut status = self.status.lock().unwrap().clone();
+        status.tasks = match &*self.tasks.lock().unwrap() {
+            Some(handle) if handle.is_finished() => "finished".to_string(),
+            Some(_) => "running".to_string(),
+            None => "none".to_string(),
+        };
+        status
+    }
+
+    /// Answers one request line, "status" or "stop", with one JSON line.
+    async fn answer(&self, stream: UnixStream) -> Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut request = String::new();
+        BufReader::new(reader).read_line(&mut request).await?;
+        let reply = match request.trim() {
+            "status" => serde_json::to_value(self.status())?,
+            "stop" => {
+                self.request_stop();
+                serde_json::json!({ "stopping": true })
+            }
+            other => serde_json::json!({ "error": format!("Unknown request '{}'", other) }),
+        };
+        writer.write_all(format!("{}\n", reply).as_bytes()).await?;
+        Ok(())
+    }
+}
+
+/// Where the watcher of a cluster keeps its files, in the logs directory.
+pub struct WatchFiles {
+    pub pid_path: String,
+    pub log_path: String,
+    pub socket_path: String,
+}
+
+impl WatchFiles {
+    pub fn for_cluster(cluster_id: &str) -> Self {
+        let base = format!("{}/watch-{}", utils::logs_directory(), cluster_id);
+        Self {
+            pid_path: format!("{}.pid", base),
+            log_path: format!("{}.log", base),
+            socket_path: format!("{}.sock", base),
+        }
+    }
+}
+
+/// Takes the watch lock of `cluster_id` for this process, or fails naming the
+/// watcher that holds it. A lock whose process is gone is taken over.
+pub async fn acquire_watch_lock(
+    pool: &SqlitePool,
+    cluster_id: &str,
+    control: &WatchControl,
+) -> Result<()> {
+    let pid = std::process::id() as i64;
+    if let Some(lock) = WatchLock::fetch_by_cluster_id(pool, cluster_id).await? {
+        // Only one watch runs per process, so a lock of this very process was
+        // left by a watch that got cancelled.
+        if lock.pid != pid && utils::process_is_alive(lock.pid) {
+            anyhow::bail!(
+                "Cluster '{}' is already watched by process {} since {}. Stop it with \
+                'cluster watch-stop --cluster-id {}'",
+                cluster_id,
+                lock.pid,
+                lock.started_at.format("%Y-%m-%d %H:%M:%S"),
+                cluster_id
+            );
+        }
+        if lock.pid != pid {
+            tracing::warn!(
+                "Taking over the watch lock of process {}, which is no longer running.",
+                lock.pid
+            );
+        }
+        WatchLock::release(pool, cluster_id, lock.pid).await?;
+    }
+    let lock = WatchLock {
+        cluster_id: cluster_id.to_string(),
+        pid,
+        socket_path: control.socket_path.clone(),
+        log_path: control.log_path.clone(),
+        started_at: Utc::now().naive_utc(),
+    };
+    if !lock.acquire(pool).await? {
+        anyhow::bail!(
+            "Another watcher took the lock of Cluster '{}' first",
+            cluster_id
+        );
+    }
+    Ok(())
+}
+
+/// Runs `monitor` under the watch lock of `cluster_id`, answering on the
+/// control socket meanwhile, and releases both when it ends.
+pub async fn run_locked(
+    pool: &SqlitePool,
+    cluster_id: &str,
+    control: &WatchControl,
+    monitor: impl Future<Output = Result<()>>,
+) -> Result<()> {
+    acquire_watch_lock(pool, cluster_id, control).await?;
+    let server = match control.serve() {
+        Ok(server) => server,
+        Err(e) => {
+            WatchLock::release(pool, cluster_id, std::process::id() as i64).await?;
+            return Err(e);
+        }
+    };
+    let result = monitor.await;
+    if let Some(server) = server {
+        server.abort();
+    }
+    if let Some(socket_path) = &control.socket_path {
+        let _ = std::fs::remove_file(socket_path);
+    }
+    WatchLock::release(pool, cluster_id, std::process::id() as i64).await?;
+    result
+}
+
+/// Sends `request` to the watcher listening on `socket_path` and returns its
+/// JSON answer.
+pub async fn watch_request(socket_path: &str, request: &str) -> Result<serde_json::Value> {
+    let exchange = async {
+        let stream = UnixStream::connect(socket_path).await?;
+        let (reader, mut writer) = stream.into_split();
+        writer
+            .write_all(format!("{}\n", request).as_bytes())
+            .await?;
+        let mut reply = String::new();
+        BufReader::new(reader).read_line(&mut reply).await?;
+        anyhow::Ok(serde_json::from_str(&reply)?)
+    };
+    match timeout(Duration::from_secs(10), exchange).await {
+        Ok(reply) => reply,
+        Err(_) => anyhow::bail!("Watcher on '{}' did not answer within 10s", socket_path),
+    }
+}
+
+/// Starts `cluster watch` again as a background process that outlives the
+/// terminal, with the same arguments, and returns once it holds the lock.
+pub async fn watch_detach(pool: &SqlitePool, cluster_id: &str) -> Result<()> {
+    if Cluster::fetch_by_id(pool, cluster_id).await?.is_none() {
+        anyhow::bail!("Cluster (id='{}') not found", cluster_id);
+    }
+    if let Some(lock) = WatchLock::fetch_by_cluster_id(pool, cluster_id).await?
+        && utils::process_is_alive(lock.pid)
+    {
+        anyhow::bail!(
+            "Cluster '{}' is already watched by process {}",
+            cluster_id,
+            lock.pid
+        );
+    }
+
+    let files = WatchFiles::for_cluster(cluster_id);
+    let log_file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&files.log_path)?;
+    let args: Vec<OsString> = std::env::args_os()
+        .skip(1)
+        .filter(|arg| arg != "--detach")
+        .chain([OsString::from("--daemon")])
+        .collect();
+    // Its own process group keeps the terminal's Ctrl+C and hangup away from it.
+    let mut child = std::process::Command::new(std::env::current_exe()?)
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(log_file.try_clone()?)
+        .stderr(log_file)
+        .process_group(0)
+        .spawn()?;
+    let pid = child.id() as i64;
+
+    let deadline = Instant::now() + Duration::from_secs(30);
+    loop {
+        if let Some(status) = child.try_wait()? {
+            anyhow::bail!(
+                "Detached watcher exited ({}) before taking the lock, see '{}'",
+                status,
+                files.log_path
+            );
+        }
+        if let Some(lock) = WatchLock::fetch_by_cluster_id(pool, cluster_id).await?
+            && lock.pid == pid
+        {
+            break;
+        }
+        if Instant::now() >= deadline {
+            anyhow::bail!(
+                "Detached watcher (pid {}) did not take the lock within 30s, see '{}'",
+                pid,
+                files.log_path
+            );
+        }
+        sleep(Duration::from_millis(200)).await;
+    }
+
+    tracing::info!(
+        "Watching Cluster '{}' in the background (pid {}), logging to '{}'.",
+        cluster_id,
+        pid,
+        files.log_path
+    );
+    tracing::info!(
+        "Check on it with 'cluster watch-status --cluster-id {}', stop it with \
+        'cluster watch-stop --cluster-id {}'.",
+        cluster_id,
+        cluster_id
+    );
+    Ok(())
+}
+
+/// The process `watch_detach` starts: `watch` with a pidfile, ignoring the
+/// hangup of the terminal it came from and stopping cleanly on SIGTERM.
+pub async fn watch_daemon(
+    pool: &SqlitePool,
+    cluster_id: &str,
+    interval_secs: u64,
+    tasks_yaml: Option<&str>,
+    no_replace: bool,
+    proactive: bool,
+) -> Result<()> {
+    let files = WatchFiles::for_cluster(cluster_id);
+    std::fs::write(&files.pid_path, format!("{}\n", std::process::id()))?;
+
+    let control = WatchControl::new(
+        cluster_id,
+        Some(files.socket_path.clone()),
+        Some(files.log_path.clone()),
+    );
+    let mut hangups = signal(SignalKind::hangup())?;
+    let mut terminations = signal(SignalKind::terminate())?;
+    let shared = Arc::clone(&control.shared);
+    let signals = tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = hangups.recv() => {}
+                _ = terminations.recv() => {
+                    tracing::info!("SIGTERM received, stopping after the current pass.");
+                    shared.request_stop();
+                }
+            }
+        }
+    });
+
+    let result = watch(
+        pool,
+        cluster_id,
+        interval_secs,
+        tasks_yaml,
+        no_replace,
+        proactive,
+        &control,
+    )
+    .await;
+    signals.abort();
+    let _ = std::fs::remove_file(&files.pid_path);
+    if let Err(e) = &result {
+        tracing::error!("Watcher of Cluster '{}' stopped: {}", cluster_id, e);
+    }
+    result
+}
+
+/// Shows what the watcher of `cluster_id` is doing.
+pub async fn watch_status(pool: &SqlitePool, cluster_id: &str) -> Result<()> {
+    let Some(lock) = WatchLock::fetch_by_cluster_id(pool, cluster_id).await? else {
+        tracing::info!("Cluster '{}' is not being watched.", cluster_id);
+        return Ok(());
+    };
+    if !utils::process_is_alive(lock.pid) {
+        tracing::info!(
+            "Cluster '{}' is not being watched: its watcher (pid {}) stopped without \
+            releasing the lock. The next 'cluster watch' takes it over.",
+            cluster_id,
+            lock.pid
+        );
+        return Ok(());
+    }
+    tracing::info!(
+        "Cluster '{}' is watched by process {} since {}.",
+        cluster_id,
+        lock.pid,
+        lock.started_at.format("%Y-%m-%d %H:%M:%S")
+    );
+    if let Some(log_path) = &lock.log_path {
+        tracing::info!("Log: '{}'", log_path);
+    }
+    let Some(socket_path) = &lock.socket_path else {
+        return Ok(());
+    };
+    let status: WatchStatus = serde_json::from_value(watch_request(socket_path, "status").await?)?;
+    match (&status.last_check_at, &status.last_check) {
+        (Some(at), Some(outcome)) => tracing::info!(
+            "Last health check: {} UTC, {}",
+            at.format("%Y-%m-%d %H:%M:%S"),
+            outcome
+        ),
+        _ => tracing::info!("Last health check: none yet"),
+    }
+    match (&status.restore, &status.restore_started_at) {
+        (Some(mode), Some(since)) => tracing::info!(
+            "Restore in progress: {} since {} UTC",
+            mode,
+            since.format("%Y-%m-%d %H:%M:%S")
+        ),
+        _ => tracing::info!("Restore in progress: none"),
+    }
+    tracing::info!("Relaunched tasks: {}", status.tasks);
+    if status.stopping {
+        tracing::info!("Stopping after the current pass.");
+    }
+    Ok(())
+}
+
+/// Asks the watcher of `cluster_id` to stop after its current pass.
+pub async fn watch_stop(pool: &SqlitePool, cluster_id: &str) -> Result<()> {
+    let lock = match WatchLock::fetch_by_cluster_id(pool, cluster_id).await? {
+        Some(lock) if utils::process_is_alive(lock.pid) => lock,
+        _ => {
+            tracing::info!("Cluster '{}' is not being watched.", cluster_id);
+            return Ok(());
+        }
+    };
+    let Some(socket_path) = &lock.socket_path else {
+        anyhow::bail!(
+            "The watcher of Cluster '{}' (pid {}) has no control socket",
+            cluster_id,
+            lock.pid
+        );
+    };
+    watch_request(socket_path, "stop").await?;
+    tracing::info!(
+        "Watcher of Cluster '{}' (pid {}) will stop after its current pass.",
+        cluster_id,
+        lock.pid
+    );
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::sqlite::SqlitePool;
+use std::path::Path;
+
+/// Copies the database to `output`, or next to it with a timestamp.
+///
+/// Uses `VACUUM INTO`, which writes a consistent snapshot through SQLite itself.
+/// Copying the file directly is not safe: in WAL mode recent commits live in
+/// the `-wal` file until a checkpoint, and a running `watch` may be writing.
+pub async fn backup(pool: &SqlitePool, database_path: &Path, output: Option<&str>) -> Result<()> {
+    let output = match output {
+        Some(path) => path.to_string(),
+        None => format!(
+            "{}.{}.bak",
+            database_path.display(),
+            Utc::now().format("%Y-%m-%d_%H-%M-%S")
+        ),
+    };
+    if Path::new(&output).exists() {
+        anyhow::bail!("Backup destination '{}' already exists", output);
+    }
+
+    match sqlx::query("VACUUM INTO ?")
+        .bind(&output)
+        .execute(pool)
+        .await
+    {
+        Ok(_) => {
+            tracing::info!("Database backed up to '{}'", output);
+            Ok(())
+        }
+        Err(e) => {
+            tracing::error!("SQLx Error: {}", e.to_string());
+            anyhow::bail!("Failed to back up the database to '{}': {}", output, e)
+        }
+    }
+}
@@ -0,0 +1,14 @@
+use crate::database::migrations;
+
+use anyhow::Result;
+use sqlx::sqlite::SqlitePool;
+
+pub async fn migrate(pool: &SqlitePool) -> Result<()> {
+    let applied = migrations::migrate(pool).await?;
+    if applied.is_empty() {
+        tracing::info!("Database schema is up to date, no migrations applied");
+    } else {
+        tracing::info!("Applied {} migration(s): {:?}", applied.len(), applied);
+    }
+    Ok(())
+}
@@ -0,0 +1,7 @@
+mod backup;
+mod migrate;
+mod status;
+
+pub use backup::*;
+pub use migrate::*;
+pub use status::*;
@@ -0,0 +1,66 @@
+use crate::database::migrations::{self, MigrationState};
+
+use anyhow::Result;
+use sqlx::sqlite::SqlitePool;
+use tabled::{Table, Tabled, settings::Style};
+
+#[derive(Tabled)]
+struct MigrationDisplay {
+    #[tabled(rename = "Version")]
+    version: i64,
+    #[tabled(rename = "Description")]
+    description: String,
+    #[tabled(rename = "State")]
+    state: String,
+}
+
+pub async fn status(pool: &SqlitePool) -> Result<()> {
+    let statuses = migrations::status(pool).await?;
+    let pending = statuses
+        .iter()
+        .filter(|s| s.state == MigrationState::Pending)
+        .count();
+    let unknown = statuses
+        .iter()
+        .filter(|s| s.state == MigrationState::Unknown)
+        .count();
+    let modified = statuses
+        .iter()
+        .filter(|s| s.state == MigrationState::Modified)
+        .count();
+
+    let table_rows: Vec<MigrationDisplay> = statuses
+        .into_iter()
+        .map(|s| MigrationDisplay {
+            version: s.version,
+            description: s.description,
+            state: s.state.to_string(),
+        })
+        .collect();
+    let mut table = Table::new(table_rows);
+    table.with(Style::rounded());
+    tracing::info!("\nMigrations:\n{}", table);
+
+    if unknown > 0 {
+        tracing::warn!(
+            "{} migration(s) were applied by a newer build of hpcac-toolkit; this build will refuse to use the database",
+            unknown
+        );
+    }
+    if modified > 0 {
+        tracing::warn!(
+            "{} migration(s) changed after they were applied to this database",
+            modified
+        );
+    }
+    if pending > 0 {
+        tracing::info!(
+            "{} pending migration(s). Run 'db migrate' or any other command to apply them",
+            pending
+        );
+    } else if unknown == 0 && modified == 0 {
+        tracing::info!("Database schema is up to date");
+    }
+
+    Ok(())
+}
@@ -1,8 +1,5 @@
 use crate::database::models::{InstanceType, Provider, ProviderConfig};
-use crate::integrations::{
-    CloudInfoProvider, CloudProvider,
-    providers::{aws::AwsInterface, vultr::VultrInterface},
-};
+use crate::integrations::{CloudInfoProvider, CloudProvider};
 use crate::utils;
 
 use anyhow::Result;
@@ -109,15 +106,8 @@ pub async fn load(
         }
     };
 
-    let config_vars = provider_config.get_config_vars(pool).await?;
     let provider_id = provider_config.provider_id.clone();
-    let cloud_interface = match provider_id.as_str() {
-        "aws" => CloudProvider::Aws(AwsInterface { config_vars }),
-        "vultr" => CloudProvider::Vultr(VultrInterface { config_vars }),
-        _ => {
-            anyhow::bail!("Provider '{}' is currently not supported.", &provider_id)
-        }
-    };
+    let cloud_interface = CloudProvider::from_provider_config(pool, &provider_config).await?;
 
     tracing::info!(
         "Loading instance_types from provider '{}' using configuration '{}'...",
@@ -1,3 +1,4 @@
 pub mod cluster;
+pub mod db;
 pub mod instance_type;
 pub mod provider_config;
@@ -0,0 +1,178 @@
+use anyhow::Result;
+use sqlx::migrate::{Migrate, MigrateError, Migrator};
+use sqlx::sqlite::SqlitePool;
+use std::collections::HashMap;
+
+/// Every migration under `migrations/`, compiled into the binary so a fresh
+/// checkout needs no sqlx-cli to get a usable database.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Applied, but the file in this build differs from what was run.
+    Modified,
+    /// Applied by a newer build; this one does not know it.
+    Unknown,
+}
+
+impl std::fmt::Display for MigrationState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state_str = match self {
+            MigrationState::Applied => "applied",
+            MigrationState::Pending => "pending",
+            MigrationState::Modified => "modified",
+            MigrationState::Unknown => "unknown",
+        };
+        write!(f, "{}", state_str)
+    }
+}
+
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+}
+
+/// Versions already applied to the database, with their checksums. Empty for a
+/// database that has never been migrated.
+async fn applied_migrations(pool: &SqlitePool) -> Result<HashMap<i64, Vec<u8>>> {
+    let has_table: bool = match sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations')",
+    )
+    .fetch_one(pool)
+    .await
+    {
+        Ok(result) => result,
+        Err(e) => {
+            tracing::error!("SQLx Error: {}", e.to_string());
+            anyhow::bail!("DB Operation Failure: {}", e);
+        }
+    };
+    if !has_table {
+        return Ok(HashMap::new());
+    }
+
+    let mut conn = pool.acquire().await?;
+    let applied = match conn.list_applied_migrations().await {
+        Ok(result) => result,
+        Err(e) => {
+            tracing::error!("SQLx Error: {}", e.to_string());
+            anyhow::bail!("Failed to read applied migrations: {}", e);
+        }
+    };
+    Ok(applied
+        .into_iter()
+        .map(|m| (m.version, m.checksum.into_owned()))
+        .collect())
+}
+
+/// Every migration known to this build or recorded in the database, in version
+/// order.
+pub async fn status(pool: &SqlitePool) -> Result<Vec<MigrationStatus>> {
+    let mut applied = applied_migrations(pool).await?;
+
+    let mut statuses: Vec<MigrationStatus> = MIGRATOR
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+        .map(|m| {
+            let state = match applied.remove(&m.version) {
+                Some(checksum) if checksum == *m.checksum => MigrationState::Applied,
+                Some(_) => MigrationState::Modified,
+                None => MigrationState::Pending,
+            };
+            MigrationStatus {
+                version: m.version,
+                description: m.description.trim().to_string(),
+                state,
+            }
+        })
+        .collect();
+    statuses.extend(applied.into_keys().map(|version| MigrationStatus {
+        version,
+        description: String::new(),
+        state: MigrationState::Unknown,
+    }));
+    statuses.sort_by_key(|s| s.version);
+
+    Ok(statuses)
+}
+
+/// Applies pending migrations and returns their versions.
+///
+/// Refuses a database migrated by a newer build: its tables may have columns
+/// and constraints this build's queries know nothing about, and writing to it
+/// could corrupt data the newer build relies on.
+pub async fn migrate(pool: &SqlitePool) -> Result<Vec<i64>> {
+    let statuses = status(pool).await?;
+    let unknown: Vec<i64> = statuses
+        .iter()
+        .filter(|s| s.state == MigrationState::Unknown)
+        .map(|s| s.version)
+        .collect();
+    if !unknown.is_empty() {
+        anyhow::bail!(
+            "The database schema is newer than this build of hpcac-toolkit (unknown migration(s): {:?}). \
+            Update hpcac-toolkit, or point DATABASE_URL at a different database",
+            unknown
+        );
+    }
+
+    let pending: Vec<i64> = statuses
+        .iter()
+        .filter(|s| s.state == MigrationState::Pending)
+        .map(|s| s.version)
+        .collect();
+    match MIGRATOR.run(pool).await {
+        Ok(()) => Ok(pending),
+        Err(MigrateError::VersionMismatch(version)) => {
+            anyhow::bail!(
+                "Migration {} was modified after it was applied to this database. \
+                Restore the original file, or recreate the database",
+                version
+            )
+        }
+        Err(e) => {
+            tracing::error!("{:?}", e);
+            anyhow::bail!("Failed to apply database migrations: {}", e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestEnv;
+
+    #[tokio::test]
+    async fn fresh_database_is_fully_migrated() {
+        let env = TestEnv::new().await;
+        let statuses = status(&env.pool).await.unwrap();
+        assert!(!statuses.is_empty());
+        assert!(statuses.iter().all(|s| s.state == MigrationState::Applied));
+
+        // Nothing left to apply, so running again is a no-op.
+        assert!(migrate(&env.pool).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn refuses_a_newer_schema() {
+        let env = TestEnv::new().await;
+        sqlx::query(
+            "INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time) \
+             VALUES (9999, 'from the future', 1, x'00', 0)",
+        )
+        .execute(&env.pool)
+        .await
+        .unwrap();
+
+        let statuses = status(&env.pool).await.unwrap();
+        let newest = statuses.last().unwrap();
+        assert_eq!(newest.version, 9999);
+        assert_eq!(newest.state, MigrationState::Unknown);
+
+        let error = migrate(&env.pool).await.unwrap_err();
+        assert!(error.to_string().contains("newer than this build"));
+    }
+}
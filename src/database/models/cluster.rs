@@ -1,4 +1,7 @@
-use crate::database::models::{InstanceType, Node, ProviderConfig, RecoveryNode, ShellCommand};
+use crate::database::models::{
+    ClusterEvent, ClusterEventType, InstanceType, Node, ProviderConfig, RecoveryNode,
+    SelectionPolicy, ShellCommand,
+};
 
 use anyhow::Result;
 use chrono::NaiveDateTime;
@@ -78,6 +81,12 @@ pub struct Cluster {
     pub state: ClusterState,
     pub cost_per_hour: f64,
     pub cost_breakdown: String,
+    pub checkpoint_signal: Option<String>, // JSON object
+    pub scheduler: Option<String>,
+    pub health_probes: Option<String>,               // JSON array
+    pub fallback_availability_zones: Option<String>, // JSON array
+    pub recovery_selection_policy: Option<String>,
+    pub recovery_max_price_per_hour: Option<f64>,
 }
 
 impl Cluster {
@@ -153,7 +162,7 @@ impl Cluster {
             };
 
             tracing::info!(
-                "  Node {}:\n    Instance Type   : {}\n    Processor       : {}\n    vCPUs:          : {}\n    GPUs:           : {}\n    Image ID        : {}\n    Allocation Mode : {}\n    Burstable Mode  : {}",
+                "  Node {}:\n    Instance Type   : {}\n    Processor       : {}\n    vCPUs:          : {}\n    GPUs:           : {}\n    Image ID        : {}\n    Allocation Mode : {}\n    Market Type     : {}\n    Burstable Mode  : {}\n    EFA Available   : {}",
                 i + 1,
                 node.instance_type,
                 processor_info,
@@ -161,13 +170,78 @@ impl Cluster {
                 gpu_info,
                 node.image_id,
                 node.allocation_mode,
-                node.burstable_mode.as_deref().unwrap_or("N/A")
+                node.market_type.as_deref().unwrap_or("N/A"),
+                node.burstable_mode.as_deref().unwrap_or("N/A"),
+                match node.efa_available {
+                    Some(true) => "yes",
+                    Some(false) => "NO (fi_info found no efa provider)",
+                    None => "N/A",
+                }
             );
         }
 
         Ok(())
     }
 
+    /// Zones a restore may move a node to, in the order they are tried.
+    pub fn fallback_availability_zones(&self) -> Vec<String> {
+        self.fallback_availability_zones
+            .as_deref()
+            .and_then(|zones| serde_json::from_str(zones).ok())
+            .unwrap_or_default()
+    }
+
+    /// Zones a replacement for a node now in `current_zone` (None for the
+    /// cluster's own) is launched in, in order: where it already is, so a
+    /// launch that finds capacity keeps its network interface, then the
+    /// cluster's zone and the fallbacks.
+    pub fn restore_zones(&self, current_zone: Option<&str>) -> Vec<String> {
+        let current = current_zone.unwrap_or(&self.availability_zone).to_string();
+        let mut zones = vec![current];
+        for zone in std::iter::once(self.availability_zone.clone())
+            .chain(self.fallback_availability_zones())
+        {
+            if !zones.contains(&zone) {
+                zones.push(zone);
+            }
+        }
+        zones
+    }
+
+    /// How a restore picks among a recovery slot's preferred instance types.
+    pub fn selection_policy(&self) -> SelectionPolicy {
+        self.recovery_selection_policy
+            .as_deref()
+            .and_then(|policy| policy.parse().ok())
+            .unwrap_or_default()
+    }
+
+    /// Replaces the cost estimate, after a restore changed the nodes.
+    pub async fn update_cost(
+        &self,
+        pool: &SqlitePool,
+        cost_per_hour: f64,
+        cost_breakdown: &str,
+    ) -> Result<()> {
+        match sqlx::query!(
+            r#"UPDATE clusters SET cost_per_hour = ?, cost_breakdown = ? WHERE id = ?"#,
+            cost_per_hour,
+            cost_breakdown,
+            self.id
+        )
+        .execute(pool)
+        .await
+        {
+            Ok(result) => {
+                if result.rows_affected() == 0 {
+                    anyhow::bail!("Cluster '{}' not found for cost update", self.id);
+                }
+            }
+            Err(e) => anyhow::bail!("DB Operation Failure: {}", e),
+        }
+        Ok(())
+    }
+
     pub async fn fetch_by_id(pool: &SqlitePool, cluster_id: &str) -> Result<Option<Cluster>> {
         let cluster = match sqlx::query_as!(
             Cluster,
@@ -190,7 +264,13 @@ impl Cluster {
                     created_at,
                     state as "state: ClusterState",
                     cost_per_hour,
-                    cost_breakdown
+                    cost_breakdown,
+                    checkpoint_signal,
+                    scheduler,
+                    health_probes,
+                    fallback_availability_zones,
+                    recovery_selection_policy,
+                    recovery_max_price_per_hour
                 FROM clusters
                 WHERE id = ?
             "#,
@@ -231,7 +311,13 @@ impl Cluster {
                     created_at,
                     state as "state: ClusterState",
                     cost_per_hour,
-                    cost_breakdown
+                    cost_breakdown,
+                    checkpoint_signal,
+                    scheduler,
+                    health_probes,
+                    fallback_availability_zones,
+                    recovery_selection_policy,
+                    recovery_max_price_per_hour
                 FROM clusters
                 WHERE display_name = ?
             "#,
@@ -272,7 +358,13 @@ impl Cluster {
                     created_at,
                     state as "state: ClusterState",
                     cost_per_hour,
-                    cost_breakdown
+                    cost_breakdown,
+                    checkpoint_signal,
+                    scheduler,
+                    health_probes,
+                    fallback_availability_zones,
+                    recovery_selection_policy,
+                    recovery_max_price_per_hour
                 FROM clusters
             "#,
         )
@@ -330,9 +422,15 @@ impl Cluster {
                     created_at,
                     state,
                     cost_per_hour,
-                    cost_breakdown
+                    cost_breakdown,
+                    checkpoint_signal,
+                    scheduler,
+                    health_probes,
+                    fallback_availability_zones,
+                    recovery_selection_policy,
+                    recovery_max_price_per_hour
                 )
-                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
             self.id,
             self.display_name,
@@ -352,6 +450,12 @@ impl Cluster {
             self.state,
             self.cost_per_hour,
             self.cost_breakdown,
+            self.checkpoint_signal,
+            self.scheduler,
+            self.health_probes,
+            self.fallback_availability_zones,
+            self.recovery_selection_policy,
+            self.recovery_max_price_per_hour,
         )
         .execute(&mut *tx)
         .await
@@ -448,6 +552,10 @@ impl Cluster {
         for query in [
             "DELETE FROM shell_commands WHERE node_id IN (SELECT id FROM nodes WHERE cluster_id = ?)",
             "DELETE FROM task_runs WHERE cluster_id = ?",
+            "DELETE FROM cluster_events WHERE cluster_id = ?",
+            "DELETE FROM recovery_incidents WHERE cluster_id = ?",
+            "DELETE FROM spawn_steps WHERE cluster_id = ?",
+            "DELETE FROM watch_locks WHERE cluster_id = ?",
             "DELETE FROM recovery_nodes WHERE cluster_id = ?",
             "DELETE FROM nodes WHERE cluster_id = ?",
         ] {
@@ -503,10 +611,17 @@ impl Cluster {
                     private_ip,
                     public_ip,
                     was_efs_configured,
-                    was_ssh_configured
+                    was_ssh_configured,
+                    spot_max_price,
+                    spot_request_type,
+                    spot_interruption_behavior,
+                    market_type,
+                    efa_available,
+                    availability_zone,
+                    node_index
                 FROM nodes
                 WHERE cluster_id = ?
-                ORDER BY rowid
+                ORDER BY node_index
             "#,
             self.id
         )
@@ -523,6 +638,9 @@ impl Cluster {
         Ok(nodes)
     }
 
+    /// Moves the cluster to `new_state` and records the transition. The previous
+    /// state is read from the DB rather than `self`, which may be stale by the time
+    /// a long spawn reaches its next transition.
     pub async fn update_state(&self, pool: &SqlitePool, new_state: ClusterState) -> Result<()> {
         tracing::info!(
             "Transitioning Cluster (id='{}') to state '{}'",
@@ -530,6 +648,48 @@ impl Cluster {
             new_state
         );
 
+        let mut tx = match pool.begin().await {
+            Ok(result) => result,
+            Err(e) => {
+                tracing::error!("SQLx Error: {:?}", e);
+                anyhow::bail!("DB Operation Failure: {}", e);
+            }
+        };
+
+        // Take the write lock before reading the previous state. In WAL mode a
+        // transaction that reads first and writes later fails outright with
+        // SQLITE_BUSY_SNAPSHOT when another writer got in between, instead of
+        // waiting out the busy timeout like a plain write does.
+        match sqlx::query!("UPDATE clusters SET state = state WHERE id = ?", self.id)
+            .execute(&mut *tx)
+            .await
+        {
+            Ok(result) => {
+                if result.rows_affected() == 0 {
+                    anyhow::bail!("Cluster '{}' not found for state transition", self.id);
+                }
+            }
+            Err(e) => {
+                tracing::error!("SQLx Error: {:?}", e);
+                anyhow::bail!("DB Operation Failure: {}", e);
+            }
+        }
+
+        let old_state = match sqlx::query_scalar!(
+            r#"SELECT state as "state: ClusterState" FROM clusters WHERE id = ?"#,
+            self.id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        {
+            Ok(Some(state)) => state,
+            Ok(None) => anyhow::bail!("Cluster '{}' not found for state transition", self.id),
+            Err(e) => {
+                tracing::error!("SQLx Error: {:?}", e);
+                anyhow::bail!("DB Operation Failure: {}", e);
+            }
+        };
+
         match sqlx::query!(
             r#"
                 UPDATE clusters 
@@ -539,14 +699,28 @@ impl Cluster {
             new_state,
             self.id
         )
-        .execute(pool)
+        .execute(&mut *tx)
         .await
         {
-            Ok(result) => {
-                if result.rows_affected() == 0 {
-                    anyhow::bail!("Cluster '{}' not found for state transition", self.id);
-                }
+            Ok(_) => {}
+            Err(e) => {
+                tracing::error!("SQLx Error: {:?}", e);
+                anyhow::bail!("DB Operation Failure: {}", e);
+            }
+        }
 
+        ClusterEvent::record(
+            &mut *tx,
+            &self.id,
+            ClusterEventType::StateTransition,
+            None,
+            &format!("{} -> {}", old_state, new_state),
+            Some(serde_json::json!({ "from": old_state, "to": new_state })),
+        )
+        .await?;
+
+        match tx.commit().await {
+            Ok(_) => {
                 tracing::info!(
                     "Successfully transitioned Cluster (id='{}') to '{}'",
                     self.id,
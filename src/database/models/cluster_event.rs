@@ -0,0 +1,143 @@
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteExecutor, Type, sqlite::SqlitePool};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ClusterEventType {
+    StateTransition,
+    NodeLaunched,
+    NodeFailed,
+    SpotNoticeReceived,
+    RestoreStarted,
+    RestoreFinished,
+    RestoreFailed,
+    CapacityFallbackUsed,
+    TaskRunStarted,
+    TaskRunEnded,
+    CheckpointAck,
+    NodeRetired,
+    ZoneFallbackUsed,
+    InstanceTypeSelected,
+}
+
+impl std::fmt::Display for ClusterEventType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let type_str = match self {
+            ClusterEventType::StateTransition => "state_transition",
+            ClusterEventType::NodeLaunched => "node_launched",
+            ClusterEventType::NodeFailed => "node_failed",
+            ClusterEventType::SpotNoticeReceived => "spot_notice_received",
+            ClusterEventType::RestoreStarted => "restore_started",
+            ClusterEventType::RestoreFinished => "restore_finished",
+            ClusterEventType::RestoreFailed => "restore_failed",
+            ClusterEventType::CapacityFallbackUsed => "capacity_fallback_used",
+            ClusterEventType::TaskRunStarted => "task_run_started",
+            ClusterEventType::TaskRunEnded => "task_run_ended",
+            ClusterEventType::CheckpointAck => "checkpoint_ack",
+            ClusterEventType::NodeRetired => "node_retired",
+            ClusterEventType::ZoneFallbackUsed => "zone_fallback_used",
+            ClusterEventType::InstanceTypeSelected => "instance_type_selected",
+        };
+        write!(f, "{}", type_str)
+    }
+}
+
+/// One entry of a cluster's timeline. Written alongside the `tracing` lines that
+/// describe the same thing, so an experiment can be reconstructed from the DB.
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ClusterEvent {
+    pub id: i64,
+    pub cluster_id: String,
+    pub event_type: ClusterEventType,
+    pub node_id: Option<String>,
+    pub message: String,
+    pub details: Option<String>, // JSON object
+    pub created_at: NaiveDateTime,
+}
+
+impl ClusterEvent {
+    /// Append an event to the cluster's timeline. Takes any executor so events
+    /// can be written inside the transaction of the change they describe.
+    pub async fn record<'e>(
+        executor: impl SqliteExecutor<'e>,
+        cluster_id: &str,
+        event_type: ClusterEventType,
+        node_id: Option<&str>,
+        message: &str,
+        details: Option<serde_json::Value>,
+    ) -> Result<()> {
+        let created_at = chrono::Utc::now().naive_utc();
+        let details = details.map(|d| d.to_string());
+        match sqlx::query!(
+            r#"
+                INSERT INTO cluster_events (cluster_id, event_type, node_id, message, details, created_at)
+                VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+            cluster_id,
+            event_type,
+            node_id,
+            message,
+            details,
+            created_at,
+        )
+        .execute(executor)
+        .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!("SQLx Error: {:?}", e);
+                anyhow::bail!("DB Operation Failure: {}", e);
+            }
+        }
+    }
+
+    /// The cluster's events in the order they happened, optionally only those at
+    /// or after `since`.
+    pub async fn fetch_by_cluster_id(
+        pool: &SqlitePool,
+        cluster_id: &str,
+        since: Option<NaiveDateTime>,
+    ) -> Result<Vec<ClusterEvent>> {
+        let events = match sqlx::query_as!(
+            ClusterEvent,
+            r#"
+                SELECT
+                    id as "id!",
+                    cluster_id,
+                    event_type as "event_type: ClusterEventType",
+                    node_id,
+                    message,
+                    details,
+                    created_at
+                FROM cluster_events
+                WHERE cluster_id = ? AND (? IS NULL OR created_at >= ?)
+                ORDER BY created_at, id
+            "#,
+            cluster_id,
+            since,
+            since,
+        )
+        .fetch_all(pool)
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                tracing::error!("SQLx Error: {:?}", e);
+                anyhow::bail!("DB Operation Failure: {}", e);
+            }
+        };
+
+        Ok(events)
+    }
+
+    /// The `details` column parsed back into JSON, or `null` when absent.
+    pub fn details_json(&self) -> serde_json::Value {
+        self.details
+            .as_deref()
+            .and_then(|d| serde_json::from_str(d).ok())
+            .unwrap_or(serde_json::Value::Null)
+    }
+}
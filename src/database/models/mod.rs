@@ -1,19 +1,29 @@
 pub mod cluster;
+pub mod cluster_event;
 pub mod instance_type;
 pub mod machine_image;
 pub mod node;
 pub mod provider;
 pub mod provider_config;
+pub mod recovery_incident;
 pub mod recovery_node;
 pub mod shell_command;
+pub mod spawn_step;
 pub mod task_run;
+pub mod watch_lock;
+#[cfg(test)]
+mod tests;
 
 pub use cluster::*;
+pub use cluster_event::*;
 pub use instance_type::*;
 pub use machine_image::*;
 pub use node::*;
 pub use provider::*;
 pub use provider_config::*;
+pub use recovery_incident::*;
 pub use recovery_node::*;
 pub use shell_command::*;
+pub use spawn_step::*;
 pub use task_run::*;
+pub use watch_lock::*;
@@ -19,6 +19,36 @@ pub struct Node {
     pub public_ip: Option<String>,
     pub was_efs_configured: bool,
     pub was_ssh_configured: bool,
+    pub spot_max_price: Option<f64>,
+    pub spot_request_type: Option<String>,
+    pub spot_interruption_behavior: Option<String>,
+    pub market_type: Option<String>,
+    pub efa_available: Option<bool>,
+    /// Zone the node was moved to by a restore. None is the cluster's own.
+    pub availability_zone: Option<String>,
+    /// Position the node's network interface, private IP, Elastic IP and
+    /// instance name derive from. Fixed at insert and unique within the
+    /// cluster, so removing a node does not renumber the others.
+    pub node_index: i64,
+}
+
+/// Hardware spec of a node row, as `update_instance_spec` writes it.
+#[derive(Debug)]
+pub struct InstanceSpec<'a> {
+    pub instance_type: &'a str,
+    pub allocation_mode: &'a str,
+    pub image_id: &'a str,
+    pub burstable_mode: Option<&'a str>,
+    pub root_volume_gb: i64,
+    pub root_volume_type: &'a str,
+    pub root_volume_iops: Option<i64>,
+}
+
+/// Sorts `nodes` into slot order: by node index, so the head comes first and a
+/// node keeps its place when another is removed, or is moved to a fallback zone
+/// and gets an address in another subnet.
+pub fn slot_order(nodes: &mut [Node]) {
+    nodes.sort_by_key(|n| n.node_index);
 }
 
 impl Node {
@@ -36,9 +66,13 @@ impl Node {
                     root_volume_type,
                     root_volume_iops,
                     was_efs_configured,
-                    was_ssh_configured
+                    was_ssh_configured,
+                    spot_max_price,
+                    spot_request_type,
+                    spot_interruption_behavior,
+                    node_index
                 )
-                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
             self.id,
             self.cluster_id,
@@ -51,6 +85,10 @@ impl Node {
             self.root_volume_iops,
             self.was_efs_configured,
             self.was_ssh_configured,
+            self.spot_max_price,
+            self.spot_request_type,
+            self.spot_interruption_behavior,
+            self.node_index,
         )
         .execute(&mut **tx)
         .await
@@ -81,7 +119,7 @@ impl Node {
 
     pub async fn reset(&self, pool: &SqlitePool) -> Result<()> {
         match sqlx::query!(
-            r#"UPDATE nodes SET private_ip = '', public_ip = NULL, was_efs_configured = false WHERE id = ?"#,
+            r#"UPDATE nodes SET private_ip = '', public_ip = NULL, was_efs_configured = false, market_type = NULL, efa_available = NULL, availability_zone = NULL WHERE id = ?"#,
             self.id
         )
         .execute(pool)
@@ -158,6 +196,95 @@ impl Node {
         Ok(())
     }
 
+    /// Records the market the live instance was actually launched in.
+    pub async fn set_market_type(&self, pool: &SqlitePool, market_type: &str) -> Result<()> {
+        match sqlx::query!(
+            r#"UPDATE nodes SET market_type = ? WHERE id = ?"#,
+            market_type,
+            self.id
+        )
+        .execute(pool)
+        .await
+        {
+            Ok(result) => {
+                if result.rows_affected() == 0 {
+                    anyhow::bail!("Node '{}' not found for market type update", self.id);
+                }
+            }
+            Err(e) => anyhow::bail!("DB Operation Failure: {}", e),
+        }
+        Ok(())
+    }
+
+    /// Records whether the EFA provider was visible on the live instance.
+    pub async fn set_efa_available(&self, pool: &SqlitePool, available: bool) -> Result<()> {
+        match sqlx::query!(
+            r#"UPDATE nodes SET efa_available = ? WHERE id = ?"#,
+            available,
+            self.id
+        )
+        .execute(pool)
+        .await
+        {
+            Ok(result) => {
+                if result.rows_affected() == 0 {
+                    anyhow::bail!("Node '{}' not found for EFA check update", self.id);
+                }
+            }
+            Err(e) => anyhow::bail!("DB Operation Failure: {}", e),
+        }
+        Ok(())
+    }
+
+    /// Records the zone a restore moved the node to, None for the cluster's own.
+    pub async fn set_availability_zone(
+        &self,
+        pool: &SqlitePool,
+        availability_zone: Option<&str>,
+    ) -> Result<()> {
+        match sqlx::query!(
+            r#"UPDATE nodes SET availability_zone = ? WHERE id = ?"#,
+            availability_zone,
+            self.id
+        )
+        .execute(pool)
+        .await
+        {
+            Ok(result) => {
+                if result.rows_affected() == 0 {
+                    anyhow::bail!("Node '{}' not found for availability zone update", self.id);
+                }
+            }
+            Err(e) => anyhow::bail!("DB Operation Failure: {}", e),
+        }
+        Ok(())
+    }
+
+    /// The node's current hardware spec.
+    pub fn instance_spec(&self) -> InstanceSpec<'_> {
+        InstanceSpec {
+            instance_type: &self.instance_type,
+            allocation_mode: &self.allocation_mode,
+            image_id: &self.image_id,
+            burstable_mode: self.burstable_mode.as_deref(),
+            root_volume_gb: self.root_volume_gb,
+            root_volume_type: &self.root_volume_type,
+            root_volume_iops: self.root_volume_iops,
+        }
+    }
+
+    /// Whether this node runs (or will run) on spot capacity.
+    ///
+    /// Prefers the recorded `market_type` over the declared `allocation_mode`:
+    /// once an instance exists, what AWS actually launched is what gets billed
+    /// and what can be reclaimed. Before launch only the declaration is known.
+    pub fn is_spot(&self) -> bool {
+        match self.market_type.as_deref() {
+            Some(market_type) => market_type == "spot",
+            None => self.allocation_mode == "spot",
+        }
+    }
+
     /// Updates instance_type and allocation_mode in-place when recovery policy
     /// specifies a different spec for this node slot.
     /// Overwrites the hardware spec of an existing node row.
@@ -170,13 +297,7 @@ impl Node {
     pub async fn update_instance_spec(
         &self,
         pool: &SqlitePool,
-        instance_type: &str,
-        allocation_mode: &str,
-        image_id: &str,
-        burstable_mode: Option<&str>,
-        root_volume_gb: i64,
-        root_volume_type: &str,
-        root_volume_iops: Option<i64>,
+        spec: &InstanceSpec<'_>,
     ) -> Result<()> {
         match sqlx::query!(
             r#"UPDATE nodes SET
@@ -188,13 +309,13 @@ impl Node {
                    root_volume_type = ?,
                    root_volume_iops = ?
                WHERE id = ?"#,
-            instance_type,
-            allocation_mode,
-            image_id,
-            burstable_mode,
-            root_volume_gb,
-            root_volume_type,
-            root_volume_iops,
+            spec.instance_type,
+            spec.allocation_mode,
+            spec.image_id,
+            spec.burstable_mode,
+            spec.root_volume_gb,
+            spec.root_volume_type,
+            spec.root_volume_iops,
             self.id,
         )
         .execute(pool)
@@ -240,6 +361,30 @@ impl Node {
         Ok(())
     }
 
+    /// Removes this node along with its init commands, which reference it.
+    pub async fn delete(&self, pool: &SqlitePool) -> Result<()> {
+        let mut tx = pool.begin().await?;
+        if let Err(e) = sqlx::query!(r#"DELETE FROM shell_commands WHERE node_id = ?"#, self.id)
+            .execute(&mut *tx)
+            .await
+        {
+            anyhow::bail!("DB Operation Failure: {}", e);
+        }
+        match sqlx::query!(r#"DELETE FROM nodes WHERE id = ?"#, self.id)
+            .execute(&mut *tx)
+            .await
+        {
+            Ok(result) => {
+                if result.rows_affected() == 0 {
+                    anyhow::bail!("Node '{}' not found for deletion", self.id);
+                }
+            }
+            Err(e) => anyhow::bail!("DB Operation Failure: {}", e),
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
     /// Looks a node up by its private address WITHIN a cluster.
     ///
     /// The cluster_id is not optional. Every cluster this toolkit provisions uses
@@ -271,7 +416,14 @@ impl Node {
                 private_ip,
                 public_ip,
                 was_efs_configured,
-                was_ssh_configured
+                was_ssh_configured,
+                spot_max_price,
+                spot_request_type,
+                spot_interruption_behavior,
+                market_type,
+                efa_available,
+                availability_zone,
+                node_index
             FROM nodes
             WHERE cluster_id = ? AND private_ip = ?
         "#,
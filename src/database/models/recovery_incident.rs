@@ -0,0 +1,306 @@
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePool;
+
+/// Milestones of a recovery, in the order they are reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryPhase {
+    NoticeReceived,
+    FailureDetected,
+    EnisReleased,
+    InstancesRequested,
+    InstancesRunning,
+    SshReady,
+    EfsMounted,
+    InitDone,
+    TaskRelaunched,
+}
+
+impl RecoveryPhase {
+    pub const ALL: [RecoveryPhase; 9] = [
+        RecoveryPhase::NoticeReceived,
+        RecoveryPhase::FailureDetected,
+        RecoveryPhase::EnisReleased,
+        RecoveryPhase::InstancesRequested,
+        RecoveryPhase::InstancesRunning,
+        RecoveryPhase::SshReady,
+        RecoveryPhase::EfsMounted,
+        RecoveryPhase::InitDone,
+        RecoveryPhase::TaskRelaunched,
+    ];
+
+    /// Column of `recovery_incidents` holding the time the phase was reached.
+    fn column(&self) -> &'static str {
+        match self {
+            RecoveryPhase::NoticeReceived => "notice_received_at",
+            RecoveryPhase::FailureDetected => "failure_detected_at",
+            RecoveryPhase::EnisReleased => "enis_released_at",
+            RecoveryPhase::InstancesRequested => "instances_requested_at",
+            RecoveryPhase::InstancesRunning => "instances_running_at",
+            RecoveryPhase::SshReady => "ssh_ready_at",
+            RecoveryPhase::EfsMounted => "efs_mounted_at",
+            RecoveryPhase::InitDone => "init_done_at",
+            RecoveryPhase::TaskRelaunched => "task_relaunched_at",
+        }
+    }
+}
+
+impl std::fmt::Display for RecoveryPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let phase_str = match self {
+            RecoveryPhase::NoticeReceived => "notice_received",
+            RecoveryPhase::FailureDetected => "failure_detected",
+            RecoveryPhase::EnisReleased => "enis_released",
+            RecoveryPhase::InstancesRequested => "instances_requested",
+            RecoveryPhase::InstancesRunning => "instances_running",
+            RecoveryPhase::SshReady => "ssh_ready",
+            RecoveryPhase::EfsMounted => "efs_mounted",
+            RecoveryPhase::InitDone => "init_done",
+            RecoveryPhase::TaskRelaunched => "task_relaunched",
+        };
+        write!(f, "{}", phase_str)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RecoveryIncident {
+    pub id: i64,
+    pub cluster_id: String,
+    pub mode: String,
+    pub failed_ips: String, // JSON array
+    pub outcome: String,
+    pub error: Option<String>,
+    pub notice_received_at: Option<NaiveDateTime>,
+    pub failure_detected_at: NaiveDateTime,
+    pub enis_released_at: Option<NaiveDateTime>,
+    pub instances_requested_at: Option<NaiveDateTime>,
+    pub instances_running_at: Option<NaiveDateTime>,
+    pub ssh_ready_at: Option<NaiveDateTime>,
+    pub efs_mounted_at: Option<NaiveDateTime>,
+    pub init_done_at: Option<NaiveDateTime>,
+    pub task_relaunched_at: Option<NaiveDateTime>,
+    pub finished_at: Option<NaiveDateTime>,
+}
+
+impl RecoveryIncident {
+    /// Opens an incident for a failure detected now. Any incident of the cluster
+    /// still open belongs to a monitor that stopped mid-recovery and is closed as
+    /// 'abandoned', so phases recorded from here on land on the new one.
+    pub async fn open(
+        pool: &SqlitePool,
+        cluster_id: &str,
+        mode: &str,
+        failed_ips: &[String],
+        notice_received_at: Option<NaiveDateTime>,
+    ) -> Result<i64> {
+        let now = chrono::Utc::now().naive_utc();
+        let failed_ips = serde_json::to_string(failed_ips)?;
+
+        let mut tx = match pool.begin().await {
+            Ok(result) => result,
+            Err(e) => {
+                tracing::error!("SQLx Error: {:?}", e);
+                anyhow::bail!("DB Operation Failure: {}", e);
+            }
+        };
+
+        match sqlx::query!(
+            r#"
+                UPDATE recovery_incidents
+                SET outcome = 'abandoned', finished_at = ?
+                WHERE cluster_id = ? AND finished_at IS NULL
+            "#,
+            now,
+            cluster_id,
+        )
+        .execute(&mut *tx)
+        .await
+        {
+            Ok(_) => {}
+            Err(e) => {
+                tracing::error!("SQLx Error: {:?}", e);
+                anyhow::bail!("DB Operation Failure: {}", e);
+            }
+        }
+
+        let id = match sqlx::query!(
+            r#"
+                INSERT INTO recovery_incidents
+                    (cluster_id, mode, failed_ips, notice_received_at, failure_detected_at)
+                VALUES (?, ?, ?, ?, ?)
+            "#,
+            cluster_id,
+            mode,
+            failed_ips,
+            notice_received_at,
+            now,
+        )
+        .execute(&mut *tx)
+        .await
+        {
+            Ok(result) => result.last_insert_rowid(),
+            Err(e) => {
+                tracing::error!("SQLx Error: {:?}", e);
+                anyhow::bail!("DB Operation Failure: {}", e);
+            }
+        };
+
+        match tx.commit().await {
+            Ok(_) => {}
+            Err(e) => {
+                tracing::error!("SQLx Error: {:?}", e);
+                anyhow::bail!("DB Operation Failure: {}", e);
+            }
+        };
+
+        Ok(id)
+    }
+
+    /// Stamps `phase` with the current time on the cluster's open incident. Only
+    /// the first time counts, and outside a recovery there is no open incident,
+    /// so providers can call this on every spawn.
+    pub async fn record_phase(
+        pool: &SqlitePool,
+        cluster_id: &str,
+        phase: RecoveryPhase,
+    ) -> Result<()> {
+        let now = chrono::Utc::now().naive_utc();
+        let column = phase.column();
+        let query = format!(
+            "UPDATE recovery_incidents SET {column} = COALESCE({column}, ?) \
+             WHERE cluster_id = ? AND finished_at IS NULL"
+        );
+        match sqlx::query(&query)
+            .bind(now)
+            .bind(cluster_id)
+            .execute(pool)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!("SQLx Error: {:?}", e);
+                anyhow::bail!("DB Operation Failure: {}", e);
+            }
+        }
+    }
+
+    /// Closes the cluster's open incident with `outcome` ('recovered' or 'failed').
+    pub async fn finish(
+        pool: &SqlitePool,
+        cluster_id: &str,
+        outcome: &str,
+        error: Option<&str>,
+    ) -> Result<()> {
+        let now = chrono::Utc::now().naive_utc();
+        match sqlx::query!(
+            r#"
+                UPDATE recovery_incidents
+                SET outcome = ?, error = ?, finished_at = ?
+                WHERE cluster_id = ? AND finished_at IS NULL
+            "#,
+            outcome,
+            error,
+            now,
+            cluster_id,
+        )
+        .execute(pool)
+        .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!("SQLx Error: {:?}", e);
+                anyhow::bail!("DB Operation Failure: {}", e);
+            }
+        }
+    }
+
+    pub async fn fetch_all_by_cluster_id(
+        pool: &SqlitePool,
+        cluster_id: &str,
+    ) -> Result<Vec<RecoveryIncident>> {
+        let incidents = match sqlx::query_as!(
+            RecoveryIncident,
+            r#"
+                SELECT
+                    id as "id!",
+                    cluster_id,
+                    mode,
+                    failed_ips,
+                    outcome,
+                    error,
+                    notice_received_at,
+                    failure_detected_at,
+                    enis_released_at,
+                    instances_requested_at,
+                    instances_running_at,
+                    ssh_ready_at,
+                    efs_mounted_at,
+                    init_done_at,
+                    task_relaunched_at,
+                    finished_at
+                FROM recovery_incidents
+                WHERE cluster_id = ?
+                ORDER BY id
+            "#,
+            cluster_id
+        )
+        .fetch_all(pool)
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                tracing::error!("SQLx Error: {:?}", e);
+                anyhow::bail!("DB Operation Failure: {}", e);
+            }
+        };
+
+        Ok(incidents)
+    }
+
+    pub fn failed_ips(&self) -> Vec<String> {
+        serde_json::from_str(&self.failed_ips).unwrap_or_default()
+    }
+
+    pub fn reached_at(&self, phase: RecoveryPhase) -> Option<NaiveDateTime> {
+        match phase {
+            RecoveryPhase::NoticeReceived => self.notice_received_at,
+            RecoveryPhase::FailureDetected => Some(self.failure_detected_at),
+            RecoveryPhase::EnisReleased => self.enis_released_at,
+            RecoveryPhase::InstancesRequested => self.instances_requested_at,
+            RecoveryPhase::InstancesRunning => self.instances_running_at,
+            RecoveryPhase::SshReady => self.ssh_ready_at,
+            RecoveryPhase::EfsMounted => self.efs_mounted_at,
+            RecoveryPhase::InitDone => self.init_done_at,
+            RecoveryPhase::TaskRelaunched => self.task_relaunched_at,
+        }
+    }
+
+    /// Seconds spent reaching each phase, counted from the previous phase that
+    /// was reached. `None` for phases never reached, and for the first one.
+    pub fn phase_durations(&self) -> Vec<(RecoveryPhase, Option<f64>)> {
+        let mut previous: Option<NaiveDateTime> = None;
+        RecoveryPhase::ALL
+            .iter()
+            .map(|&phase| {
+                let reached = self.reached_at(phase);
+                let duration = match (previous, reached) {
+                    (Some(start), Some(end)) => {
+                        Some((end - start).num_milliseconds() as f64 / 1000.0)
+                    }
+                    _ => None,
+                };
+                if reached.is_some() {
+                    previous = reached;
+                }
+                (phase, duration)
+            })
+            .collect()
+    }
+
+    /// Seconds from detecting the failure to closing the incident.
+    pub fn total_duration(&self) -> Option<f64> {
+        self.finished_at
+            .map(|end| (end - self.failure_detected_at).num_milliseconds() as f64 / 1000.0)
+    }
+}
@@ -2,6 +2,80 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use sqlx::{Transaction, sqlite::SqlitePool};
 
+/// How a restore picks among a recovery slot's preferred instance types,
+/// `on_interruption.selection_policy` in the cluster YAML.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectionPolicy {
+    /// In the declared order.
+    #[default]
+    Ordered,
+    /// Lowest hourly price first.
+    Cheapest,
+    /// Lowest hourly price per vCPU first.
+    CheapestPerVcpu,
+}
+
+impl std::fmt::Display for SelectionPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SelectionPolicy::Ordered => write!(f, "ordered"),
+            SelectionPolicy::Cheapest => write!(f, "cheapest"),
+            SelectionPolicy::CheapestPerVcpu => write!(f, "cheapest_per_vcpu"),
+        }
+    }
+}
+
+impl std::str::FromStr for SelectionPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "ordered" => Ok(SelectionPolicy::Ordered),
+            "cheapest" => Ok(SelectionPolicy::Cheapest),
+            "cheapest_per_vcpu" => Ok(SelectionPolicy::CheapestPerVcpu),
+            _ => Err(format!("Invalid selection policy: {}", s)),
+        }
+    }
+}
+
+/// A recovery candidate with what it costs at the moment.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PricedInstanceType {
+    pub name: String,
+    pub vcpus: i64,
+    /// None when no price is known for the type.
+    pub price_per_hour: Option<f64>,
+}
+
+impl SelectionPolicy {
+    /// Orders `candidates`, given in declared order, by this policy. Ties keep
+    /// the declared order, and so do types without a price, after the priced
+    /// ones. With a `max_price_per_hour`, types above it are dropped, as are
+    /// those without a price since they cannot be shown to be below it.
+    pub fn rank(
+        &self,
+        mut candidates: Vec<PricedInstanceType>,
+        max_price_per_hour: Option<f64>,
+    ) -> Vec<PricedInstanceType> {
+        if let Some(max_price) = max_price_per_hour {
+            candidates.retain(|c| c.price_per_hour.is_some_and(|price| price <= max_price));
+        }
+        let key = |c: &PricedInstanceType| match self {
+            SelectionPolicy::Ordered => Some(0.0),
+            SelectionPolicy::Cheapest => c.price_per_hour,
+            SelectionPolicy::CheapestPerVcpu => c.price_per_hour.map(|p| p / c.vcpus.max(1) as f64),
+        };
+        candidates.sort_by(|a, b| match (key(a), key(b)) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
+        candidates
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct RecoveryNode {
     pub id: String,
@@ -25,6 +99,10 @@ pub struct RecoveryNode {
     /// stand-in for a CPU node has a local NVMe store to mount that the original
     /// never had.
     pub init_commands: Option<String>,
+    /// preferred_instance_types as ranked and filtered by the cluster's
+    /// selection policy at the latest restore, as a JSON array. NULL means the
+    /// declared order.
+    pub selected_instance_types: Option<String>,
 }
 
 impl RecoveryNode {
@@ -38,6 +116,39 @@ impl RecoveryNode {
         self.instance_types().into_iter().next()
     }
 
+    /// Instance types a restore launches this slot with, in the order they are
+    /// tried: as the selection policy last ranked them, or as declared.
+    pub fn launch_order(&self) -> Vec<String> {
+        self.selected_instance_types
+            .as_deref()
+            .and_then(|types| serde_json::from_str(types).ok())
+            .unwrap_or_else(|| self.instance_types())
+    }
+
+    pub async fn set_selected_instance_types(
+        &self,
+        pool: &SqlitePool,
+        instance_types: &[String],
+    ) -> Result<()> {
+        let selected_instance_types = serde_json::to_string(instance_types)?;
+        match sqlx::query!(
+            r#"UPDATE recovery_nodes SET selected_instance_types = ? WHERE id = ?"#,
+            selected_instance_types,
+            self.id
+        )
+        .execute(pool)
+        .await
+        {
+            Ok(result) => {
+                if result.rows_affected() == 0 {
+                    anyhow::bail!("Recovery node '{}' not found for selection update", self.id);
+                }
+            }
+            Err(e) => anyhow::bail!("DB Operation Failure: {}", e),
+        }
+        Ok(())
+    }
+
     /// Init commands declared for this slot, in order. `None` means the slot
     /// declares none and the inherit behaviour applies; `Some(vec![])` means an
     /// explicitly empty list, which clears the replacement's commands.
@@ -71,9 +182,10 @@ impl RecoveryNode {
                     root_volume_type,
                     root_volume_iops,
                     count,
-                    init_commands
+                    init_commands,
+                    selected_instance_types
                 )
-                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
             self.id,
             self.cluster_id,
@@ -86,6 +198,7 @@ impl RecoveryNode {
             self.root_volume_iops,
             self.count,
             self.init_commands,
+            self.selected_instance_types,
         )
         .execute(&mut **tx)
         .await
@@ -118,7 +231,8 @@ impl RecoveryNode {
                     root_volume_type,
                     root_volume_iops,
                     count,
-                    init_commands
+                    init_commands,
+                    selected_instance_types
                 FROM recovery_nodes
                 WHERE cluster_id = ?
             "#,
@@ -0,0 +1,256 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sqlx::{Type, sqlite::SqlitePool};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SpawnStepKind {
+    // Cluster-wide
+    Vpc,
+    Subnet,
+    InternetGateway,
+    RouteTable,
+    SecurityGroup,
+    EfsDevice,
+    EfsMountTarget,
+    SshKey,
+    PlacementGroup,
+    // Per node
+    NetworkInterface,
+    ElasticIp,
+    Instance,
+    Ssh,
+    EfsMount,
+    Init,
+}
+
+impl SpawnStepKind {
+    /// Steps that describe the node's current instance rather than resources
+    /// that outlive it. They no longer hold once the instance is replaced.
+    pub const INSTANCE_STEPS: [SpawnStepKind; 4] = [
+        SpawnStepKind::Instance,
+        SpawnStepKind::Ssh,
+        SpawnStepKind::EfsMount,
+        SpawnStepKind::Init,
+    ];
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SpawnStep {
+    node_id: String,
+    step: SpawnStepKind,
+    resource_id: Option<String>,
+}
+
+/// Completed steps of a cluster's spawn, loaded once and kept in sync with the
+/// `spawn_steps` table as a spawn makes progress.
+pub struct SpawnProgress {
+    cluster_id: String,
+    /// (node_id, step) -> resource_id. node_id is '' for cluster-wide steps.
+    steps: HashMap<(String, SpawnStepKind), Option<String>>,
+}
+
+impl SpawnProgress {
+    pub async fn load(pool: &SqlitePool, cluster_id: &str) -> Result<Self> {
+        let rows = match sqlx::query_as!(
+            SpawnStep,
+            r#"
+                SELECT
+                    node_id,
+                    step as "step: SpawnStepKind",
+                    resource_id
+                FROM spawn_steps
+                WHERE cluster_id = ?
+            "#,
+            cluster_id
+        )
+        .fetch_all(pool)
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                tracing::error!("SQLx Error: {:?}", e);
+                anyhow::bail!("DB Operation Failure: {}", e);
+            }
+        };
+
+        Ok(Self {
+            cluster_id: cluster_id.to_string(),
+            steps: rows
+                .into_iter()
+                .map(|row| ((row.node_id, row.step), row.resource_id))
+                .collect(),
+        })
+    }
+
+    /// Forgets every completed step of the cluster, so the next spawn starts over.
+    pub async fn clear(pool: &SqlitePool, cluster_id: &str) -> Result<()> {
+        match sqlx::query!("DELETE FROM spawn_steps WHERE cluster_id = ?", cluster_id)
+            .execute(pool)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!("SQLx Error: {:?}", e);
+                anyhow::bail!("DB Operation Failure: {}", e);
+            }
+        }
+    }
+
+    /// Forgets the steps tied to a node's instance. Called when the instance is
+    /// gone, so the next spawn brings its replacement all the way up.
+    pub async fn clear_instance_steps(
+        pool: &SqlitePool,
+        cluster_id: &str,
+        node_id: &str,
+    ) -> Result<()> {
+        let [instance, ssh, efs_mount, init] = SpawnStepKind::INSTANCE_STEPS;
+        match sqlx::query!(
+            r#"
+                DELETE FROM spawn_steps
+                WHERE cluster_id = ? AND node_id = ? AND step IN (?, ?, ?, ?)
+            "#,
+            cluster_id,
+            node_id,
+            instance,
+            ssh,
+            efs_mount,
+            init,
+        )
+        .execute(pool)
+        .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!("SQLx Error: {:?}", e);
+                anyhow::bail!("DB Operation Failure: {}", e);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Whether the step was completed. `node_id` is None for cluster-wide steps.
+    pub fn is_completed(&self, node_id: Option<&str>, step: SpawnStepKind) -> bool {
+        self.steps
+            .contains_key(&(node_id.unwrap_or_default().to_string(), step))
+    }
+
+    /// The resource a completed step created, if it recorded one.
+    pub fn resource_id(&self, node_id: Option<&str>, step: SpawnStepKind) -> Option<&str> {
+        self.steps
+            .get(&(node_id.unwrap_or_default().to_string(), step))
+            .and_then(|id| id.as_deref())
+    }
+
+    pub async fn complete(
+        &mut self,
+        pool: &SqlitePool,
+        node_id: Option<&str>,
+        step: SpawnStepKind,
+        resource_id: Option<&str>,
+    ) -> Result<()> {
+        let node_id = node_id.unwrap_or_default();
+        let completed_at = chrono::Utc::now().naive_utc();
+        match sqlx::query!(
+            r#"
+                INSERT INTO spawn_steps (cluster_id, node_id, step, resource_id, completed_at)
+                VALUES (?, ?, ?, ?, ?)
+                ON CONFLICT(cluster_id, node_id, step) DO UPDATE SET
+                    resource_id = excluded.resource_id,
+                    completed_at = excluded.completed_at
+            "#,
+            self.cluster_id,
+            node_id,
+            step,
+            resource_id,
+            completed_at,
+        )
+        .execute(pool)
+        .await
+        {
+            Ok(_) => {}
+            Err(e) => {
+                tracing::error!("SQLx Error: {:?}", e);
+                anyhow::bail!("DB Operation Failure: {}", e);
+            }
+        };
+
+        self.steps.insert(
+            (node_id.to_string(), step),
+            resource_id.map(|id| id.to_string()),
+        );
+        Ok(())
+    }
+
+    /// The resource `ensure` finds or creates, recorded as the step's. A resumed
+    /// step still runs it, so a resource deleted since it was recorded is
+    /// created again instead of failing a later step.
+    pub async fn resume_or(
+        &mut self,
+        pool: &SqlitePool,
+        node_id: Option<&str>,
+        step: SpawnStepKind,
+        ensure: impl Future<Output = Result<String>>,
+    ) -> Result<String> {
+        let resource_id = ensure.await?;
+        match self.resource_id(node_id, step) {
+            Some(recorded) if recorded == resource_id => {
+                tracing::info!(
+                    "Resuming with '{}' from a completed {:?} step",
+                    resource_id,
+                    step
+                );
+                return Ok(resource_id);
+            }
+            Some(recorded) => tracing::warn!(
+                "'{}' from a completed {:?} step no longer exists, using '{}'",
+                recorded,
+                step,
+                resource_id
+            ),
+            None => {}
+        }
+        self.complete(pool, node_id, step, Some(&resource_id))
+            .await?;
+        Ok(resource_id)
+    }
+
+    /// Records that the node now runs on `instance_id`. When a recorded instance
+    /// was replaced since, the node's later steps were done on an instance that no
+    /// longer exists and are forgotten.
+    pub async fn complete_instance(
+        &mut self,
+        pool: &SqlitePool,
+        node_id: &str,
+        instance_id: &str,
+    ) -> Result<()> {
+        let recorded = self.resource_id(Some(node_id), SpawnStepKind::Instance);
+        if recorded.is_some_and(|recorded| recorded != instance_id) {
+            tracing::info!(
+                "Node '{}' is on a new instance '{}', redoing its setup",
+                node_id,
+                instance_id
+            );
+            SpawnProgress::clear_instance_steps(pool, &self.cluster_id, node_id).await?;
+            for step in SpawnStepKind::INSTANCE_STEPS {
+                self.steps.remove(&(node_id.to_string(), step));
+            }
+        }
+        self.complete(
+            pool,
+            Some(node_id),
+            SpawnStepKind::Instance,
+            Some(instance_id),
+        )
+        .await
+    }
+}
@@ -1,6 +1,13 @@
+use crate::database::models::{ClusterEvent, ClusterEventType};
+
 use anyhow::Result;
 use chrono::NaiveDateTime;
 use sqlx::sqlite::SqlitePool;
+use std::collections::{BTreeMap, HashSet};
+
+/// Identifies a run within a tasks file: task name, run index and matrix
+/// parameters (empty for tasks without a matrix).
+pub type TaskRunKey = (String, i64, BTreeMap<String, String>);
 
 #[derive(Debug, sqlx::FromRow)]
 pub struct TaskRun {
@@ -8,9 +15,39 @@ pub struct TaskRun {
     pub cluster_id: String,
     pub task_name: String,
     pub run_index: i64,
+    pub tasks_file_hash: Option<String>,
+    pub node_ids: Option<String>, // JSON array
+    pub params: Option<String>,   // JSON object
+    pub attempt: i64,
+    pub launcher: Option<String>,
+    pub checkpoint_signal: Option<String>, // JSON object
     pub status: String,
     pub started_at: NaiveDateTime,
     pub finished_at: Option<NaiveDateTime>,
+    pub exit_code: Option<i64>,
+    pub wall_secs: Option<f64>,
+    pub log_tail: Option<String>,
+    pub log_path: Option<String>,
+}
+
+/// Where a run's job runs and how it is asked to checkpoint.
+#[derive(Debug)]
+pub struct TaskRunJob<'a> {
+    pub node_ids: &'a [String],
+    pub launcher: &'a str,
+    /// JSON object, None to signal the job through its launcher.
+    pub checkpoint_signal: Option<&'a str>,
+}
+
+/// What a run left behind when it finished. Every field is optional: a run that
+/// never started in tmux, or whose log could not be read, has none of it.
+#[derive(Debug, Default)]
+pub struct TaskRunOutput {
+    pub exit_code: Option<i64>,
+    /// The last lines of the run's log.
+    pub log_tail: Option<String>,
+    /// Where the full log was downloaded to.
+    pub log_path: Option<String>,
 }
 
 impl TaskRun {
@@ -18,18 +55,37 @@ impl TaskRun {
     pub async fn start(
         pool: &SqlitePool,
         cluster_id: &str,
-        task_name: &str,
-        run_index: i64,
+        tasks_file_hash: &str,
+        key: &TaskRunKey,
+        attempt: i64,
+        job: &TaskRunJob<'_>,
     ) -> Result<Self> {
+        let (task_name, run_index, params) = key;
+        let node_ids = job.node_ids;
+        let run_index = *run_index;
         let started_at = chrono::Utc::now().naive_utc();
+        let node_ids_json = serde_json::to_string(node_ids)?;
+        let params_json = if params.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(params)?)
+        };
         let id = match sqlx::query!(
             r#"
-                INSERT INTO task_runs (cluster_id, task_name, run_index, status, started_at)
-                VALUES (?, ?, ?, 'running', ?)
+                INSERT INTO task_runs
+                    (cluster_id, task_name, run_index, tasks_file_hash, node_ids, params, attempt,
+                     launcher, checkpoint_signal, status, started_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, 'running', ?)
             "#,
             cluster_id,
             task_name,
             run_index,
+            tasks_file_hash,
+            node_ids_json,
+            params_json,
+            attempt,
+            job.launcher,
+            job.checkpoint_signal,
             started_at,
         )
         .execute(pool)
@@ -39,35 +95,179 @@ impl TaskRun {
             Err(e) => anyhow::bail!("DB Operation Failure: {}", e),
         };
 
+        ClusterEvent::record(
+            pool,
+            cluster_id,
+            ClusterEventType::TaskRunStarted,
+            None,
+            &format!(
+                "Task '{}' run {} started (attempt {})",
+                task_name, run_index, attempt
+            ),
+            Some(serde_json::json!({
+                "task_run_id": id,
+                "task_name": task_name,
+                "run_index": run_index,
+                "node_ids": node_ids,
+                "params": params,
+                "attempt": attempt,
+            })),
+        )
+        .await?;
+
         Ok(Self {
             id,
             cluster_id: cluster_id.to_string(),
             task_name: task_name.to_string(),
             run_index,
+            tasks_file_hash: Some(tasks_file_hash.to_string()),
+            node_ids: Some(node_ids_json),
+            params: params_json,
+            attempt,
+            launcher: Some(job.launcher.to_string()),
+            checkpoint_signal: job.checkpoint_signal.map(|c| c.to_string()),
             status: "running".to_string(),
             started_at,
             finished_at: None,
+            exit_code: None,
+            wall_secs: None,
+            log_tail: None,
+            log_path: None,
         })
     }
 
-    /// Mark the task run as finished with the given status ("success" or "failed").
-    pub async fn finish(&self, pool: &SqlitePool, status: &str) -> Result<()> {
+    /// Every run of the cluster, oldest first.
+    pub async fn fetch_by_cluster_id(pool: &SqlitePool, cluster_id: &str) -> Result<Vec<Self>> {
+        match sqlx::query_as!(
+            TaskRun,
+            r#"
+                SELECT
+                    id as "id!",
+                    cluster_id,
+                    task_name,
+                    run_index,
+                    tasks_file_hash,
+                    node_ids,
+                    params,
+                    attempt,
+                    launcher,
+                    checkpoint_signal,
+                    status,
+                    started_at,
+                    finished_at,
+                    exit_code,
+                    wall_secs,
+                    log_tail,
+                    log_path
+                FROM task_runs
+                WHERE cluster_id = ?
+                ORDER BY id
+            "#,
+            cluster_id
+        )
+        .fetch_all(pool)
+        .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => anyhow::bail!("DB Operation Failure: {}", e),
+        }
+    }
+
+    /// The runs of the cluster still in progress.
+    pub async fn fetch_running(pool: &SqlitePool, cluster_id: &str) -> Result<Vec<Self>> {
+        Ok(Self::fetch_by_cluster_id(pool, cluster_id)
+            .await?
+            .into_iter()
+            .filter(|run| run.status == "running")
+            .collect())
+    }
+
+    /// Every run of the tasks file with hash `tasks_file_hash` that finished
+    /// successfully on the cluster.
+    pub async fn fetch_succeeded(
+        pool: &SqlitePool,
+        cluster_id: &str,
+        tasks_file_hash: &str,
+    ) -> Result<HashSet<TaskRunKey>> {
+        let rows = match sqlx::query!(
+            r#"
+                SELECT task_name, run_index, params
+                FROM task_runs
+                WHERE cluster_id = ? AND tasks_file_hash = ? AND status = 'success'
+            "#,
+            cluster_id,
+            tasks_file_hash,
+        )
+        .fetch_all(pool)
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => anyhow::bail!("DB Operation Failure: {}", e),
+        };
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let params = row
+                    .params
+                    .and_then(|p| serde_json::from_str(&p).ok())
+                    .unwrap_or_default();
+                (row.task_name, row.run_index, params)
+            })
+            .collect())
+    }
+
+    /// Mark the task run as finished with the given status ("success", "failed"
+    /// or "timeout") and store what it left behind.
+    pub async fn finish(
+        &self,
+        pool: &SqlitePool,
+        status: &str,
+        output: &TaskRunOutput,
+    ) -> Result<()> {
         let finished_at = chrono::Utc::now().naive_utc();
+        let wall_secs = (finished_at - self.started_at).num_milliseconds() as f64 / 1000.0;
         match sqlx::query!(
             r#"
                 UPDATE task_runs
-                SET status = ?, finished_at = ?
+                SET status = ?, finished_at = ?, exit_code = ?, wall_secs = ?, log_tail = ?,
+                    log_path = ?
                 WHERE id = ?
             "#,
             status,
             finished_at,
+            output.exit_code,
+            wall_secs,
+            output.log_tail,
+            output.log_path,
             self.id,
         )
         .execute(pool)
         .await
         {
-            Ok(_) => Ok(()),
+            Ok(_) => {}
             Err(e) => anyhow::bail!("DB Operation Failure: {}", e),
         }
+
+        ClusterEvent::record(
+            pool,
+            &self.cluster_id,
+            ClusterEventType::TaskRunEnded,
+            None,
+            &format!(
+                "Task '{}' run {} ended: {}",
+                self.task_name, self.run_index, status
+            ),
+            Some(serde_json::json!({
+                "task_run_id": self.id,
+                "task_name": self.task_name,
+                "run_index": self.run_index,
+                "attempt": self.attempt,
+                "status": status,
+                "exit_code": output.exit_code,
+                "wall_secs": wall_secs,
+            })),
+        )
+        .await
     }
 }
@@ -0,0 +1,895 @@
+//! The model layer against a database built from `migrations/`.
+
+use crate::database::models::{
+    Cluster, ClusterEvent, ClusterEventType, ClusterState, ConfigVar, InstanceSpec, InstanceType,
+    Node, PricedInstanceType, ProviderConfig, RecoveryNode, SelectionPolicy, ShellCommand,
+    SpawnProgress, SpawnStepKind, TaskRun, TaskRunJob, TaskRunKey, TaskRunOutput, WatchLock,
+};
+use crate::testing::TestEnv;
+
+use sqlx::sqlite::SqlitePool;
+use std::collections::{BTreeMap, HashSet};
+use std::str::FromStr;
+
+async fn insert_provider_config(pool: &SqlitePool, display_name: &str) -> ProviderConfig {
+    let config_vars = ["ACCESS_KEY_ID", "SECRET_ACCESS_KEY"]
+        .into_iter()
+        .map(|key| ConfigVar {
+            id: 0,
+            provider_config_id: 0,
+            key: key.to_string(),
+            value: format!("{}-value", key.to_lowercase()),
+        })
+        .collect();
+    ProviderConfig::insert(
+        pool,
+        display_name.to_string(),
+        "aws".to_string(),
+        config_vars,
+    )
+    .await
+    .unwrap();
+    ProviderConfig::fetch_all_by_provider(pool, "aws")
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|c| c.display_name == display_name)
+        .unwrap()
+}
+
+fn sample_cluster(id: &str, provider_config_id: i64) -> Cluster {
+    Cluster {
+        id: id.to_string(),
+        display_name: format!("{}-name", id),
+        provider_id: "aws".to_string(),
+        provider_config_id,
+        public_ssh_key_path: "~/.ssh/id_rsa.pub".to_string(),
+        private_ssh_key_path: "~/.ssh/id_rsa".to_string(),
+        region: "us-east-1".to_string(),
+        availability_zone: "us-east-1a".to_string(),
+        use_node_affinity: false,
+        use_elastic_fabric_adapters: false,
+        use_elastic_file_system: false,
+        efs_performance_mode: "general_purpose".to_string(),
+        efs_throughput_mode: "bursting".to_string(),
+        efs_provisioned_throughput_mbs: None,
+        created_at: chrono::Utc::now().naive_utc(),
+        state: ClusterState::Pending,
+        cost_per_hour: 0.25,
+        cost_breakdown: "{}".to_string(),
+        checkpoint_signal: None,
+        scheduler: None,
+        health_probes: None,
+        fallback_availability_zones: None,
+        recovery_selection_policy: None,
+        recovery_max_price_per_hour: None,
+    }
+}
+
+fn sample_node(cluster_id: &str, id: &str, node_index: i64) -> Node {
+    Node {
+        id: id.to_string(),
+        cluster_id: cluster_id.to_string(),
+        instance_type: "t3.micro".to_string(),
+        allocation_mode: "on-demand".to_string(),
+        burstable_mode: None,
+        image_id: "ami-0123".to_string(),
+        root_volume_gb: 20,
+        root_volume_type: "gp3".to_string(),
+        root_volume_iops: None,
+        private_ip: None,
+        public_ip: None,
+        was_efs_configured: false,
+        was_ssh_configured: false,
+        spot_max_price: None,
+        spot_request_type: None,
+        spot_interruption_behavior: None,
+        market_type: None,
+        efa_available: None,
+        availability_zone: None,
+        node_index,
+    }
+}
+
+fn sample_command(node_id: &str, ordering: i64, script: &str) -> ShellCommand {
+    ShellCommand {
+        id: 0,
+        ordering,
+        node_id: node_id.to_string(),
+        script: script.to_string(),
+        status: "NOT_EXECUTED".to_string(),
+        result: None,
+        triggered_at: None,
+        execution_time: None,
+    }
+}
+
+fn sample_recovery_node(cluster_id: &str) -> RecoveryNode {
+    RecoveryNode {
+        id: format!("{}-recovery-0", cluster_id),
+        cluster_id: cluster_id.to_string(),
+        allocation_mode: "spot".to_string(),
+        preferred_instance_types: r#"["c7i.large","c6i.large"]"#.to_string(),
+        burstable_mode: None,
+        image_id: "ami-0456".to_string(),
+        root_volume_gb: 30,
+        root_volume_type: "gp3".to_string(),
+        root_volume_iops: None,
+        count: 2,
+        init_commands: Some(r#"["echo replacement"]"#.to_string()),
+        selected_instance_types: None,
+    }
+}
+
+fn run_key(task_name: &str, run_index: i64) -> TaskRunKey {
+    (task_name.to_string(), run_index, BTreeMap::new())
+}
+
+fn job(node_ids: &[String]) -> TaskRunJob<'_> {
+    TaskRunJob {
+        node_ids,
+        launcher: "openmpi",
+        checkpoint_signal: None,
+    }
+}
+
+/// Inserts a cluster with two nodes, two init commands on the first node and
+/// one recovery slot.
+async fn insert_cluster(pool: &SqlitePool, id: &str) -> Cluster {
+    let provider_config = insert_provider_config(pool, &format!("{}-config", id)).await;
+    let cluster = sample_cluster(id, provider_config.id);
+    let nodes = vec![
+        sample_node(id, &format!("{}-node-0", id), 0),
+        sample_node(id, &format!("{}-node-1", id), 1),
+    ];
+    let commands = vec![
+        sample_command(&nodes[0].id, 2, "echo second"),
+        sample_command(&nodes[0].id, 1, "echo first"),
+    ];
+    cluster
+        .insert(pool, nodes, commands, vec![sample_recovery_node(id)])
+        .await
+        .unwrap();
+    cluster
+}
+
+async fn count(pool: &SqlitePool, table: &str) -> i64 {
+    sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table))
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn provider_config_insert_fetch_and_delete() {
+    let env = TestEnv::new().await;
+    let config = insert_provider_config(&env.pool, "main").await;
+
+    let fetched = ProviderConfig::fetch_by_id(&env.pool, config.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(fetched.display_name, "main");
+    assert_eq!(fetched.provider_id, "aws");
+    let config_vars = fetched.get_config_vars(&env.pool).await.unwrap();
+    assert_eq!(config_vars.len(), 2);
+    assert!(
+        config_vars
+            .iter()
+            .any(|v| v.key == "ACCESS_KEY_ID" && v.value == "access_key_id-value")
+    );
+
+    let duplicate =
+        ProviderConfig::insert(&env.pool, "main".to_string(), "aws".to_string(), vec![]).await;
+    assert!(
+        duplicate
+            .unwrap_err()
+            .to_string()
+            .contains("already exists")
+    );
+
+    fetched.delete(&env.pool).await.unwrap();
+    assert!(
+        ProviderConfig::fetch_by_id(&env.pool, config.id)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(count(&env.pool, "config_variables").await, 0);
+}
+
+#[tokio::test]
+async fn cluster_insert_fetch_and_delete() {
+    let env = TestEnv::new().await;
+    let cluster = insert_cluster(&env.pool, "alpha").await;
+
+    let fetched = Cluster::fetch_by_id(&env.pool, "alpha")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(fetched.display_name, "alpha-name");
+    assert_eq!(fetched.state, ClusterState::Pending);
+    assert_eq!(fetched.cost_per_hour, 0.25);
+    assert!(
+        Cluster::fetch_by_name(&env.pool, "alpha-name")
+            .await
+            .unwrap()
+            .is_some()
+    );
+    assert_eq!(Cluster::fetch_all(&env.pool).await.unwrap().len(), 1);
+
+    // Nodes come back in insertion order, and commands in their declared order.
+    let nodes = cluster.get_nodes(&env.pool).await.unwrap();
+    let node_ids: Vec<&str> = nodes.iter().map(|n| n.id.as_str()).collect();
+    assert_eq!(node_ids, ["alpha-node-0", "alpha-node-1"]);
+    assert_eq!(
+        nodes[0].get_init_commands(&env.pool).await.unwrap(),
+        ["echo first", "echo second"]
+    );
+    assert!(
+        nodes[1]
+            .get_init_commands(&env.pool)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let recovery_nodes = RecoveryNode::fetch_all_by_cluster_id(&env.pool, "alpha")
+        .await
+        .unwrap();
+    assert_eq!(recovery_nodes.len(), 1);
+    assert_eq!(
+        recovery_nodes[0].instance_types(),
+        ["c7i.large", "c6i.large"]
+    );
+    assert_eq!(
+        recovery_nodes[0].primary_instance_type().as_deref(),
+        Some("c7i.large")
+    );
+    assert_eq!(
+        recovery_nodes[0].declared_init_commands(),
+        Some(vec!["echo replacement".to_string()])
+    );
+    assert_eq!(recovery_nodes[0].count, 2);
+    assert_eq!(recovery_nodes[0].launch_order(), ["c7i.large", "c6i.large"]);
+    recovery_nodes[0]
+        .set_selected_instance_types(&env.pool, &["c6i.large".to_string()])
+        .await
+        .unwrap();
+    let recovery_nodes = RecoveryNode::fetch_all_by_cluster_id(&env.pool, "alpha")
+        .await
+        .unwrap();
+    assert_eq!(recovery_nodes[0].launch_order(), ["c6i.large"]);
+    assert_eq!(
+        recovery_nodes[0].primary_instance_type().as_deref(),
+        Some("c7i.large")
+    );
+
+    TaskRun::start(
+        &env.pool,
+        "alpha",
+        "abc",
+        &run_key("solve", 0),
+        1,
+        &job(&[]),
+    )
+    .await
+    .unwrap();
+
+    // Deleting the cluster clears every row that references it.
+    Cluster::delete(&env.pool, "alpha").await.unwrap();
+    assert!(
+        Cluster::fetch_by_id(&env.pool, "alpha")
+            .await
+            .unwrap()
+            .is_none()
+    );
+    for table in [
+        "nodes",
+        "shell_commands",
+        "recovery_nodes",
+        "task_runs",
+        "cluster_events",
+    ] {
+        assert_eq!(count(&env.pool, table).await, 0, "rows left in {}", table);
+    }
+    assert!(Cluster::delete(&env.pool, "alpha").await.is_err());
+}
+
+#[test]
+fn cluster_restore_zones_start_where_the_node_is() {
+    let mut cluster = sample_cluster("alpha", 1);
+    assert_eq!(cluster.restore_zones(None), vec!["us-east-1a"]);
+
+    cluster.fallback_availability_zones = Some(r#"["us-east-1b","us-east-1c"]"#.to_string());
+    assert_eq!(
+        cluster.restore_zones(None),
+        vec!["us-east-1a", "us-east-1b", "us-east-1c"]
+    );
+    assert_eq!(
+        cluster.restore_zones(Some("us-east-1c")),
+        vec!["us-east-1c", "us-east-1a", "us-east-1b"]
+    );
+}
+
+#[test]
+fn selection_policy_ranks_recovery_candidates() {
+    let priced = |name: &str, vcpus: i64, price: Option<f64>| PricedInstanceType {
+        name: name.to_string(),
+        vcpus,
+        price_per_hour: price,
+    };
+    let candidates = vec![
+        priced("c7i.xlarge", 4, Some(0.20)),
+        priced("c6i.large", 2, Some(0.08)),
+        priced("c5.2xlarge", 8, Some(0.24)),
+        priced("c6a.large", 2, None),
+    ];
+    let names = |ranked: Vec<PricedInstanceType>| -> Vec<String> {
+        ranked.into_iter().map(|c| c.name).collect()
+    };
+
+    assert_eq!(
+        names(SelectionPolicy::Ordered.rank(candidates.clone(), None)),
+        ["c7i.xlarge", "c6i.large", "c5.2xlarge", "c6a.large"]
+    );
+    assert_eq!(
+        names(SelectionPolicy::Cheapest.rank(candidates.clone(), None)),
+        ["c6i.large", "c7i.xlarge", "c5.2xlarge", "c6a.large"]
+    );
+    assert_eq!(
+        names(SelectionPolicy::CheapestPerVcpu.rank(candidates.clone(), None)),
+        ["c5.2xlarge", "c6i.large", "c7i.xlarge", "c6a.large"]
+    );
+    // The ceiling drops what is above it and what has no price.
+    assert_eq!(
+        names(SelectionPolicy::Ordered.rank(candidates.clone(), Some(0.20))),
+        ["c7i.xlarge", "c6i.large"]
+    );
+    assert!(
+        SelectionPolicy::Cheapest
+            .rank(candidates, Some(0.05))
+            .is_empty()
+    );
+
+    assert_eq!(
+        "cheapest_per_vcpu".parse::<SelectionPolicy>(),
+        Ok(SelectionPolicy::CheapestPerVcpu)
+    );
+    assert!("priciest".parse::<SelectionPolicy>().is_err());
+}
+
+#[tokio::test]
+async fn cluster_rejects_nodes_of_another_cluster() {
+    let env = TestEnv::new().await;
+    let provider_config = insert_provider_config(&env.pool, "main").await;
+    let cluster = sample_cluster("alpha", provider_config.id);
+
+    let result = cluster
+        .insert(
+            &env.pool,
+            vec![sample_node("beta", "stray", 0)],
+            vec![],
+            vec![],
+        )
+        .await;
+    assert!(result.is_err());
+    // The transaction rolled back, so not even the cluster row was written.
+    assert_eq!(count(&env.pool, "clusters").await, 0);
+}
+
+#[tokio::test]
+async fn cluster_state_round_trip() {
+    let env = TestEnv::new().await;
+    let cluster = insert_cluster(&env.pool, "alpha").await;
+
+    for state in [
+        ClusterState::Spawning,
+        ClusterState::Running,
+        ClusterState::Restoring,
+        ClusterState::Terminating,
+        ClusterState::Terminated,
+        ClusterState::Failed,
+        ClusterState::Pending,
+    ] {
+        cluster
+            .update_state(&env.pool, state.clone())
+            .await
+            .unwrap();
+        let fetched = Cluster::fetch_by_id(&env.pool, "alpha")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(fetched.state, state);
+
+        // The stored text is the Display form, which FromStr reads back.
+        let stored: String = sqlx::query_scalar("SELECT state FROM clusters WHERE id = 'alpha'")
+            .fetch_one(&env.pool)
+            .await
+            .unwrap();
+        assert_eq!(stored, state.to_string());
+        assert_eq!(ClusterState::from_str(&stored).unwrap(), state);
+    }
+    assert!(ClusterState::from_str("exploded").is_err());
+}
+
+#[tokio::test]
+async fn node_updates() {
+    let env = TestEnv::new().await;
+    let cluster = insert_cluster(&env.pool, "alpha").await;
+    let nodes = cluster.get_nodes(&env.pool).await.unwrap();
+    let node = &nodes[1];
+
+    node.set_ips(&env.pool, "10.0.0.11", "").await.unwrap();
+    node.set_market_type(&env.pool, "spot").await.unwrap();
+    node.set_efa_available(&env.pool, true).await.unwrap();
+    node.set_efs_configuration_state(&env.pool, true)
+        .await
+        .unwrap();
+    node.set_availability_zone(&env.pool, Some("us-east-1b"))
+        .await
+        .unwrap();
+    let fetched = Node::fetch_by_private_ip(&env.pool, "alpha", "10.0.0.11")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(fetched.id, node.id);
+    assert_eq!(fetched.market_type.as_deref(), Some("spot"));
+    assert!(fetched.is_spot());
+    assert_eq!(fetched.efa_available, Some(true));
+    assert!(fetched.was_efs_configured);
+    assert_eq!(fetched.availability_zone.as_deref(), Some("us-east-1b"));
+
+    fetched
+        .update_instance_spec(
+            &env.pool,
+            &InstanceSpec {
+                instance_type: "g6.xlarge",
+                allocation_mode: "spot",
+                image_id: "ami-gpu",
+                burstable_mode: None,
+                root_volume_gb: 100,
+                root_volume_type: "io2",
+                root_volume_iops: Some(4000),
+            },
+        )
+        .await
+        .unwrap();
+    let updated = Node::fetch_by_private_ip(&env.pool, "alpha", "10.0.0.11")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(updated.instance_type, "g6.xlarge");
+    assert_eq!(updated.allocation_mode, "spot");
+    assert_eq!(updated.image_id, "ami-gpu");
+    assert_eq!(updated.root_volume_gb, 100);
+    assert_eq!(updated.root_volume_type, "io2");
+    assert_eq!(updated.root_volume_iops, Some(4000));
+
+    updated.reset(&env.pool).await.unwrap();
+    let reset = &cluster.get_nodes(&env.pool).await.unwrap()[1];
+    assert_eq!(reset.private_ip.as_deref(), Some(""));
+    assert_eq!(reset.public_ip, None);
+    assert_eq!(reset.market_type, None);
+    assert_eq!(reset.efa_available, None);
+    assert!(!reset.was_efs_configured);
+    assert_eq!(reset.availability_zone, None);
+
+    let missing = sample_node("alpha", "missing", 2);
+    assert!(missing.set_ips(&env.pool, "10.0.0.99", "").await.is_err());
+    assert!(
+        missing
+            .update_instance_spec(&env.pool, &missing.instance_spec())
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn node_delete_by_private_ip_is_scoped_to_its_cluster() {
+    let env = TestEnv::new().await;
+    // Every cluster uses the same private range, so the address repeats.
+    for id in ["alpha", "beta"] {
+        let cluster = insert_cluster(&env.pool, id).await;
+        let nodes = cluster.get_nodes(&env.pool).await.unwrap();
+        nodes[1].set_ips(&env.pool, "10.0.0.11", "").await.unwrap();
+    }
+
+    Node::delete_by_private_ip(&env.pool, "alpha", "10.0.0.11")
+        .await
+        .unwrap();
+    assert!(
+        Node::fetch_by_private_ip(&env.pool, "alpha", "10.0.0.11")
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert!(
+        Node::fetch_by_private_ip(&env.pool, "beta", "10.0.0.11")
+            .await
+            .unwrap()
+            .is_some()
+    );
+    assert_eq!(count(&env.pool, "nodes").await, 3);
+
+    // Nothing left to delete is not an error.
+    Node::delete_by_private_ip(&env.pool, "alpha", "10.0.0.11")
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn node_delete_takes_its_init_commands() {
+    let env = TestEnv::new().await;
+    let cluster = insert_cluster(&env.pool, "alpha").await;
+    let nodes = cluster.get_nodes(&env.pool).await.unwrap();
+
+    // Node 0 has commands, which would otherwise block the delete.
+    nodes[0].delete(&env.pool).await.unwrap();
+    assert_eq!(count(&env.pool, "nodes").await, 1);
+    assert_eq!(count(&env.pool, "shell_commands").await, 0);
+    assert!(nodes[0].delete(&env.pool).await.is_err());
+}
+
+#[tokio::test]
+async fn shell_command_replace_all_for_node() {
+    let env = TestEnv::new().await;
+    let cluster = insert_cluster(&env.pool, "alpha").await;
+    let node = cluster.get_nodes(&env.pool).await.unwrap().remove(0);
+
+    let scripts = vec![
+        "mount /dev/nvme1n1 /scratch".to_string(),
+        "nvidia-smi".to_string(),
+    ];
+    let mut tx = env.pool.begin().await.unwrap();
+    ShellCommand::replace_all_for_node(&mut tx, &node.id, &scripts)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+    assert_eq!(node.get_init_commands(&env.pool).await.unwrap(), scripts);
+    let commands = ShellCommand::fetch_all_by_node_id(&env.pool, node.id.clone())
+        .await
+        .unwrap();
+    assert!(commands.iter().all(|c| c.status == "NOT_EXECUTED"));
+
+    // Rolling back leaves the previous commands in place.
+    let mut tx = env.pool.begin().await.unwrap();
+    ShellCommand::replace_all_for_node(&mut tx, &node.id, &[])
+        .await
+        .unwrap();
+    tx.rollback().await.unwrap();
+    assert_eq!(node.get_init_commands(&env.pool).await.unwrap(), scripts);
+
+    let mut tx = env.pool.begin().await.unwrap();
+    ShellCommand::replace_all_for_node(&mut tx, &node.id, &[])
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+    assert!(node.get_init_commands(&env.pool).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn task_run_start_and_finish() {
+    let env = TestEnv::new().await;
+    insert_cluster(&env.pool, "alpha").await;
+
+    let first = TaskRun::start(
+        &env.pool,
+        "alpha",
+        "abc",
+        &run_key("solve", 0),
+        1,
+        &job(&[]),
+    )
+    .await
+    .unwrap();
+    let nodes = ["node-a".to_string(), "node-b".to_string()];
+    let second = TaskRun::start(
+        &env.pool,
+        "alpha",
+        "abc",
+        &run_key("solve", 1),
+        1,
+        &job(&nodes),
+    )
+    .await
+    .unwrap();
+    assert_ne!(first.id, second.id);
+    assert_eq!(first.status, "running");
+    assert!(first.finished_at.is_none());
+    assert_eq!(second.node_ids.as_deref(), Some(r#"["node-a","node-b"]"#));
+
+    let output = TaskRunOutput {
+        exit_code: Some(0),
+        log_tail: Some("converged".to_string()),
+        log_path: Some("results/solve/run_1/hpcac.log".to_string()),
+    };
+    first.finish(&env.pool, "success", &output).await.unwrap();
+    let runs = TaskRun::fetch_by_cluster_id(&env.pool, "alpha")
+        .await
+        .unwrap();
+    assert_eq!(runs.len(), 2);
+    assert_eq!(runs[0].status, "success");
+    assert!(runs[0].finished_at.is_some());
+    assert_eq!(runs[0].exit_code, Some(0));
+    assert!(runs[0].wall_secs.is_some_and(|secs| secs >= 0.0));
+    assert_eq!(runs[0].log_tail.as_deref(), Some("converged"));
+    assert_eq!(
+        runs[0].log_path.as_deref(),
+        Some("results/solve/run_1/hpcac.log")
+    );
+    assert_eq!(runs[1].status, "running");
+    assert!(runs[1].wall_secs.is_none());
+    let running = TaskRun::fetch_running(&env.pool, "alpha").await.unwrap();
+    assert_eq!(running.len(), 1);
+    assert_eq!(running[0].id, second.id);
+    assert_eq!(running[0].launcher.as_deref(), Some("openmpi"));
+
+    // Only the successful run of this very tasks file counts as done.
+    let other_file = TaskRun::start(
+        &env.pool,
+        "alpha",
+        "def",
+        &run_key("solve", 1),
+        1,
+        &job(&[]),
+    )
+    .await
+    .unwrap();
+    other_file
+        .finish(&env.pool, "success", &TaskRunOutput::default())
+        .await
+        .unwrap();
+    let params = BTreeMap::from([("k".to_string(), "4".to_string())]);
+    let sweep_key = ("sweep".to_string(), 1, params.clone());
+    let sweep = TaskRun::start(&env.pool, "alpha", "abc", &sweep_key, 1, &job(&[]))
+        .await
+        .unwrap();
+    assert_eq!(sweep.params.as_deref(), Some(r#"{"k":"4"}"#));
+    sweep
+        .finish(&env.pool, "timeout", &TaskRunOutput::default())
+        .await
+        .unwrap();
+    // A retry is a new row; one successful attempt marks the run as done.
+    let retry = TaskRun::start(&env.pool, "alpha", "abc", &sweep_key, 2, &job(&[]))
+        .await
+        .unwrap();
+    assert_eq!(retry.attempt, 2);
+    retry
+        .finish(&env.pool, "success", &TaskRunOutput::default())
+        .await
+        .unwrap();
+    let succeeded = TaskRun::fetch_succeeded(&env.pool, "alpha", "abc")
+        .await
+        .unwrap();
+    assert_eq!(
+        succeeded,
+        HashSet::from([
+            ("solve".to_string(), 0, BTreeMap::new()),
+            ("sweep".to_string(), 1, params),
+        ])
+    );
+}
+
+#[tokio::test]
+async fn cluster_events_timeline() {
+    let env = TestEnv::new().await;
+    let cluster = insert_cluster(&env.pool, "alpha").await;
+    insert_cluster(&env.pool, "beta").await;
+
+    cluster
+        .update_state(&env.pool, ClusterState::Spawning)
+        .await
+        .unwrap();
+    let run = TaskRun::start(
+        &env.pool,
+        "alpha",
+        "abc",
+        &run_key("solve", 0),
+        1,
+        &job(&[]),
+    )
+    .await
+    .unwrap();
+    run.finish(&env.pool, "failed", &TaskRunOutput::default())
+        .await
+        .unwrap();
+    ClusterEvent::record(
+        &env.pool,
+        "beta",
+        ClusterEventType::NodeFailed,
+        Some("beta-node-0"),
+        "Node 'beta-node-0' failed its health check",
+        None,
+    )
+    .await
+    .unwrap();
+
+    // Only alpha's events, in order, with the transition read from the DB.
+    let events = ClusterEvent::fetch_by_cluster_id(&env.pool, "alpha", None)
+        .await
+        .unwrap();
+    let types: Vec<ClusterEventType> = events.iter().map(|e| e.event_type.clone()).collect();
+    assert_eq!(
+        types,
+        [
+            ClusterEventType::StateTransition,
+            ClusterEventType::TaskRunStarted,
+            ClusterEventType::TaskRunEnded,
+        ]
+    );
+    assert_eq!(events[0].message, "pending -> spawning");
+    assert_eq!(events[0].details_json()["to"], "spawning");
+    assert_eq!(events[2].details_json()["status"], "failed");
+    assert!(events[2].node_id.is_none());
+
+    let since = events[1].created_at;
+    let recent = ClusterEvent::fetch_by_cluster_id(&env.pool, "alpha", Some(since))
+        .await
+        .unwrap();
+    assert_eq!(recent.len(), 2);
+    assert_eq!(recent[0].event_type, ClusterEventType::TaskRunStarted);
+
+    let later = since + chrono::Duration::hours(1);
+    assert!(
+        ClusterEvent::fetch_by_cluster_id(&env.pool, "alpha", Some(later))
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn watch_lock_admits_one_watcher_per_cluster() {
+    let env = TestEnv::new().await;
+    insert_cluster(&env.pool, "alpha").await;
+    let lock = |pid: i64| WatchLock {
+        cluster_id: "alpha".to_string(),
+        pid,
+        socket_path: Some("/tmp/watch-alpha.sock".to_string()),
+        log_path: None,
+        started_at: chrono::Utc::now().naive_utc(),
+    };
+
+    assert!(lock(100).acquire(&env.pool).await.unwrap());
+    assert!(!lock(200).acquire(&env.pool).await.unwrap());
+
+    // Only the holder's release counts.
+    WatchLock::release(&env.pool, "alpha", 200).await.unwrap();
+    let held = WatchLock::fetch_by_cluster_id(&env.pool, "alpha")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(held.pid, 100);
+    WatchLock::release(&env.pool, "alpha", 100).await.unwrap();
+    assert!(lock(200).acquire(&env.pool).await.unwrap());
+
+    // A watched cluster can still be deleted.
+    Cluster::delete(&env.pool, "alpha").await.unwrap();
+    assert_eq!(count(&env.pool, "watch_locks").await, 0);
+}
+
+#[tokio::test]
+async fn instance_type_upsert_many_updates_in_place() {
+    let env = TestEnv::new().await;
+    let instance_type = |price: f64| InstanceType {
+        name: "c7i.large".to_string(),
+        cpu_architecture: "x86_64".to_string(),
+        vcpus: 2,
+        core_count: Some(1),
+        threads_per_core: Some(2),
+        cpu_type: "Intel Xeon".to_string(),
+        gpu_count: 0,
+        gpu_type: None,
+        fpga_count: 0,
+        fpga_type: None,
+        memory_in_mib: 4096,
+        supports_spot: true,
+        is_baremetal: false,
+        is_burstable: false,
+        supports_efa: false,
+        has_affinity_settings: false,
+        on_demand_price_per_hour: Some(price),
+        spot_price_per_hour: None,
+        region: "us-east-1".to_string(),
+        provider_id: "aws".to_string(),
+    };
+
+    InstanceType::upsert_many(&env.pool, vec![instance_type(0.09)])
+        .await
+        .unwrap();
+    InstanceType::upsert_many(&env.pool, vec![instance_type(0.11)])
+        .await
+        .unwrap();
+
+    assert_eq!(count(&env.pool, "instance_types").await, 1);
+    let fetched = InstanceType::fetch_by_name_and_region(&env.pool, "c7i.large", "us-east-1")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(fetched.on_demand_price_per_hour, Some(0.11));
+    assert!(
+        InstanceType::fetch_by_name_and_region(&env.pool, "c7i.large", "eu-west-1")
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+/// `main.rs` opens the database in WAL mode with a 30s busy timeout so that a
+/// `watch` and a `tasks` process can write at the same time. Several pools on
+/// one file stand in for those processes.
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_writers_with_wal() {
+    let env = TestEnv::new().await;
+    insert_cluster(&env.pool, "alpha").await;
+
+    let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode")
+        .fetch_one(&env.pool)
+        .await
+        .unwrap();
+    assert_eq!(journal_mode, "wal");
+
+    const WRITERS: i64 = 4;
+    const RUNS_PER_WRITER: i64 = 25;
+    let mut handles = Vec::new();
+    for writer in 0..WRITERS {
+        let pool = TestEnv::connect(&env.dir).await;
+        handles.push(tokio::spawn(async move {
+            let cluster = Cluster::fetch_by_id(&pool, "alpha").await?.unwrap();
+            for run_index in 0..RUNS_PER_WRITER {
+                let key = run_key(&format!("writer-{}", writer), run_index);
+                let run = TaskRun::start(&pool, "alpha", "abc", &key, 1, &job(&[])).await?;
+                run.finish(&pool, "success", &TaskRunOutput::default())
+                    .await?;
+                cluster.update_state(&pool, ClusterState::Running).await?;
+            }
+            anyhow::Ok(())
+        }));
+    }
+    for handle in handles {
+        handle.await.unwrap().unwrap();
+    }
+
+    assert_eq!(
+        count(&env.pool, "task_runs").await,
+        WRITERS * RUNS_PER_WRITER
+    );
+    let unfinished: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM task_runs WHERE status != 'success'")
+            .fetch_one(&env.pool)
+            .await
+            .unwrap();
+    assert_eq!(unfinished, 0);
+}
+
+#[tokio::test]
+async fn resumed_steps_recreate_resources_deleted_since() {
+    let env = TestEnv::new().await;
+    insert_cluster(&env.pool, "alpha").await;
+
+    let mut progress = SpawnProgress::load(&env.pool, "alpha").await.unwrap();
+    let vpc_id = progress
+        .resume_or(&env.pool, None, SpawnStepKind::Vpc, async {
+            Ok("vpc-1".to_string())
+        })
+        .await
+        .unwrap();
+    assert_eq!(vpc_id, "vpc-1");
+
+    // The recorded VPC is gone: the lookup creates another, which is recorded
+    // in its place.
+    let mut progress = SpawnProgress::load(&env.pool, "alpha").await.unwrap();
+    let vpc_id = progress
+        .resume_or(&env.pool, None, SpawnStepKind::Vpc, async {
+            Ok("vpc-2".to_string())
+        })
+        .await
+        .unwrap();
+    assert_eq!(vpc_id, "vpc-2");
+    let progress = SpawnProgress::load(&env.pool, "alpha").await.unwrap();
+    assert_eq!(
+        progress.resource_id(None, SpawnStepKind::Vpc),
+        Some("vpc-2")
+    );
+}
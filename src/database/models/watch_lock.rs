@@ -0,0 +1,88 @@
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePool;
+
+/// The `cluster watch` process monitoring a cluster. The row is the lock: the
+/// primary key on cluster_id is what keeps a second watcher out.
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WatchLock {
+    pub cluster_id: String,
+    pub pid: i64,
+    pub socket_path: Option<String>,
+    pub log_path: Option<String>,
+    pub started_at: NaiveDateTime,
+}
+
+impl WatchLock {
+    /// Takes the lock for this row's cluster. Returns false, leaving the table
+    /// untouched, when another watcher holds it.
+    pub async fn acquire(&self, pool: &SqlitePool) -> Result<bool> {
+        match sqlx::query!(
+            r#"
+                INSERT OR IGNORE INTO watch_locks (cluster_id, pid, socket_path, log_path, started_at)
+                VALUES (?, ?, ?, ?, ?)
+            "#,
+            self.cluster_id,
+            self.pid,
+            self.socket_path,
+            self.log_path,
+            self.started_at,
+        )
+        .execute(pool)
+        .await
+        {
+            Ok(result) => Ok(result.rows_affected() == 1),
+            Err(e) => {
+                tracing::error!("SQLx Error: {:?}", e);
+                anyhow::bail!("DB Operation Failure: {}", e);
+            }
+        }
+    }
+
+    /// Drops the lock of `cluster_id` if process `pid` holds it. A watcher
+    /// releasing its own lock and one clearing a dead watcher's use the same
+    /// call, and neither can remove a lock taken over in the meantime.
+    pub async fn release(pool: &SqlitePool, cluster_id: &str, pid: i64) -> Result<()> {
+        match sqlx::query!(
+            r#"DELETE FROM watch_locks WHERE cluster_id = ? AND pid = ?"#,
+            cluster_id,
+            pid
+        )
+        .execute(pool)
+        .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => anyhow::bail!("DB Operation Failure: {}", e),
+        }
+    }
+
+    pub async fn fetch_by_cluster_id(
+        pool: &SqlitePool,
+        cluster_id: &str,
+    ) -> Result<Option<WatchLock>> {
+        match sqlx::query_as!(
+            WatchLock,
+            r#"
+                SELECT
+                    cluster_id,
+                    pid,
+                    socket_path,
+                    log_path,
+                    started_at
+                FROM watch_locks
+                WHERE cluster_id = ?
+            "#,
+            cluster_id
+        )
+        .fetch_optional(pool)
+        .await
+        {
+            Ok(lock) => Ok(lock),
+            Err(e) => {
+                tracing::error!("SQLx Error: {:?}", e);
+                anyhow::bail!("DB Operation Failure: {}", e);
+            }
+        }
+    }
+}
@@ -1,6 +1,11 @@
-use crate::database::models::{Cluster, InstanceType, MachineImage, Node};
-use crate::integrations::providers::{aws::AwsInterface, vultr::VultrInterface};
-use crate::utils::ProgressTracker;
+use crate::database::models::{Cluster, InstanceType, MachineImage, Node, ProviderConfig};
+#[cfg(test)]
+use crate::integrations::providers::mock::MockInterface;
+use crate::integrations::providers::{
+    aws::{AwsInterface, EbsPricing},
+    vultr::VultrInterface,
+};
+use crate::utils::{ProgressTracker, command_runner::CommandRunner, ssh::SshSession};
 
 use anyhow::{Error, Result};
 use sqlx::sqlite::SqlitePool;
@@ -61,9 +66,176 @@ pub trait CloudResourceManager {
     ) -> Result<Vec<String>, Error>;
 }
 
+/// Whether a launch error says the provider has no capacity for the requested
+/// type in the zone. SpotMaxPriceTooLow counts: the spot price is per type and
+/// zone, so another type or another zone may still fit under the max price.
+pub fn is_capacity_error(message: &str) -> bool {
+    message.contains("InsufficientInstanceCapacity") || message.contains("SpotMaxPriceTooLow")
+}
+
+// Optional capabilities. Only some providers offer them, so commands ask the
+// CloudProvider for one and skip the feature when it answers None.
+
+/// Per-cluster queue delivering the provider's spot interruption notices.
+pub trait SpotInterruptionQueue {
+    async fn ensure_spot_interruption_queue(
+        &self,
+        cluster_id: &str,
+        region: &str,
+    ) -> Result<String, Error>;
+    async fn get_spot_interruption_queue_url(
+        &self,
+        cluster_id: &str,
+        region: &str,
+    ) -> Result<Option<String>, Error>;
+    async fn poll_spot_interruption_queue(
+        &self,
+        cluster_id: &str,
+        region: &str,
+        queue_url: &str,
+    ) -> Result<Vec<String>, Error>;
+    async fn cleanup_spot_interruption_queue(
+        &self,
+        cluster_id: &str,
+        region: &str,
+        queue_url: &str,
+    ) -> Result<(), Error>;
+    async fn send_simulated_spot_interruption(
+        &self,
+        cluster: &Cluster,
+        node_private_ip: &str,
+    ) -> Result<(), Error>;
+}
+
+/// Live spot market prices, for cost estimates of spot nodes.
+pub trait SpotPricing {
+    async fn fetch_spot_prices(
+        &self,
+        region: &str,
+        instance_type_names: &[String],
+        availability_zone: &str,
+    ) -> Result<HashMap<String, f64>, Error>;
+}
+
+/// Network interfaces that outlive their instance and must be released before
+/// a replacement can take over the failed node's address.
+pub trait NetworkInterfaceRelease {
+    async fn wait_for_enis_released(
+        &self,
+        region: &str,
+        private_ips: &[String],
+        max_wait: std::time::Duration,
+    ) -> bool;
+    async fn delete_detached_eni_by_private_ip(
+        &self,
+        region: &str,
+        private_ip: &str,
+    ) -> Result<(), Error>;
+}
+
+/// Root volumes billed separately from the instance.
+pub trait BlockStoragePricing {
+    async fn fetch_ebs_pricing(
+        &self,
+        region: &str,
+        volume_type: &str,
+    ) -> Result<EbsPricing, Error>;
+}
+
 pub enum CloudProvider {
     Aws(AwsInterface),
     Vultr(VultrInterface),
+    #[cfg(test)]
+    Mock(MockInterface),
+}
+
+/// The providers with a spot interruption queue and detachable network
+/// interfaces: AWS, and the mock standing in for it in tests.
+#[derive(Clone, Copy)]
+pub enum AwsCapabilities<'a> {
+    Aws(&'a AwsInterface),
+    #[cfg(test)]
+    Mock(&'a MockInterface),
+}
+
+impl CloudProvider {
+    /// Builds the interface for the provider behind a stored configuration.
+    pub async fn from_provider_config(
+        pool: &SqlitePool,
+        provider_config: &ProviderConfig,
+    ) -> Result<Self, Error> {
+        let config_vars = provider_config.get_config_vars(pool).await?;
+        match provider_config.provider_id.as_str() {
+            "aws" => Ok(CloudProvider::Aws(AwsInterface { config_vars })),
+            "vultr" => Ok(CloudProvider::Vultr(VultrInterface { config_vars })),
+            #[cfg(test)]
+            "mock" => Ok(CloudProvider::Mock(MockInterface::from_config_vars(
+                &config_vars,
+            )?)),
+            provider_id => {
+                anyhow::bail!("Provider '{}' is currently not supported.", provider_id)
+            }
+        }
+    }
+
+    pub fn spot_interruption_queue(&self) -> Option<impl SpotInterruptionQueue + Copy> {
+        self.aws_capabilities()
+    }
+
+    pub fn spot_pricing(&self) -> Option<&impl SpotPricing> {
+        match self {
+            CloudProvider::Aws(aws) => Some(aws),
+            CloudProvider::Vultr(_) => None,
+            #[cfg(test)]
+            CloudProvider::Mock(_) => None,
+        }
+    }
+
+    pub fn network_interface_release(&self) -> Option<impl NetworkInterfaceRelease + Copy> {
+        self.aws_capabilities()
+    }
+
+    fn aws_capabilities(&self) -> Option<AwsCapabilities<'_>> {
+        match self {
+            CloudProvider::Aws(aws) => Some(AwsCapabilities::Aws(aws)),
+            CloudProvider::Vultr(_) => None,
+            #[cfg(test)]
+            CloudProvider::Mock(mock) => Some(AwsCapabilities::Mock(mock)),
+        }
+    }
+
+    pub fn block_storage_pricing(&self) -> Option<&impl BlockStoragePricing> {
+        match self {
+            CloudProvider::Aws(aws) => Some(aws),
+            CloudProvider::Vultr(_) => None,
+            #[cfg(test)]
+            CloudProvider::Mock(_) => None,
+        }
+    }
+
+    /// Runner for commands on a cluster node, reached the way its provider
+    /// allows.
+    pub fn command_runner(
+        &self,
+        private_ip: &str,
+        public_ip: Option<&str>,
+        head_public_ip: &str,
+        private_key_path: &str,
+    ) -> CommandRunner {
+        let provider_id = match self {
+            CloudProvider::Aws(_) => "aws",
+            CloudProvider::Vultr(_) => "vultr",
+            #[cfg(test)]
+            CloudProvider::Mock(mock) => return mock.command_runner(private_ip),
+        };
+        CommandRunner::Ssh(SshSession::for_cluster_node(
+            provider_id,
+            private_ip,
+            public_ip,
+            head_public_ip,
+            private_key_path,
+        ))
+    }
 }
 
 impl CloudInfoProvider for CloudProvider {
@@ -71,6 +243,8 @@ impl CloudInfoProvider for CloudProvider {
         match self {
             CloudProvider::Aws(aws) => aws.fetch_regions(tracker).await,
             CloudProvider::Vultr(vultr) => vultr.fetch_regions(tracker).await,
+            #[cfg(test)]
+            CloudProvider::Mock(mock) => mock.fetch_regions(tracker).await,
         }
     }
 
@@ -82,6 +256,8 @@ impl CloudInfoProvider for CloudProvider {
         match self {
             CloudProvider::Aws(aws) => aws.fetch_zones(region, tracker).await,
             CloudProvider::Vultr(vultr) => vultr.fetch_zones(region, tracker).await,
+            #[cfg(test)]
+            CloudProvider::Mock(mock) => mock.fetch_zones(region, tracker).await,
         }
     }
 
@@ -93,6 +269,8 @@ impl CloudInfoProvider for CloudProvider {
         match self {
             CloudProvider::Aws(aws) => aws.fetch_instance_types(region, tracker).await,
             CloudProvider::Vultr(vultr) => vultr.fetch_instance_types(region, tracker).await,
+            #[cfg(test)]
+            CloudProvider::Mock(mock) => mock.fetch_instance_types(region, tracker).await,
         }
     }
 
@@ -107,6 +285,8 @@ impl CloudInfoProvider for CloudProvider {
             CloudProvider::Vultr(vultr) => {
                 vultr.fetch_prices(region, instance_types, tracker).await
             }
+            #[cfg(test)]
+            CloudProvider::Mock(mock) => mock.fetch_prices(region, instance_types, tracker).await,
         }
     }
 
@@ -118,6 +298,8 @@ impl CloudInfoProvider for CloudProvider {
         match self {
             CloudProvider::Aws(aws) => aws.fetch_machine_image(region, image_id).await,
             CloudProvider::Vultr(vultr) => vultr.fetch_machine_image(region, image_id).await,
+            #[cfg(test)]
+            CloudProvider::Mock(mock) => mock.fetch_machine_image(region, image_id).await,
         }
     }
 }
@@ -132,6 +314,8 @@ impl CloudResourceManager for CloudProvider {
         match self {
             CloudProvider::Aws(aws) => aws.spawn_cluster(pool, cluster, nodes).await,
             CloudProvider::Vultr(vultr) => vultr.spawn_cluster(pool, cluster, nodes).await,
+            #[cfg(test)]
+            CloudProvider::Mock(mock) => mock.spawn_cluster(pool, cluster, nodes).await,
         }
     }
 
@@ -144,6 +328,8 @@ impl CloudResourceManager for CloudProvider {
         match self {
             CloudProvider::Aws(aws) => aws.terminate_cluster(pool, cluster, nodes).await,
             CloudProvider::Vultr(vultr) => vultr.terminate_cluster(pool, cluster, nodes).await,
+            #[cfg(test)]
+            CloudProvider::Mock(mock) => mock.terminate_cluster(pool, cluster, nodes).await,
         }
     }
 
@@ -163,6 +349,11 @@ impl CloudResourceManager for CloudProvider {
                     .simulate_cluster_failure(pool, cluster, node_private_ip)
                     .await
             }
+            #[cfg(test)]
+            CloudProvider::Mock(mock) => {
+                mock.simulate_cluster_failure(pool, cluster, node_private_ip)
+                    .await
+            }
         }
     }
 
@@ -174,6 +365,140 @@ impl CloudResourceManager for CloudProvider {
         match self {
             CloudProvider::Aws(aws) => aws.check_cluster_health(pool, cluster).await,
             CloudProvider::Vultr(vultr) => vultr.check_cluster_health(pool, cluster).await,
+            #[cfg(test)]
+            CloudProvider::Mock(mock) => mock.check_cluster_health(pool, cluster).await,
+        }
+    }
+}
+
+impl SpotInterruptionQueue for AwsCapabilities<'_> {
+    async fn ensure_spot_interruption_queue(
+        &self,
+        cluster_id: &str,
+        region: &str,
+    ) -> Result<String, Error> {
+        match self {
+            AwsCapabilities::Aws(aws) => {
+                aws.ensure_spot_interruption_queue(cluster_id, region).await
+            }
+            #[cfg(test)]
+            AwsCapabilities::Mock(mock) => {
+                mock.ensure_spot_interruption_queue(cluster_id, region)
+                    .await
+            }
+        }
+    }
+
+    async fn get_spot_interruption_queue_url(
+        &self,
+        cluster_id: &str,
+        region: &str,
+    ) -> Result<Option<String>, Error> {
+        match self {
+            AwsCapabilities::Aws(aws) => {
+                aws.get_spot_interruption_queue_url(cluster_id, region)
+                    .await
+            }
+            #[cfg(test)]
+            AwsCapabilities::Mock(mock) => {
+                mock.get_spot_interruption_queue_url(cluster_id, region)
+                    .await
+            }
+        }
+    }
+
+    async fn poll_spot_interruption_queue(
+        &self,
+        cluster_id: &str,
+        region: &str,
+        queue_url: &str,
+    ) -> Result<Vec<String>, Error> {
+        match self {
+            AwsCapabilities::Aws(aws) => {
+                aws.poll_spot_interruption_queue(cluster_id, region, queue_url)
+                    .await
+            }
+            #[cfg(test)]
+            AwsCapabilities::Mock(mock) => {
+                mock.poll_spot_interruption_queue(cluster_id, region, queue_url)
+                    .await
+            }
+        }
+    }
+
+    async fn cleanup_spot_interruption_queue(
+        &self,
+        cluster_id: &str,
+        region: &str,
+        queue_url: &str,
+    ) -> Result<(), Error> {
+        match self {
+            AwsCapabilities::Aws(aws) => {
+                aws.cleanup_spot_interruption_queue(cluster_id, region, queue_url)
+                    .await
+            }
+            #[cfg(test)]
+            AwsCapabilities::Mock(mock) => {
+                mock.cleanup_spot_interruption_queue(cluster_id, region, queue_url)
+                    .await
+            }
+        }
+    }
+
+    async fn send_simulated_spot_interruption(
+        &self,
+        cluster: &Cluster,
+        node_private_ip: &str,
+    ) -> Result<(), Error> {
+        match self {
+            AwsCapabilities::Aws(aws) => {
+                aws.send_simulated_spot_interruption(cluster, node_private_ip)
+                    .await
+            }
+            #[cfg(test)]
+            AwsCapabilities::Mock(mock) => {
+                mock.send_simulated_spot_interruption(cluster, node_private_ip)
+                    .await
+            }
+        }
+    }
+}
+
+impl NetworkInterfaceRelease for AwsCapabilities<'_> {
+    async fn wait_for_enis_released(
+        &self,
+        region: &str,
+        private_ips: &[String],
+        max_wait: std::time::Duration,
+    ) -> bool {
+        match self {
+            AwsCapabilities::Aws(aws) => {
+                aws.wait_for_enis_released(region, private_ips, max_wait)
+                    .await
+            }
+            #[cfg(test)]
+            AwsCapabilities::Mock(mock) => {
+                mock.wait_for_enis_released(region, private_ips, max_wait)
+                    .await
+            }
+        }
+    }
+
+    async fn delete_detached_eni_by_private_ip(
+        &self,
+        region: &str,
+        private_ip: &str,
+    ) -> Result<(), Error> {
+        match self {
+            AwsCapabilities::Aws(aws) => {
+                aws.delete_detached_eni_by_private_ip(region, private_ip)
+                    .await
+            }
+            #[cfg(test)]
+            AwsCapabilities::Mock(mock) => {
+                mock.delete_detached_eni_by_private_ip(region, private_ip)
+                    .await
+            }
         }
     }
 }
@@ -47,9 +47,15 @@ pub struct AwsClusterContext {
     pub ec2_instance_ids: HashMap<usize, String>,
     pub efs_device_id: Option<String>,
     pub efs_mount_target_id: Option<String>,
+    // Subnets of the fallback zones nodes were moved to, keyed by zone. The
+    // cluster's own zone uses subnet_id.
+    pub fallback_subnet_ids: HashMap<String, String>,
 
     // Cluster and network configuration
     pub availability_zone: String,
+    pub fallback_availability_zones: Vec<String>,
+    // Nodes running outside the cluster's zone, by node index.
+    pub node_availability_zones: HashMap<usize, String>,
     pub use_node_affinity: bool,
     pub use_elastic_fabric_adapters: bool,
     pub public_ssh_key_path: String,
@@ -106,9 +112,12 @@ impl AwsClusterContext {
             ec2_instance_ids: HashMap::new(),
             efs_device_id: None,
             efs_mount_target_id: None,
+            fallback_subnet_ids: HashMap::new(),
 
             // Copy some cluster configuration for convenience
             availability_zone: cluster.availability_zone.clone(),
+            fallback_availability_zones: cluster.fallback_availability_zones(),
+            node_availability_zones: HashMap::new(),
             use_node_affinity: cluster.use_node_affinity,
             use_elastic_fabric_adapters: cluster.use_elastic_fabric_adapters,
             public_ssh_key_path: cluster.public_ssh_key_path.clone(),
@@ -137,9 +146,54 @@ impl AwsClusterContext {
         format!("{}-EIP-{}", self.cluster_id, node_index)
     }
 
-    /// Generate private IP for a specific node index
+    /// Generate private IP for a specific node index, in the subnet of the
+    /// zone the node runs in
     pub fn network_interface_private_ip(&self, node_index: usize) -> String {
-        format!("10.0.0.{}", node_index + 10)
+        let zone = self.node_availability_zone(node_index);
+        format!("10.0.{}.{}", self.zone_subnet_index(zone), node_index + 10)
+    }
+
+    /// Zone a specific node index runs in
+    pub fn node_availability_zone(&self, node_index: usize) -> &str {
+        self.node_availability_zones
+            .get(&node_index)
+            .map_or(&self.availability_zone, |zone| zone)
+    }
+
+    /// Third octet of the subnet in a zone: 0 for the cluster's own zone, then
+    /// one per fallback zone in declaration order
+    pub fn zone_subnet_index(&self, availability_zone: &str) -> usize {
+        self.fallback_availability_zones
+            .iter()
+            .position(|zone| zone == availability_zone)
+            .map_or(0, |position| position + 1)
+    }
+
+    /// Generate a subnet name for a zone. The cluster's own zone keeps the
+    /// original name, so clusters spawned before fallback zones find theirs.
+    pub fn subnet_name_for(&self, availability_zone: &str) -> String {
+        if availability_zone == self.availability_zone {
+            self.subnet_name.clone()
+        } else {
+            format!("{}-{}", self.subnet_name, availability_zone)
+        }
+    }
+
+    /// Generate the CIDR block of the subnet in a zone
+    pub fn subnet_cidr_block_for(&self, availability_zone: &str) -> String {
+        if availability_zone == self.availability_zone {
+            self.subnet_cidr_block.clone()
+        } else {
+            format!("10.0.{}.0/24", self.zone_subnet_index(availability_zone))
+        }
+    }
+
+    /// Subnet a specific node index gets its network interface in
+    pub fn node_subnet_id(&self, node_index: usize) -> Option<&String> {
+        match self.node_availability_zones.get(&node_index) {
+            Some(zone) => self.fallback_subnet_ids.get(zone),
+            None => self.subnet_id.as_ref(),
+        }
     }
 }
 
@@ -1,5 +1,5 @@
 use crate::database::models::{InstanceType, MachineImage};
-use crate::integrations::CloudInfoProvider;
+use crate::integrations::{BlockStoragePricing, CloudInfoProvider, SpotPricing};
 use crate::utils::ProgressTracker;
 
 use anyhow::Result;
@@ -525,10 +525,10 @@ impl CloudInfoProvider for AwsInterface {
     }
 }
 
-impl AwsInterface {
+impl BlockStoragePricing for AwsInterface {
     /// Fetch EBS pricing for a given volume type and region from the AWS Pricing API.
     /// Returns storage cost per GB-month and (for gp3) provisioned throughput cost per MB/s-month.
-    pub async fn fetch_ebs_pricing(&self, region: &str, volume_type: &str) -> Result<EbsPricing> {
+    async fn fetch_ebs_pricing(&self, region: &str, volume_type: &str) -> Result<EbsPricing> {
         let client = self.get_pricing_client().await?;
 
         let make_filter = |field: &str, value: &str| {
@@ -614,11 +614,13 @@ impl AwsInterface {
             throughput_per_mbs_month,
         })
     }
+}
 
+impl SpotPricing for AwsInterface {
     /// Fetch current spot prices for the given instance types via EC2 describe_spot_price_history.
     /// Returns a map of instance_type -> current spot price (USD/hour).
     /// If availability_zone is empty, prices from any AZ in the region are accepted.
-    pub async fn fetch_spot_prices(
+    async fn fetch_spot_prices(
         &self,
         region: &str,
         instance_type_names: &[String],
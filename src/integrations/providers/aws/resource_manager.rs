@@ -1,14 +1,87 @@
-use super::interface::AwsInterface;
-
-use crate::database::models::{Cluster, ClusterState, Node, RecoveryNode};
-use crate::integrations::CloudResourceManager;
+use super::interface::{AwsClusterContext, AwsInterface};
+use super::resources::elastic_compute::MAX_CAPACITY_ROUNDS;
+
+use crate::database::models::{
+    Cluster, ClusterEvent, ClusterEventType, ClusterState, InstanceSpec, Node, RecoveryIncident,
+    RecoveryNode, RecoveryPhase, SpawnProgress, SpawnStepKind,
+};
+use crate::integrations::{CloudResourceManager, SpotInterruptionQueue, is_capacity_error};
 use crate::utils;
+use crate::utils::command_runner::CommandRunner;
+use crate::utils::slurm;
 use crate::utils::ssh::SshSession;
 
 use anyhow::Result;
 use sqlx::sqlite::SqlitePool;
+use std::collections::HashMap;
 use tokio::time::{Duration, sleep};
 
+impl AwsInterface {
+    /// Finds or creates the cluster's Subnet in fallback zone `availability_zone`
+    /// and routes it like the cluster's own.
+    async fn ensure_fallback_subnet(
+        &self,
+        context: &mut AwsClusterContext,
+        availability_zone: &str,
+    ) -> Result<()> {
+        if context.fallback_subnet_ids.contains_key(availability_zone) {
+            return Ok(());
+        }
+        let subnet_id = self.ensure_subnet(context, availability_zone).await?;
+        self.associate_route_table_with_subnet(context, &subnet_id)
+            .await?;
+        context
+            .fallback_subnet_ids
+            .insert(availability_zone.to_string(), subnet_id);
+        Ok(())
+    }
+
+    /// Moves node `node_index` to `availability_zone` ahead of a launch there:
+    /// its network interface is recreated in that zone's Subnet, which gives it
+    /// a new private IP. Returns the new interface.
+    async fn move_node_to_zone(
+        &self,
+        pool: &SqlitePool,
+        context: &mut AwsClusterContext,
+        node: &mut Node,
+        node_index: usize,
+        availability_zone: &str,
+    ) -> Result<String> {
+        tracing::info!(
+            "Moving node {} from zone '{}' to '{}'...",
+            node_index,
+            context.node_availability_zone(node_index),
+            availability_zone
+        );
+        self.cleanup_elastic_network_interface(context, node_index)
+            .await?;
+        if availability_zone == context.availability_zone {
+            context.node_availability_zones.remove(&node_index);
+        } else {
+            self.ensure_fallback_subnet(context, availability_zone)
+                .await?;
+            context
+                .node_availability_zones
+                .insert(node_index, availability_zone.to_string());
+        }
+        let eni_id = self
+            .ensure_elastic_network_interface(context, node_index)
+            .await?;
+        context
+            .elastic_network_interface_ids
+            .insert(node_index, eni_id.clone());
+
+        let moved_to =
+            (availability_zone != context.availability_zone).then_some(availability_zone);
+        node.set_availability_zone(pool, moved_to).await?;
+        node.availability_zone = moved_to.map(|zone| zone.to_string());
+        let private_ip = context.network_interface_private_ip(node_index);
+        node.set_ips(pool, &private_ip, "").await?;
+        node.private_ip = Some(private_ip);
+        Ok(eni_id)
+    }
+}
+
 impl CloudResourceManager for AwsInterface {
     async fn spawn_cluster(
         &self,
@@ -17,6 +90,15 @@ impl CloudResourceManager for AwsInterface {
         mut nodes: Vec<Node>,
     ) -> Result<()> {
         let mut context = self.create_cluster_context(&cluster).await?;
+        for node in &nodes {
+            if let Some(zone) = &node.availability_zone
+                && *zone != cluster.availability_zone
+            {
+                context
+                    .node_availability_zones
+                    .insert(node.node_index as usize, zone.clone());
+            }
+        }
         let mut steps = 7 + (6 * nodes.len());
         if cluster.use_node_affinity {
             steps += 1;
@@ -26,6 +108,12 @@ impl CloudResourceManager for AwsInterface {
         } else {
             steps += nodes.len();
         }
+        if cluster.use_elastic_fabric_adapters {
+            steps += nodes.len();
+        }
+        if cluster.scheduler.is_some() {
+            steps += 1;
+        }
 
         let spawning_message = format!("Spawning Cluster '{}'...", cluster.display_name);
         tracing::info!(spawning_message);
@@ -37,6 +125,10 @@ impl CloudResourceManager for AwsInterface {
             ClusterState::Spawning
         };
 
+        // Steps an earlier, interrupted attempt already completed. A plain
+        // `cluster spawn` cleared them, so this is empty unless resuming.
+        let mut progress = SpawnProgress::load(pool, &cluster.id).await?;
+
         // A cluster that isn't mid-restore has no live instances, so every node
         // below gets a brand-new EC2 instance with nothing mounted on it. Any
         // `was_efs_configured` flag still set from an earlier spawn is therefore
@@ -45,9 +137,14 @@ impl CloudResourceManager for AwsInterface {
         // empty. Clear it up front so the mount always runs on fresh instances.
         // On a restore the surviving nodes really are still mounted, so their
         // flags must be preserved and only the replacement nodes (inserted with
-        // the flag unset) get mounted.
+        // the flag unset) get mounted. The same holds for a resumed spawn whose
+        // nodes have a recorded instance: the instance step re-checks that it
+        // is still the same one.
         if !matches!(cluster.state, ClusterState::Running | ClusterState::Restoring) {
-            for node in nodes.iter_mut().filter(|node| node.was_efs_configured) {
+            for node in nodes.iter_mut().filter(|node| {
+                node.was_efs_configured
+                    && !progress.is_completed(Some(&node.id), SpawnStepKind::Instance)
+            }) {
                 node.set_efs_configuration_state(pool, false).await?;
                 node.was_efs_configured = false;
             }
@@ -64,9 +161,14 @@ impl CloudResourceManager for AwsInterface {
         /*
          * AWS CLUSTER CLOUD RESOURCE CREATION CYCLE
          *
+         * Completed steps are recorded as they finish. A resumed spawn looks up
+         * what they created again and recreates whatever was deleted since. An
+         * instance can also die while the spawn is interrupted, and a node on a
+         * new instance redoes SSH, mount and init.
+         *
          * 1. (conditional) Request EFS device
          * 2. Create VPC
-         * 3. Create Subnet
+         * 3. Create Subnet (and one per fallback zone a restore moved a node to)
          * 4. Create Internet Gateway
          * 5. Create Route Table (and Routing Rules and Internet Gateway and Subnet attachments)
          * 6. Create Security Groups (and attach all of them to the VPC)
@@ -80,64 +182,139 @@ impl CloudResourceManager for AwsInterface {
          *     11.3. Associate Elastic IP with ENI device
          * }
          * 12. for each node {
-         *     12.1. Request EC2 instance creation
+         *     12.1. Request EC2 instance creation (on a restore, in a fallback
+         *           zone when the node's zone has no capacity)
          * }
          * 13. Wait for all EC2 instances to be ready
-         * 14. (conditional) Wait for EFS mount target to be ready
+         * 14. (conditional) Wait for EFS mount targets to be ready (one per zone)
          * 15. Wait for SSH to be ready on all instances
          * 16. (conditional) Attach EC2 Instances to EFS mount target via SSH
          * 17. Dispatch EC2 Instance initialization commands via SSH
+         * 18. (conditional) Verify the EFA provider is visible on every node
+         * 19. (conditional) Install and configure Slurm on every node
          */
 
         // 1. Request EFS device creation...
+        // The device step is recorded once the device is ready (step 7).
+        let efs_device_ready = progress.is_completed(None, SpawnStepKind::EfsDevice);
         if cluster.use_elastic_file_system {
             operation_spinner
                 .update_message("Requesting Elastic File System (EFS) device creation...");
-            context.efs_device_id = Some(
-                self.request_elastic_file_system_device_creation(&context)
-                    .await?,
-            );
+            context.efs_device_id =
+                Some(match progress.resource_id(None, SpawnStepKind::EfsDevice) {
+                    Some(efs_device_id) => efs_device_id.to_string(),
+                    None => {
+                        self.request_elastic_file_system_device_creation(&context)
+                            .await?
+                    }
+                });
             main_progress.inc(1);
         }
 
         // 2. Create VPC
         operation_spinner.update_message("Creating Virtual Private Cloud (VPC)...");
-        context.vpc_id = Some(self.ensure_vpc(&context).await?);
+        context.vpc_id = Some(
+            progress
+                .resume_or(pool, None, SpawnStepKind::Vpc, self.ensure_vpc(&context))
+                .await?,
+        );
         main_progress.inc(1);
 
         // 3. Create Subnet
         operation_spinner.update_message("Creating Subnet...");
-        context.subnet_id = Some(self.ensure_subnet(&context).await?);
+        context.subnet_id = Some(
+            progress
+                .resume_or(
+                    pool,
+                    None,
+                    SpawnStepKind::Subnet,
+                    self.ensure_subnet(&context, &cluster.availability_zone),
+                )
+                .await?,
+        );
         main_progress.inc(1);
 
         // 4. Create Internet Gateway
         operation_spinner.update_message("Creating Internet Gateway...");
-        context.gateway_id = Some(self.ensure_internet_gateway(&context).await?);
+        context.gateway_id = Some(
+            progress
+                .resume_or(
+                    pool,
+                    None,
+                    SpawnStepKind::InternetGateway,
+                    self.ensure_internet_gateway(&context),
+                )
+                .await?,
+        );
         main_progress.inc(1);
 
         // 5. Create Route Table
         operation_spinner.update_message("Creating Route Table and Routing Rules...");
-        context.route_table_id = Some(self.ensure_route_table(&context).await?);
+        context.route_table_id = Some(
+            progress
+                .resume_or(
+                    pool,
+                    None,
+                    SpawnStepKind::RouteTable,
+                    self.ensure_route_table(&context),
+                )
+                .await?,
+        );
         main_progress.inc(1);
 
+        // Subnets of the fallback zones nodes were moved to by an earlier restore.
+        let moved_zones: Vec<String> = context.node_availability_zones.values().cloned().collect();
+        for zone in moved_zones {
+            operation_spinner.update_message(&format!("Creating Subnet in zone '{}'...", zone));
+            self.ensure_fallback_subnet(&mut context, &zone).await?;
+        }
+
         // 6. Create Security Groups
         operation_spinner.update_message("Creating Security Group and Security Rules...");
-        context.security_group_ids = self.ensure_security_group(&context).await?;
+        let security_group_ids = progress
+            .resume_or(pool, None, SpawnStepKind::SecurityGroup, async {
+                // Sorted, so the recorded groups compare equal however they
+                // are listed.
+                let mut ids = self.ensure_security_group(&context).await?;
+                ids.sort();
+                Ok(serde_json::to_string(&ids)?)
+            })
+            .await?;
+        context.security_group_ids = serde_json::from_str(&security_group_ids)?;
         main_progress.inc(1);
 
         if cluster.use_elastic_file_system {
             // 7. Wait for EFS device to be ready...
-            operation_spinner
-                .update_message("Waiting for Elastic File System (EFS) device to be ready...");
-            self.wait_for_elastic_file_system_device_to_be_ready(&context)
-                .await?;
+            if !efs_device_ready {
+                operation_spinner
+                    .update_message("Waiting for Elastic File System (EFS) device to be ready...");
+                self.wait_for_elastic_file_system_device_to_be_ready(&context)
+                    .await?;
+                progress
+                    .complete(
+                        pool,
+                        None,
+                        SpawnStepKind::EfsDevice,
+                        context.efs_device_id.as_deref(),
+                    )
+                    .await?;
+            }
             main_progress.inc(1);
 
             // 8. Request EFS mount target creation...
             operation_spinner
                 .update_message("Requesting Elastic File System (EFS) mount target creation...");
             context.efs_mount_target_id = Some(
-                self.request_elastic_file_system_mount_target_creation(&context)
+                progress
+                    .resume_or(
+                        pool,
+                        None,
+                        SpawnStepKind::EfsMountTarget,
+                        self.request_elastic_file_system_mount_target_creation(
+                            &context,
+                            &cluster.availability_zone,
+                        ),
+                    )
                     .await?,
             );
             main_progress.inc(1);
@@ -145,28 +322,51 @@ impl CloudResourceManager for AwsInterface {
 
         // 9. Create SSH Key Pair
         operation_spinner.update_message("Importing the SSH key pair...");
-        context.ssh_key_id = Some(self.ensure_ssh_key(&context).await?);
+        context.ssh_key_id = Some(
+            progress
+                .resume_or(
+                    pool,
+                    None,
+                    SpawnStepKind::SshKey,
+                    self.ensure_ssh_key(&context),
+                )
+                .await?,
+        );
         main_progress.inc(1);
 
         // 10. Create Placement Group
         if context.use_node_affinity {
             operation_spinner.update_message("Creating a Placement Group...");
-            context.placement_group_name_actual =
-                Some(self.ensure_placement_group(&context).await?);
+            context.placement_group_name_actual = Some(
+                progress
+                    .resume_or(
+                        pool,
+                        None,
+                        SpawnStepKind::PlacementGroup,
+                        self.ensure_placement_group(&context),
+                    )
+                    .await?,
+            );
             main_progress.inc(1);
         }
 
         // 13. Create ENI devices; allocate Elastic IP only for node 0 (head node).
         // Worker nodes are reachable via the head node and do not need a public IP.
-        for (node_index, node) in nodes.iter().enumerate() {
+        for (position, node) in nodes.iter().enumerate() {
+            let node_index = node.node_index as usize;
             // 13.1. Create ENI device
             operation_spinner.update_message(&format!(
                 "Creating {} of {} Elastic Network Interface (ENI) devices",
-                node_index + 1,
+                position + 1,
                 nodes.len()
             ));
-            let eni_id = self
-                .ensure_elastic_network_interface(&context, node_index)
+            let eni_id = progress
+                .resume_or(
+                    pool,
+                    Some(&node.id),
+                    SpawnStepKind::NetworkInterface,
+                    self.ensure_elastic_network_interface(&context, node_index),
+                )
                 .await?;
             context
                 .elastic_network_interface_ids
@@ -174,7 +374,23 @@ impl CloudResourceManager for AwsInterface {
             let node_private_ip = context.network_interface_private_ip(node_index);
             main_progress.inc(1);
 
-            if node_index == 0 {
+            // The head's address is on its node row once the EIP step completes.
+            let recorded_eip = progress
+                .resource_id(Some(&node.id), SpawnStepKind::ElasticIp)
+                .zip(node.public_ip.as_deref())
+                .map(|(eip_id, public_ip)| (eip_id.to_string(), public_ip.to_string()));
+            if node_index == 0
+                && let Some((eip_id, node_public_ip)) = recorded_eip
+            {
+                tracing::info!(
+                    "Resuming with Elastic IP '{}' ({}) from a completed step",
+                    eip_id,
+                    node_public_ip
+                );
+                context.elastic_ip_ids.insert(node_index, eip_id);
+                context.elastic_ips.insert(node_index, node_public_ip);
+                main_progress.inc(2);
+            } else if node_index == 0 {
                 // Head node: allocate and associate an Elastic IP for external SSH access.
                 operation_spinner.update_message("Allocating Elastic IP for head node...");
                 let eip_id = self.ensure_elastic_ip(&context, node_index).await?;
@@ -190,6 +406,14 @@ impl CloudResourceManager for AwsInterface {
                     .await?;
                 context.elastic_ips.insert(node_index, node_public_ip.clone());
                 node.set_ips(pool, &node_private_ip, &node_public_ip).await?;
+                progress
+                    .complete(
+                        pool,
+                        Some(&node.id),
+                        SpawnStepKind::ElasticIp,
+                        Some(&eip_id),
+                    )
+                    .await?;
                 main_progress.inc(1);
             } else {
                 // Worker nodes: private IP only, accessed via head node jump host.
@@ -203,8 +427,10 @@ impl CloudResourceManager for AwsInterface {
             }
         }
 
-        // A recovery slot may list several acceptable instance types in priority
-        // order. Key each list by its primary type so any node carrying that type
+        // A recovery slot may list several acceptable instance types, tried in
+        // the order its selection policy ranked them at this restore (the
+        // declared order by default). Key each list by its first type so any
+        // node carrying that type
         // — the replaced slot itself, plus any scale-up nodes fanned out from it —
         // can fall back when the preferred type has no capacity. Slots with a
         // single type are omitted, leaving those launches exactly as before.
@@ -215,7 +441,7 @@ impl CloudResourceManager for AwsInterface {
             std::collections::HashMap::new();
         if is_restore {
             for recovery_node in RecoveryNode::fetch_all_by_cluster_id(pool, &cluster.id).await? {
-                let types = recovery_node.instance_types();
+                let types = recovery_node.launch_order();
                 if types.len() > 1 {
                     let primary = types[0].clone();
                     capacity_fallbacks.entry(primary).or_insert(types);
@@ -225,12 +451,12 @@ impl CloudResourceManager for AwsInterface {
 
         // 12. Request EC2 Instances
         let node_count = nodes.len();
-        for (node_index, node) in nodes.iter_mut().enumerate() {
+        for (position, node) in nodes.iter_mut().enumerate() {
+            let node_index = node.node_index as usize;
             // 12.1. Request EC2 instance creation...
-            // TODO: Add Spot support
             operation_spinner.update_message(&format!(
                 "Requesting {} of {} EC2 Instances (type='{}')",
-                node_index + 1,
+                position + 1,
                 node_count,
                 node.instance_type
             ));
@@ -238,36 +464,157 @@ impl CloudResourceManager for AwsInterface {
                 .get(&node.instance_type)
                 .cloned()
                 .unwrap_or_default();
-            let launch = self
-                .request_elastic_compute_instance_creation(&context, node, node_index, &candidates)
+            // On a restore, a worker whose zone has capacity for none of its types
+            // moves on to the next zone, where only the last one retries. The
+            // head node stays put: it holds the Elastic IP every SSH session
+            // goes through.
+            let zones = if is_restore && node_index > 0 {
+                cluster.restore_zones(node.availability_zone.as_deref())
+            } else {
+                vec![context.node_availability_zone(node_index).to_string()]
+            };
+            let started_in = context.node_availability_zone(node_index).to_string();
+            let mut attempt = 0;
+            let launch = loop {
+                let zone = &zones[attempt];
+                let next_zone = zones.get(attempt + 1);
+                if zone != context.node_availability_zone(node_index) {
+                    let eni_id = self
+                        .move_node_to_zone(pool, &mut context, node, node_index, zone)
+                        .await?;
+                    progress
+                        .complete(
+                            pool,
+                            Some(&node.id),
+                            SpawnStepKind::NetworkInterface,
+                            Some(&eni_id),
+                        )
+                        .await?;
+                }
+                let capacity_rounds = if next_zone.is_some() {
+                    0
+                } else {
+                    MAX_CAPACITY_ROUNDS
+                };
+                match self
+                    .request_elastic_compute_instance_creation(
+                        &context,
+                        node,
+                        node_index,
+                        &candidates,
+                        capacity_rounds,
+                    )
+                    .await
+                {
+                    Ok(launch) => break launch,
+                    Err(e) => match next_zone {
+                        Some(next_zone) if is_capacity_error(&e.to_string()) => {
+                            tracing::warn!(
+                                "No capacity for node {} in zone '{}', trying zone '{}'",
+                                node_index,
+                                zone,
+                                next_zone
+                            );
+                            attempt += 1;
+                        }
+                        _ => return Err(e),
+                    },
+                }
+            };
+            let zone = context.node_availability_zone(node_index).to_string();
+            if zone != started_in {
+                warn_about_zone_move(&cluster, node_index, &zone);
+                ClusterEvent::record(
+                    pool,
+                    &cluster.id,
+                    ClusterEventType::ZoneFallbackUsed,
+                    Some(&node.id),
+                    &format!(
+                        "Node {} moved from zone '{}' to '{}'",
+                        node_index, started_in, zone
+                    ),
+                    Some(serde_json::json!({
+                        "node_index": node_index,
+                        "from": started_in,
+                        "to": zone,
+                        "private_ip": node.private_ip,
+                    })),
+                )
+                .await?;
+            }
+            // A resumed spawn finds the instances it already launched; only a new
+            // one is news.
+            if progress.resource_id(Some(&node.id), SpawnStepKind::Instance)
+                != Some(launch.instance_id.as_str())
+            {
+                ClusterEvent::record(
+                    pool,
+                    &cluster.id,
+                    ClusterEventType::NodeLaunched,
+                    Some(&node.id),
+                    &format!(
+                        "Node {} launched as '{}' ({})",
+                        node_index, launch.instance_type, launch.market_type
+                    ),
+                    Some(serde_json::json!({
+                        "node_index": node_index,
+                        "instance_id": launch.instance_id,
+                        "instance_type": launch.instance_type,
+                        "market_type": launch.market_type,
+                    })),
+                )
+                .await?;
+            }
+            progress
+                .complete_instance(pool, &node.id, &launch.instance_id)
                 .await?;
             context.ec2_instance_ids.insert(node_index, launch.instance_id);
+            if node.market_type.as_deref() != Some(launch.market_type.as_str()) {
+                node.set_market_type(pool, &launch.market_type).await?;
+                node.market_type = Some(launch.market_type);
+            }
             // Persist a fallback so the DB describes the cluster that actually
             // exists. Downstream cost reporting and any later restore read this.
             if launch.instance_type != node.instance_type {
+                ClusterEvent::record(
+                    pool,
+                    &cluster.id,
+                    ClusterEventType::CapacityFallbackUsed,
+                    Some(&node.id),
+                    &format!(
+                        "Node {} fell back from '{}' to '{}'",
+                        node_index, node.instance_type, launch.instance_type
+                    ),
+                    Some(serde_json::json!({
+                        "node_index": node_index,
+                        "requested": node.instance_type,
+                        "launched": launch.instance_type,
+                    })),
+                )
+                .await?;
                 // Only the type changed here; a capacity fallback keeps the image
                 // and storage the slot already declared.
                 node.update_instance_spec(
                     pool,
-                    &launch.instance_type,
-                    &node.allocation_mode,
-                    &node.image_id,
-                    node.burstable_mode.as_deref(),
-                    node.root_volume_gb,
-                    &node.root_volume_type,
-                    node.root_volume_iops,
+                    &InstanceSpec {
+                        instance_type: &launch.instance_type,
+                        ..node.instance_spec()
+                    },
                 )
                 .await?;
                 node.instance_type = launch.instance_type;
             }
             main_progress.inc(1);
         }
+        RecoveryIncident::record_phase(pool, &cluster.id, RecoveryPhase::InstancesRequested)
+            .await?;
 
         // 15. Wait for all EC2 Instances to be available
         operation_spinner.update_message("Waiting for all EC2 Instances to be available...");
         sleep(Duration::from_secs(5)).await;
         self.wait_for_all_elastic_compute_instances_to_be_available(&context)
             .await?;
+        RecoveryIncident::record_phase(pool, &cluster.id, RecoveryPhase::InstancesRunning).await?;
         main_progress.inc(1);
 
         if cluster.use_elastic_file_system {
@@ -275,6 +622,25 @@ impl CloudResourceManager for AwsInterface {
             operation_spinner.update_message("Waiting for the EFS mount target to be ready...");
             self.wait_for_elastic_file_system_mount_target_to_be_ready(&context)
                 .await?;
+            // Nodes in a fallback zone mount from a mount target in that zone.
+            let mut moved_zones: Vec<String> =
+                context.node_availability_zones.values().cloned().collect();
+            moved_zones.sort();
+            moved_zones.dedup();
+            for zone in moved_zones {
+                operation_spinner.update_message(&format!(
+                    "Waiting for the EFS mount target in zone '{}' to be ready...",
+                    zone
+                ));
+                let mount_target_id = self
+                    .request_elastic_file_system_mount_target_creation(&context, &zone)
+                    .await?;
+                self.wait_for_elastic_file_system_mount_target_to_be_available(
+                    &context,
+                    &mount_target_id,
+                )
+                .await?;
+            }
             main_progress.inc(1);
         }
 
@@ -295,7 +661,12 @@ impl CloudResourceManager for AwsInterface {
 
         // 17. Wait for SSH to be ready on all instances.
         // Head node is polled directly; workers are reached via the head as a jump host.
-        for (node_index, _node) in nodes.iter().enumerate() {
+        for node in &nodes {
+            let node_index = node.node_index as usize;
+            if progress.is_completed(Some(&node.id), SpawnStepKind::Ssh) {
+                main_progress.inc(1);
+                continue;
+            }
             let ssh = if node_index == 0 {
                 operation_spinner.update_message(&format!(
                     "Waiting for SSH on head node ({})...",
@@ -316,32 +687,37 @@ impl CloudResourceManager for AwsInterface {
                 )
             };
             ssh.wait_until_ready(Duration::from_secs(300)).await?;
+            progress
+                .complete(pool, Some(&node.id), SpawnStepKind::Ssh, None)
+                .await?;
             main_progress.inc(1);
         }
+        RecoveryIncident::record_phase(pool, &cluster.id, RecoveryPhase::SshReady).await?;
 
         // 18. (conditional) Attach EC2 Instances to EFS mount target via SSH
         if cluster.use_elastic_file_system {
             // Mount by IP address rather than DNS name to bypass systemd-resolved's
             // NXDOMAIN negative-caching, which was causing mount failures during
             // early boot even after the mount target reached the "available" state.
-            let efs_mount_ip = self
-                .get_elastic_file_system_mount_target_ip(&context)
-                .await?;
+            let mut efs_mount_ips: HashMap<String, String> = HashMap::new();
             let efs_dns_name = format!(
                 "{}.efs.{}.amazonaws.com",
                 context.efs_device_id.clone().unwrap(),
                 cluster.region,
             );
-            for (node_index, _node) in nodes.iter().enumerate() {
+            for (position, node) in nodes.iter().enumerate() {
+                let node_index = node.node_index as usize;
                 operation_spinner.update_message(&format!(
                     "Attaching Node {} of {} to EFS mount target...",
-                    node_index + 1,
+                    position + 1,
                     nodes.len()
                 ));
-                if nodes[node_index].was_efs_configured {
+                if node.was_efs_configured
+                    || progress.is_completed(Some(&node.id), SpawnStepKind::EfsMount)
+                {
                     tracing::info!(
                         "Skipping Node {} of {} (already configured for EFS)...",
-                        node_index + 1,
+                        position + 1,
                         nodes.len()
                     );
                 } else {
@@ -355,6 +731,17 @@ impl CloudResourceManager for AwsInterface {
                             &cluster.private_ssh_key_path,
                         )
                     };
+                    let zone = context.node_availability_zone(node_index).to_string();
+                    let efs_mount_ip = match efs_mount_ips.get(&zone) {
+                        Some(ip) => ip.clone(),
+                        None => {
+                            let ip = self
+                                .get_elastic_file_system_mount_target_ip(&context, &zone)
+                                .await?;
+                            efs_mount_ips.insert(zone, ip.clone());
+                            ip
+                        }
+                    };
                     let efs_attach_script = format!(
                         r#"
 rpm -q nfs-utils &>/dev/null || sudo yum install -y nfs-utils
@@ -390,19 +777,31 @@ echo "EFS mount and setup complete!"
 "#
                     );
                     ssh.run_command(&efs_attach_script).await?;
-                    nodes[node_index]
-                        .set_efs_configuration_state(pool, true)
+                    node.set_efs_configuration_state(pool, true).await?;
+                    progress
+                        .complete(pool, Some(&node.id), SpawnStepKind::EfsMount, None)
                         .await?;
                 }
                 main_progress.inc(1);
             }
+            RecoveryIncident::record_phase(pool, &cluster.id, RecoveryPhase::EfsMounted).await?;
         }
 
         // 19. Dispatch EC2 Instance initialization commands via SSH
-        for (node_index, node) in nodes.iter().enumerate() {
+        for (position, node) in nodes.iter().enumerate() {
+            let node_index = node.node_index as usize;
+            if progress.is_completed(Some(&node.id), SpawnStepKind::Init) {
+                tracing::info!(
+                    "Skipping Node {} of {} (setup and init commands already completed)...",
+                    position + 1,
+                    nodes.len()
+                );
+                main_progress.inc(1);
+                continue;
+            }
             operation_spinner.update_message(&format!(
                 "Running base setup on Node {} of {}...",
-                node_index + 1,
+                position + 1,
                 nodes.len()
             ));
             let ssh = if node_index == 0 {
@@ -435,12 +834,92 @@ echo "EFS mount and setup complete!"
                 tracing::info!(
                     "Running {} init command(s) on Node {} of {}...",
                     node_init_commands.len(),
-                    node_index + 1,
+                    position + 1,
                     nodes.len()
                 );
                 let init_script = format!("set -e\n{}", node_init_commands.join("\n"));
                 ssh.run_command_streaming(&init_script).await?;
             }
+            progress
+                .complete(pool, Some(&node.id), SpawnStepKind::Init, None)
+                .await?;
+            main_progress.inc(1);
+        }
+        RecoveryIncident::record_phase(pool, &cluster.id, RecoveryPhase::InitDone).await?;
+
+        // 20. (conditional) Verify EFA on every node.
+        // An EFA interface only helps if libfabric on the image can see it. An AMI
+        // without the EFA software, or a type that silently fell back to ENA,
+        // still boots and passes every check above, and MPI then runs over TCP
+        // with nothing but the benchmark numbers to show for it. Record what
+        // `fi_info` reports so the node row says which fabric it is really on.
+        if cluster.use_elastic_fabric_adapters {
+            for (position, node) in nodes.iter_mut().enumerate() {
+                let node_index = node.node_index as usize;
+                operation_spinner.update_message(&format!(
+                    "Verifying EFA on Node {} of {}...",
+                    position + 1,
+                    node_count
+                ));
+                let ssh = if node_index == 0 {
+                    SshSession::for_aws(&head_public_ip, &cluster.private_ssh_key_path)
+                } else {
+                    let worker_private_ip = context.network_interface_private_ip(node_index);
+                    SshSession::for_aws_worker(
+                        &worker_private_ip,
+                        &head_public_ip,
+                        &cluster.private_ssh_key_path,
+                    )
+                };
+                // The EFA installer puts fi_info under /opt/amazon/efa/bin, which
+                // is not on the PATH of a non-login shell.
+                let output = ssh
+                    .run_command(
+                        "if PATH=\"$PATH:/opt/amazon/efa/bin\" fi_info -p efa >/dev/null 2>&1; \
+                        then echo efa-ok; else echo efa-missing; fi",
+                    )
+                    .await?;
+                let available = output.trim() == "efa-ok";
+                if !available {
+                    tracing::warn!(
+                        "EFA provider not found on Node {} of {} (type='{}'): MPI will fall back to TCP. \
+                        Check that the image has the EFA software installed",
+                        position + 1,
+                        node_count,
+                        node.instance_type
+                    );
+                }
+                node.set_efa_available(pool, available).await?;
+                node.efa_available = Some(available);
+                main_progress.inc(1);
+            }
+        }
+
+        // 21. (conditional) Install and configure Slurm on every node. A restore
+        // goes through here too, which rewrites the node list so the
+        // replacement nodes join.
+        if cluster.scheduler.as_deref() == Some("slurm") {
+            operation_spinner.update_message("Configuring Slurm...");
+            let slurm_nodes: Vec<(String, CommandRunner)> = nodes
+                .iter()
+                .map(|node| {
+                    let node_index = node.node_index as usize;
+                    let private_ip = context.network_interface_private_ip(node_index);
+                    let ssh = if node_index == 0 {
+                        SshSession::for_aws(&head_public_ip, &cluster.private_ssh_key_path)
+                    } else {
+                        SshSession::for_aws_worker(
+                            &private_ip,
+                            &head_public_ip,
+                            &cluster.private_ssh_key_path,
+                        )
+                    };
+                    (private_ip, CommandRunner::Ssh(ssh))
+                })
+                .collect();
+            // The controller runs on node 0, the head holding the Elastic IP.
+            let head_ip = context.network_interface_private_ip(0);
+            slurm::configure_slurm(&cluster.id, &head_ip, &slurm_nodes).await?;
             main_progress.inc(1);
         }
 
@@ -456,13 +935,13 @@ echo "EFS mount and setup complete!"
             head_public_ip
         );
 
-        if !is_restore && nodes.iter().any(|n| n.allocation_mode == "spot") {
-            if let Err(e) = self
+        if !is_restore
+            && nodes.iter().any(|n| n.is_spot())
+            && let Err(e) = self
                 .ensure_spot_interruption_queue(&cluster.id, &cluster.region)
                 .await
-            {
-                tracing::warn!("Could not set up spot interruption queue: {}", e);
-            }
+        {
+            tracing::warn!("Could not set up spot interruption queue: {}", e);
         }
 
         Ok(())
@@ -487,7 +966,7 @@ echo "EFS mount and setup complete!"
         let terminating_message = format!("Terminating Cluster '{}'...", cluster.display_name);
         tracing::info!(terminating_message);
 
-        let has_spot_nodes = nodes.iter().any(|n| n.allocation_mode == "spot");
+        let has_spot_nodes = nodes.iter().any(|n| n.is_spot());
         if has_spot_nodes {
             if let Ok(Some(queue_url)) = self
                 .get_spot_interruption_queue_url(&cluster.id, &cluster.region)
@@ -580,11 +1059,12 @@ echo "EFS mount and setup complete!"
             .await?;
         main_progress.inc(nodes.len() as u64);
 
-        for (node_index, _node) in nodes.iter().enumerate() {
+        for (position, node) in nodes.iter().enumerate() {
+            let node_index = node.node_index as usize;
             // 6.1. Dissociate from ENI device and deallocate Elastic IP
             operation_spinner.update_message(&format!(
                 "Destroying Elastic IP {}/{}",
-                node_index + 1,
+                position + 1,
                 nodes.len()
             ));
             self.cleanup_elastic_ip(&context, node_index).await?;
@@ -593,7 +1073,7 @@ echo "EFS mount and setup complete!"
             // 6.2. Destroy ENI device
             operation_spinner.update_message(&format!(
                 "Destroying Elastic Network Interface {}/{}",
-                node_index + 1,
+                position + 1,
                 nodes.len()
             ));
             self.cleanup_elastic_network_interface(&context, node_index)
@@ -751,3 +1231,33 @@ echo "EFS mount and setup complete!"
         Ok(failed_ips)
     }
 }
+
+/// Spells out what a node moved out of the cluster's zone costs.
+fn warn_about_zone_move(cluster: &Cluster, node_index: usize, zone: &str) {
+    tracing::warn!(
+        "Node {} now runs in zone '{}', away from the rest of the cluster in '{}'. MPI traffic \
+        to it crosses zones, with higher latency and inter-zone transfer billed per GB",
+        node_index,
+        zone,
+        cluster.availability_zone
+    );
+    if cluster.use_elastic_file_system {
+        tracing::warn!(
+            "EFS gets a mount target in zone '{}' for it, and the cross-zone traffic to the \
+            file system is billed too",
+            zone
+        );
+    }
+    if cluster.use_node_affinity {
+        tracing::warn!(
+            "Node {} launched outside the cluster's placement group",
+            node_index
+        );
+    }
+    if cluster.use_elastic_fabric_adapters {
+        tracing::warn!(
+            "EFA traffic does not cross zones: MPI reaches node {} over TCP",
+            node_index
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::database::models::Node;
+use crate::integrations::is_capacity_error;
 use crate::integrations::providers::aws::{AwsInterface, interface::AwsClusterContext};
 
 use anyhow::Result;
@@ -6,22 +7,39 @@ use tokio::time::{Duration, sleep};
 
 /// Result of a launch. `instance_type` is the type that AWS actually accepted,
 /// which differs from the node's preferred type whenever a capacity fallback
-/// applied, so callers can persist what really came up.
+/// applied, so callers can persist what really came up. `market_type` is
+/// likewise read back from the instance ('spot' or 'on-demand') rather than
+/// assumed from the node's allocation_mode.
 pub struct InstanceLaunch {
     pub instance_id: String,
     pub instance_type: String,
+    pub market_type: String,
+}
+
+/// Rounds over the candidate types before a launch gives up, 30s apart.
+pub const MAX_CAPACITY_ROUNDS: u32 = 10;
+const CAPACITY_RETRY_DELAY_SECS: u64 = 30;
+
+fn instance_market_type(instance: &aws_sdk_ec2::types::Instance) -> String {
+    match instance.instance_lifecycle() {
+        Some(aws_sdk_ec2::types::InstanceLifecycleType::Spot) => "spot".to_string(),
+        _ => "on-demand".to_string(),
+    }
 }
 
 impl AwsInterface {
     /// `candidate_instance_types` is an ordered preference list. The first entry
     /// that AWS has capacity for wins. Pass an empty slice to launch exactly
-    /// `node.instance_type` with no fallback.
+    /// `node.instance_type` with no fallback. `capacity_rounds` is how many
+    /// times the list is retried after a first pass finds no capacity, 0 when
+    /// another zone is there to try instead.
     pub async fn request_elastic_compute_instance_creation(
         &self,
         context: &AwsClusterContext,
         node: &Node,
         node_index: usize,
         candidate_instance_types: &[String],
+        capacity_rounds: u32,
     ) -> Result<InstanceLaunch> {
         let instance_name = context.ec2_instance_name(node_index);
         let describe_instances_response = match context
@@ -64,6 +82,7 @@ impl AwsInterface {
                                             .instance_type()
                                             .map(|t| t.as_str().to_string())
                                             .unwrap_or_else(|| node.instance_type.clone()),
+                                        market_type: instance_market_type(instance),
                                     });
                                 }
                                 aws_sdk_ec2::types::InstanceStateName::Terminated
@@ -145,6 +164,9 @@ impl AwsInterface {
             .min_count(1)
             .max_count(1)
             .key_name(context.ssh_key_name.clone())
+            // No interface_type here: an attached, pre-existing ENI already has
+            // one, and ensure_elastic_network_interface creates it as EFA (and
+            // checks it on reuse) when the cluster asks for EFA.
             .network_interfaces(
                 aws_sdk_ec2::types::InstanceNetworkInterfaceSpecification::builder()
                     .device_index(0)
@@ -160,14 +182,39 @@ impl AwsInterface {
             run_instances_request = run_instances_request.credit_specification(credit_spec);
         }
 
-        if context.use_node_affinity {
-            if let Some(placement_group_name) = &context.placement_group_name_actual {
-                run_instances_request = run_instances_request.placement(
-                    aws_sdk_ec2::types::Placement::builder()
-                        .group_name(placement_group_name)
-                        .build(),
-                );
-            }
+        if node.allocation_mode == "spot" {
+            let spot_instance_type = match node.spot_request_type.as_deref() {
+                Some("persistent") => aws_sdk_ec2::types::SpotInstanceType::Persistent,
+                _ => aws_sdk_ec2::types::SpotInstanceType::OneTime,
+            };
+            let interruption_behavior = aws_sdk_ec2::types::InstanceInterruptionBehavior::from(
+                node.spot_interruption_behavior.as_deref().unwrap_or("terminate"),
+            );
+            // No max price means AWS caps the bid at the on-demand price.
+            let spot_options = aws_sdk_ec2::types::SpotMarketOptions::builder()
+                .set_max_price(node.spot_max_price.map(|price| price.to_string()))
+                .spot_instance_type(spot_instance_type)
+                .instance_interruption_behavior(interruption_behavior)
+                .build();
+            run_instances_request = run_instances_request.instance_market_options(
+                aws_sdk_ec2::types::InstanceMarketOptionsRequest::builder()
+                    .market_type(aws_sdk_ec2::types::MarketType::Spot)
+                    .spot_options(spot_options)
+                    .build(),
+            );
+        }
+
+        // The placement group lives in the cluster's zone, so a node moved to a
+        // fallback zone launches outside it.
+        if context.use_node_affinity
+            && !context.node_availability_zones.contains_key(&node_index)
+            && let Some(placement_group_name) = &context.placement_group_name_actual
+        {
+            run_instances_request = run_instances_request.placement(
+                aws_sdk_ec2::types::Placement::builder()
+                    .group_name(placement_group_name)
+                    .build(),
+            );
         }
 
         run_instances_request = run_instances_request.tag_specifications(
@@ -193,9 +240,6 @@ impl AwsInterface {
         // the list means a preferred type that frees up is still picked over a
         // fallback that was available earlier. Only when every candidate is refused
         // does the round sleep and repeat.
-        const MAX_CAPACITY_ROUNDS: u32 = 10;
-        const CAPACITY_RETRY_DELAY_SECS: u64 = 30;
-
         let candidates: Vec<String> = if candidate_instance_types.is_empty() {
             vec![node.instance_type.clone()]
         } else {
@@ -208,6 +252,9 @@ impl AwsInterface {
         let mut last_transient_error = String::from("none");
         let (run_instances_response, launched_instance_type) = loop {
             let mut accepted = None;
+            // Waiting out a busy interface is needed in any zone, so it keeps the
+            // full retry budget even when the capacity budget is spent.
+            let mut eni_was_busy = false;
             for candidate in &candidates {
                 let attempt = run_instances_request
                     .clone()
@@ -232,11 +279,18 @@ impl AwsInterface {
                         // purely a matter of retrying until the detach completes.
                         // A fixed pre-launch sleep cannot cover it because detach time
                         // is not bounded.
-                        let no_capacity = msg.contains("InsufficientInstanceCapacity");
+                        //
+                        // SpotMaxPriceTooLow is grouped with the capacity shortage: the
+                        // spot price is per type, so a fallback type may still fit under
+                        // the node's max price, and the price itself moves over time.
+                        let no_capacity = is_capacity_error(&msg);
                         let eni_busy = msg.contains("InvalidNetworkInterface.InUse");
+                        eni_was_busy |= eni_busy;
                         if no_capacity || eni_busy {
                             last_transient_error = if eni_busy {
                                 "InvalidNetworkInterface.InUse".to_string()
+                            } else if msg.contains("SpotMaxPriceTooLow") {
+                                format!("SpotMaxPriceTooLow ({})", candidate)
                             } else {
                                 format!("InsufficientInstanceCapacity ({})", candidate)
                             };
@@ -247,7 +301,7 @@ impl AwsInterface {
                                 );
                             } else if candidates.len() > 1 {
                                 tracing::warn!(
-                                    "InsufficientInstanceCapacity for '{}' as '{}' — trying next preferred type",
+                                    "No capacity for '{}' as '{}' — trying next preferred type",
                                     instance_name,
                                     candidate
                                 );
@@ -268,13 +322,18 @@ impl AwsInterface {
             }
 
             round += 1;
-            if round > MAX_CAPACITY_ROUNDS {
+            let max_rounds = if eni_was_busy {
+                MAX_CAPACITY_ROUNDS
+            } else {
+                capacity_rounds
+            };
+            if round > max_rounds {
                 anyhow::bail!(
                     "Could not launch '{}' as any of [{}] after {} rounds ({}s). Last transient error: {}",
                     instance_name,
                     candidates.join(", "),
-                    MAX_CAPACITY_ROUNDS,
-                    MAX_CAPACITY_ROUNDS as u64 * CAPACITY_RETRY_DELAY_SECS,
+                    max_rounds,
+                    max_rounds as u64 * CAPACITY_RETRY_DELAY_SECS,
                     last_transient_error
                 );
             }
@@ -284,7 +343,7 @@ impl AwsInterface {
                 candidates.join(", "),
                 CAPACITY_RETRY_DELAY_SECS,
                 round,
-                MAX_CAPACITY_ROUNDS
+                max_rounds
             );
             sleep(Duration::from_secs(CAPACITY_RETRY_DELAY_SECS)).await;
         };
@@ -299,8 +358,10 @@ impl AwsInterface {
                         node.instance_type
                     );
                 }
+                let market_type = instance_market_type(instance);
                 tracing::info!(
-                    "Requested new instance '{}' (type='{}') with ID '{}' and {}GB root volume",
+                    "Requested new {} instance '{}' (type='{}') with ID '{}' and {}GB root volume",
+                    market_type,
                     instance_name,
                     launched_instance_type,
                     instance_id,
@@ -309,6 +370,7 @@ impl AwsInterface {
                 return Ok(InstanceLaunch {
                     instance_id: instance_id.to_string(),
                     instance_type: launched_instance_type,
+                    market_type,
                 });
             }
         }
@@ -460,6 +522,7 @@ impl AwsInterface {
         };
 
         let mut instances_to_terminate: Vec<String> = Vec::new();
+        let mut spot_requests_to_cancel: Vec<String> = Vec::new();
         for reservation in describe_instances_response.reservations() {
             for instance in reservation.instances() {
                 if let Some(instance_id) = instance.instance_id() {
@@ -476,6 +539,9 @@ impl AwsInterface {
                                         state_name
                                     );
                                     instances_to_terminate.push(instance_id.to_string());
+                                    if let Some(request_id) = instance.spot_instance_request_id() {
+                                        spot_requests_to_cancel.push(request_id.to_string());
+                                    }
                                 }
                                 aws_sdk_ec2::types::InstanceStateName::Terminated
                                 | aws_sdk_ec2::types::InstanceStateName::ShuttingDown => {
@@ -498,6 +564,31 @@ impl AwsInterface {
             }
         }
 
+        // A persistent spot request outlives its instance: terminating only the
+        // instance makes AWS launch a fresh one from the same request, and keep
+        // billing for it, after the cluster is gone. Cancel the requests first.
+        if !spot_requests_to_cancel.is_empty() {
+            match context
+                .ec2_client
+                .cancel_spot_instance_requests()
+                .set_spot_instance_request_ids(Some(spot_requests_to_cancel.clone()))
+                .send()
+                .await
+            {
+                Ok(_) => {
+                    tracing::info!(
+                        "Cancelled {} spot instance request(s): {:?}",
+                        spot_requests_to_cancel.len(),
+                        spot_requests_to_cancel
+                    );
+                }
+                Err(e) => {
+                    tracing::error!("{:?}", e);
+                    anyhow::bail!("Failure cancelling spot instance requests: {}", e);
+                }
+            }
+        }
+
         if instances_to_terminate.is_empty() {
             tracing::info!(
                 "No EC2 instances found for cluster '{}'",
@@ -4,13 +4,31 @@ use anyhow::Result;
 use std::time::Duration;
 use tokio::time::sleep;
 
+/// The cluster's Subnet in `availability_zone`, which holds the EFS mount
+/// target its nodes there mount from.
+fn zone_subnet_id(context: &AwsClusterContext, availability_zone: &str) -> Result<String> {
+    let subnet_id = if availability_zone == context.availability_zone {
+        context.subnet_id.clone()
+    } else {
+        context.fallback_subnet_ids.get(availability_zone).cloned()
+    };
+    match subnet_id {
+        Some(subnet_id) => Ok(subnet_id),
+        None => anyhow::bail!("Missing Subnet for zone '{}'", availability_zone),
+    }
+}
+
 impl AwsInterface {
+    /// Requests the mount target in `availability_zone`. EFS serves a zone only
+    /// through a mount target of its own, so a zone a node is moved to needs
+    /// one too.
     pub async fn request_elastic_file_system_mount_target_creation(
         &self,
         context: &AwsClusterContext,
+        availability_zone: &str,
     ) -> Result<String> {
         let efs_id = context.efs_device_id.clone().unwrap();
-        let subnet_id = context.subnet_id.clone().unwrap();
+        let subnet_id = zone_subnet_id(context, availability_zone)?;
 
         match context
             .efs_client
@@ -30,7 +48,11 @@ impl AwsInterface {
                             subnet_id
                         );
                         return Ok(mount_target_id);
-                    } else {
+                    } else if !context
+                        .fallback_subnet_ids
+                        .values()
+                        .any(|id| id == mount_target_info.subnet_id())
+                    {
                         tracing::warn!(
                             "There's an unexpected EFS mount target (id='{}') for EFS device (id='{}') in Subnet (id='{}')",
                             mount_target_id,
@@ -49,13 +71,14 @@ impl AwsInterface {
         // Pin the mount target to a high IP outside the node-IP range (10.0.0.10+).
         // AWS's DHCP for EFS mount targets is non-deterministic and can grab low IPs
         // that collide with node ENIs (see interface.rs:network_interface_private_ip).
+        let mount_target_ip = format!("10.0.{}.250", context.zone_subnet_index(availability_zone));
         let new_mount_target_id = match context
             .efs_client
             .create_mount_target()
             .file_system_id(efs_id.clone())
             .subnet_id(subnet_id.clone())
             .security_groups(&context.security_group_ids[0])
-            .ip_address("10.0.0.250")
+            .ip_address(mount_target_ip)
             .send()
             .await
         {
@@ -137,7 +160,67 @@ impl AwsInterface {
         }
     }
 
-    /// Fetches the IP address of the EFS mount target in the cluster's subnet.
+    /// Waits for a mount target requested after the cluster's first one, in a
+    /// fallback zone, to become available.
+    pub async fn wait_for_elastic_file_system_mount_target_to_be_available(
+        &self,
+        context: &AwsClusterContext,
+        mount_target_id: &str,
+    ) -> Result<()> {
+        let max_wait_time = Duration::from_secs(300);
+        let poll_interval = Duration::from_secs(10);
+        let start_time = std::time::Instant::now();
+
+        loop {
+            if start_time.elapsed() >= max_wait_time {
+                anyhow::bail!(
+                    "Timeout waiting for EFS mount target (id='{}') to be available after {} seconds",
+                    mount_target_id,
+                    max_wait_time.as_secs()
+                );
+            }
+
+            let response = match context
+                .efs_client
+                .describe_mount_targets()
+                .mount_target_id(mount_target_id)
+                .send()
+                .await
+            {
+                Ok(response) => response,
+                Err(e) => {
+                    tracing::error!("{:?}", e);
+                    anyhow::bail!("Failure describing EFS mount target: {}", e);
+                }
+            };
+
+            match response.mount_targets().first() {
+                Some(mount_target)
+                    if *mount_target.life_cycle_state()
+                        == aws_sdk_efs::types::LifeCycleState::Available =>
+                {
+                    tracing::info!(
+                        "EFS mount target (id='{}') is now available!",
+                        mount_target_id
+                    );
+                    return Ok(());
+                }
+                Some(mount_target) => {
+                    tracing::info!(
+                        "EFS mount target (id='{}') is not available yet, (state='{}')...",
+                        mount_target_id,
+                        mount_target.life_cycle_state()
+                    );
+                }
+                None => anyhow::bail!("EFS mount target (id='{}') not found", mount_target_id),
+            }
+
+            sleep(poll_interval).await;
+        }
+    }
+
+    /// Fetches the IP address of the EFS mount target in the cluster's subnet
+    /// in `availability_zone`.
     /// Used to mount the EFS by IP instead of DNS name, sidestepping systemd-resolved's
     /// NXDOMAIN negative-caching behavior that has caused mount failures during
     /// early instance boot (see resource_manager.rs EFS attach script).
@@ -147,9 +230,10 @@ impl AwsInterface {
     pub async fn get_elastic_file_system_mount_target_ip(
         &self,
         context: &AwsClusterContext,
+        availability_zone: &str,
     ) -> Result<String> {
         let efs_id = context.efs_device_id.clone().unwrap();
-        let subnet_id = context.subnet_id.clone().unwrap();
+        let subnet_id = zone_subnet_id(context, availability_zone)?;
 
         let response = match context
             .efs_client
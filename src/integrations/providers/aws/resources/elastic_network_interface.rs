@@ -1,3 +1,4 @@
+use crate::integrations::NetworkInterfaceRelease;
 use crate::integrations::providers::aws::{AwsInterface, interface::AwsClusterContext};
 
 use anyhow::Result;
@@ -11,7 +12,13 @@ impl AwsInterface {
     ) -> Result<String> {
         let eni_name = context.network_interface_name(node_index);
         let private_ip = context.network_interface_private_ip(node_index);
-        let context_subnet_id = context.subnet_id.as_ref().unwrap();
+        let Some(context_subnet_id) = context.node_subnet_id(node_index) else {
+            anyhow::bail!(
+                "Missing Subnet for zone '{}' of node {}",
+                context.node_availability_zone(node_index),
+                node_index
+            );
+        };
         let context_security_group_ids = &context.security_group_ids;
 
         let describe_eni_response = match context
@@ -44,6 +51,20 @@ impl AwsInterface {
                     eni_name,
                     eni_id
                 );
+                // An interface's type is fixed at creation and the instance inherits
+                // it, so reusing a plain ENI on an EFA cluster would quietly launch
+                // the node on ENA. Refuse instead of handing it to run_instances.
+                if context.use_elastic_fabric_adapters
+                    && eni.interface_type() != Some(&aws_sdk_ec2::types::NetworkInterfaceType::Efa)
+                {
+                    anyhow::bail!(
+                        "Existing Elastic Network Interface '{}' ('{}') is not an EFA interface \
+                        (type: {:?}). Terminate the cluster so it is recreated with EFA",
+                        eni_name,
+                        eni_id,
+                        eni.interface_type()
+                    );
+                }
                 return Ok(eni_id.to_string());
             }
         }
@@ -92,7 +113,7 @@ impl AwsInterface {
             tracing::info!(
                 "Created new Elastic Network Interface '{}'{}",
                 eni_id,
-                if context.use_node_affinity {
+                if context.use_elastic_fabric_adapters {
                     " (EFA enabled)"
                 } else {
                     ""
@@ -211,9 +232,85 @@ impl AwsInterface {
         Ok(())
     }
 
-    /// Delete any detached ENI whose private IP matches `private_ip`.
-    /// Used by the scale-down path to clean up the dead node's ENI before it
-    /// blocks security-group and subnet deletion on cluster termination.
+    async fn wait_for_eni_status(
+        &self,
+        context: &AwsClusterContext,
+        eni_id: &str,
+        desired_status: aws_sdk_ec2::types::NetworkInterfaceStatus,
+    ) -> Result<()> {
+        let max_attempts = 10; // Maximum number of attempts (10 * 6 seconds = 1 minute)
+        let sleep_duration = Duration::from_secs(6);
+
+        for attempt in 1..=max_attempts {
+            match context
+                .ec2_client
+                .describe_network_interfaces()
+                .network_interface_ids(eni_id)
+                .send()
+                .await
+            {
+                Ok(response) => {
+                    if let Some(eni) = response.network_interfaces().first() {
+                        match eni.status() {
+                            Some(status) if *status == desired_status => {
+                                tracing::info!(
+                                    "Network interface '{}' reached desired status: {:?}",
+                                    eni_id,
+                                    desired_status
+                                );
+                                return Ok(());
+                            }
+                            Some(status) => {
+                                tracing::info!(
+                                    "Elastic Network Interface '{}' status: {:?}, waiting for {:?} (attempt {}/{})",
+                                    eni_id,
+                                    status,
+                                    desired_status,
+                                    attempt,
+                                    max_attempts
+                                );
+                            }
+                            None => {
+                                tracing::warn!(
+                                    "Elastic Network Interface '{}' status is unknown, waiting for {:?} (attempt {}/{})",
+                                    eni_id,
+                                    desired_status,
+                                    attempt,
+                                    max_attempts
+                                );
+                            }
+                        }
+                    } else {
+                        anyhow::bail!(
+                            "Elastic Network Interface '{}' not found during status check",
+                            eni_id
+                        );
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("{:?}", e);
+                    anyhow::bail!(
+                        "Failed to check Elastic Network Interface '{}' status",
+                        eni_id
+                    );
+                }
+            }
+
+            if attempt < max_attempts {
+                tokio::time::sleep(sleep_duration).await;
+            }
+        }
+
+        anyhow::bail!(
+            "Elastic Network Interface '{}' did not reach desired status {:?} within {} seconds",
+            eni_id,
+            desired_status,
+            max_attempts * sleep_duration.as_secs()
+        );
+    }
+}
+
+impl NetworkInterfaceRelease for AwsInterface {
     /// Blocks until the ENIs for `private_ips` have been released by the
     /// instances that held them, or until `max_wait` elapses.
     ///
@@ -226,7 +323,7 @@ impl AwsInterface {
     /// restore came to fail outright with InvalidNetworkInterface.InUse.
     ///
     /// Returns true if every address was released within the deadline.
-    pub async fn wait_for_enis_released(
+    async fn wait_for_enis_released(
         &self,
         region: &str,
         private_ips: &[String],
@@ -291,7 +388,10 @@ impl AwsInterface {
         }
     }
 
-    pub async fn delete_detached_eni_by_private_ip(
+    /// Delete any detached ENI whose private IP matches `private_ip`.
+    /// Used by the scale-down path to clean up the dead node's ENI before it
+    /// blocks security-group and subnet deletion on cluster termination.
+    async fn delete_detached_eni_by_private_ip(
         &self,
         region: &str,
         private_ip: &str,
@@ -351,81 +451,4 @@ impl AwsInterface {
 
         Ok(())
     }
-
-    async fn wait_for_eni_status(
-        &self,
-        context: &AwsClusterContext,
-        eni_id: &str,
-        desired_status: aws_sdk_ec2::types::NetworkInterfaceStatus,
-    ) -> Result<()> {
-        let max_attempts = 10; // Maximum number of attempts (10 * 6 seconds = 1 minute)
-        let sleep_duration = Duration::from_secs(6);
-
-        for attempt in 1..=max_attempts {
-            match context
-                .ec2_client
-                .describe_network_interfaces()
-                .network_interface_ids(eni_id)
-                .send()
-                .await
-            {
-                Ok(response) => {
-                    if let Some(eni) = response.network_interfaces().first() {
-                        match eni.status() {
-                            Some(status) if *status == desired_status => {
-                                tracing::info!(
-                                    "Network interface '{}' reached desired status: {:?}",
-                                    eni_id,
-                                    desired_status
-                                );
-                                return Ok(());
-                            }
-                            Some(status) => {
-                                tracing::info!(
-                                    "Elastic Network Interface '{}' status: {:?}, waiting for {:?} (attempt {}/{})",
-                                    eni_id,
-                                    status,
-                                    desired_status,
-                                    attempt,
-                                    max_attempts
-                                );
-                            }
-                            None => {
-                                tracing::warn!(
-                                    "Elastic Network Interface '{}' status is unknown, waiting for {:?} (attempt {}/{})",
-                                    eni_id,
-                                    desired_status,
-                                    attempt,
-                                    max_attempts
-                                );
-                            }
-                        }
-                    } else {
-                        anyhow::bail!(
-                            "Elastic Network Interface '{}' not found during status check",
-                            eni_id
-                        );
-                    }
-                }
-                Err(e) => {
-                    tracing::error!("{:?}", e);
-                    anyhow::bail!(
-                        "Failed to check Elastic Network Interface '{}' status",
-                        eni_id
-                    );
-                }
-            }
-
-            if attempt < max_attempts {
-                tokio::time::sleep(sleep_duration).await;
-            }
-        }
-
-        anyhow::bail!(
-            "Elastic Network Interface '{}' did not reach desired status {:?} within {} seconds",
-            eni_id,
-            desired_status,
-            max_attempts * sleep_duration.as_secs()
-        );
-    }
 }
@@ -253,6 +253,42 @@ impl AwsInterface {
         anyhow::bail!("Failure finding the id of the created Route Table resource");
     }
 
+    /// Associates the cluster's Route Table with a Subnet created after it, in a
+    /// fallback zone.
+    pub async fn associate_route_table_with_subnet(
+        &self,
+        context: &AwsClusterContext,
+        subnet_id: &str,
+    ) -> Result<()> {
+        let route_table_id = context.route_table_id.as_ref().unwrap();
+        tracing::info!(
+            "Associating Route Table '{}' with Subnet '{}'...",
+            route_table_id,
+            subnet_id
+        );
+        match context
+            .ec2_client
+            .associate_route_table()
+            .route_table_id(route_table_id)
+            .subnet_id(subnet_id)
+            .send()
+            .await
+        {
+            Ok(_) => Ok(()),
+            // A resumed restore finds the Subnet it created last time.
+            Err(e) if format!("{:?}", e).contains("Resource.AlreadyAssociated") => Ok(()),
+            Err(e) => {
+                tracing::error!("{:?}", e);
+                anyhow::bail!(
+                    "Failure associating Route Table '{}' with Subnet '{}': {}",
+                    route_table_id,
+                    subnet_id,
+                    e
+                );
+            }
+        }
+    }
+
     pub async fn cleanup_route_table(&self, context: &AwsClusterContext) -> Result<()> {
         let describe_route_tables_response = match context
             .ec2_client
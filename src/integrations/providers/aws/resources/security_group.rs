@@ -45,6 +45,13 @@ impl AwsInterface {
 
             if !security_group_ids.is_empty() {
                 tracing::info!("Using existing Security Groups: {:?}", security_group_ids);
+                // Groups created before the egress rule existed lack it.
+                if context.use_elastic_fabric_adapters {
+                    for sg_id in &security_group_ids {
+                        self.ensure_self_referential_egress_rule(context, sg_id)
+                            .await?;
+                    }
+                }
                 return Ok(security_group_ids);
             }
         }
@@ -122,6 +129,9 @@ impl AwsInterface {
                 }
             }
 
+            self.ensure_self_referential_egress_rule(context, security_group_id)
+                .await?;
+
             // Add SSH ingress rule for external access
             tracing::info!(
                 "Adding SSH ingress rule to security group '{}'...",
@@ -168,6 +178,63 @@ impl AwsInterface {
         }
     }
 
+    /// EFA traffic must be allowed out of the group to itself by an explicit
+    /// rule. The default 0.0.0.0/0 egress rule covers plain IP traffic but not
+    /// the OS-bypass path, so without this MPI over EFA hangs at startup even
+    /// though fi_info lists the provider.
+    async fn ensure_self_referential_egress_rule(
+        &self,
+        context: &AwsClusterContext,
+        security_group_id: &str,
+    ) -> Result<()> {
+        tracing::info!(
+            "Adding self-referential egress rule to Security Group '{}'...",
+            security_group_id
+        );
+        match context
+            .ec2_client
+            .authorize_security_group_egress()
+            .group_id(security_group_id)
+            .ip_permissions(
+                aws_sdk_ec2::types::IpPermission::builder()
+                    .ip_protocol("-1") // All protocols
+                    .from_port(0)
+                    .to_port(0)
+                    .user_id_group_pairs(
+                        aws_sdk_ec2::types::UserIdGroupPair::builder()
+                            .group_id(security_group_id) // Self-reference
+                            .build(),
+                    )
+                    .build(),
+            )
+            .send()
+            .await
+        {
+            Ok(_) => {
+                tracing::info!(
+                    "Successfully added self-referential egress rule to Security Group '{}'",
+                    security_group_id
+                );
+                Ok(())
+            }
+            Err(e) if format!("{:?}", e).contains("InvalidPermission.Duplicate") => {
+                tracing::info!(
+                    "Security Group '{}' already has the self-referential egress rule",
+                    security_group_id
+                );
+                Ok(())
+            }
+            Err(e) => {
+                tracing::error!("{:?}", e);
+                anyhow::bail!(
+                    "Failure adding self-referential egress rule to Security Group '{}': {}",
+                    security_group_id,
+                    e
+                );
+            }
+        }
+    }
+
     pub async fn cleanup_security_group(&self, context: &AwsClusterContext) -> Result<()> {
         let describe_security_groups_response = match context
             .ec2_client
@@ -1,11 +1,12 @@
 use crate::database::models::Cluster;
+use crate::integrations::SpotInterruptionQueue;
 use crate::integrations::providers::aws::AwsInterface;
 
 use anyhow::Result;
 use aws_sdk_sqs::types::QueueAttributeName;
 use std::collections::HashMap;
 
-impl AwsInterface {
+impl SpotInterruptionQueue for AwsInterface {
     /// Publishes a synthetic spot interruption notice for one node onto this
     /// cluster's own interruption queue, in the shape EventBridge delivers.
     ///
@@ -18,7 +19,7 @@ impl AwsInterface {
     /// Used by V-B scenario (i), where the notice must arrive at a controlled
     /// moment. Pair it with `cluster test-failure` on the same node roughly two
     /// minutes later to reproduce AWS reclaiming the instance after its warning.
-    pub async fn send_simulated_spot_interruption(
+    async fn send_simulated_spot_interruption(
         &self,
         cluster: &Cluster,
         node_private_ip: &str,
@@ -78,7 +79,7 @@ impl AwsInterface {
     /// Creates an SQS queue and an EventBridge rule that routes EC2 spot interruption
     /// warnings for this cluster into the queue. Returns the queue URL.
     /// Idempotent: safe to call again after a crash.
-    pub async fn ensure_spot_interruption_queue(
+    async fn ensure_spot_interruption_queue(
         &self,
         cluster_id: &str,
         region: &str,
@@ -166,7 +167,7 @@ impl AwsInterface {
 
     /// Drains the spot interruption SQS queue and returns the private IPs of any nodes
     /// belonging to this cluster that have received an interruption notice.
-    pub async fn poll_spot_interruption_queue(
+    async fn poll_spot_interruption_queue(
         &self,
         cluster_id: &str,
         region: &str,
@@ -246,7 +247,7 @@ impl AwsInterface {
 
     /// Resolves the queue URL for this cluster's spot interruption queue.
     /// Returns None if the queue does not exist (i.e., cluster has no spot nodes).
-    pub async fn get_spot_interruption_queue_url(
+    async fn get_spot_interruption_queue_url(
         &self,
         cluster_id: &str,
         region: &str,
@@ -260,7 +261,7 @@ impl AwsInterface {
     }
 
     /// Removes the EventBridge rule and SQS queue created for this cluster.
-    pub async fn cleanup_spot_interruption_queue(
+    async fn cleanup_spot_interruption_queue(
         &self,
         cluster_id: &str,
         region: &str,
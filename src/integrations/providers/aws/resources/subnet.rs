@@ -3,11 +3,24 @@ use crate::integrations::providers::aws::{AwsInterface, interface::AwsClusterCon
 use anyhow::Result;
 
 impl AwsInterface {
-    pub async fn ensure_subnet(&self, context: &AwsClusterContext) -> Result<String> {
+    /// Finds or creates the cluster's Subnet in `availability_zone`, which is
+    /// the cluster's own zone except when a restore moves a node elsewhere.
+    pub async fn ensure_subnet(
+        &self,
+        context: &AwsClusterContext,
+        availability_zone: &str,
+    ) -> Result<String> {
+        let subnet_name = context.subnet_name_for(availability_zone);
         let describe_subnets_response = match context
             .ec2_client
             .describe_subnets()
             .filters(context.cluster_id_filter.clone())
+            .filters(
+                aws_sdk_ec2::types::Filter::builder()
+                    .name("tag:Name")
+                    .values(&subnet_name)
+                    .build(),
+            )
             .send()
             .await
         {
@@ -21,22 +34,25 @@ impl AwsInterface {
         let subnets = describe_subnets_response.subnets();
         if let Some(subnet) = subnets.first() {
             if let Some(subnet_id) = subnet.subnet_id() {
-                tracing::info!("Found existing Subnet: '{}'", subnet_id);
+                tracing::info!("Found existing Subnet '{}': '{}'", subnet_name, subnet_id);
                 return Ok(subnet_id.to_string());
             }
         }
 
-        tracing::info!("No existing Subnet found, creating a new one...");
+        tracing::info!(
+            "No existing Subnet '{}' found, creating a new one...",
+            subnet_name
+        );
 
         let create_subnet_response = match context
             .ec2_client
             .create_subnet()
             .vpc_id(context.vpc_id.as_ref().unwrap())
-            .cidr_block(context.subnet_cidr_block.clone())
-            .set_availability_zone(if context.availability_zone.is_empty() {
+            .cidr_block(context.subnet_cidr_block_for(availability_zone))
+            .set_availability_zone(if availability_zone.is_empty() {
                 None
             } else {
-                Some(context.availability_zone.clone())
+                Some(availability_zone.to_string())
             })
             .tag_specifications(
                 aws_sdk_ec2::types::TagSpecification::builder()
@@ -44,7 +60,7 @@ impl AwsInterface {
                     .tags(
                         aws_sdk_ec2::types::Tag::builder()
                             .key("Name")
-                            .value(context.subnet_name.clone())
+                            .value(subnet_name)
                             .build(),
                     )
                     .tags(context.cluster_id_tag.clone())
@@ -72,6 +88,7 @@ impl AwsInterface {
         anyhow::bail!("Failure finding the id of the created Subnet resource");
     }
 
+    /// Deletes every Subnet of the cluster, fallback zones included.
     pub async fn cleanup_subnet(&self, context: &AwsClusterContext) -> Result<()> {
         let describe_subnets_response = match context
             .ec2_client
@@ -88,30 +105,29 @@ impl AwsInterface {
         };
 
         let subnets = describe_subnets_response.subnets();
-        if let Some(subnet) = subnets.first() {
-            if let Some(subnet_id) = subnet.subnet_id() {
-                tracing::info!("Found existing Subnet to cleanup: '{}'", subnet_id);
-                tracing::info!("Deleting Subnet '{}'...", subnet_id);
-                match context
-                    .ec2_client
-                    .delete_subnet()
-                    .subnet_id(subnet_id)
-                    .send()
-                    .await
-                {
-                    Ok(_) => {
-                        tracing::info!("Subnet '{}' deleted successfully", subnet_id);
-                        return Ok(());
-                    }
-                    Err(e) => {
-                        tracing::error!("{:?}", e);
-                        anyhow::bail!("Failure deleting Subnet resource: {}", e);
-                    }
-                };
-            }
+        if subnets.is_empty() {
+            tracing::info!("No existing Subnet found");
+            return Ok(());
+        }
+        for subnet_id in subnets.iter().filter_map(|subnet| subnet.subnet_id()) {
+            tracing::info!("Found existing Subnet to cleanup: '{}'", subnet_id);
+            tracing::info!("Deleting Subnet '{}'...", subnet_id);
+            match context
+                .ec2_client
+                .delete_subnet()
+                .subnet_id(subnet_id)
+                .send()
+                .await
+            {
+                Ok(_) => {
+                    tracing::info!("Subnet '{}' deleted successfully", subnet_id);
+                }
+                Err(e) => {
+                    tracing::error!("{:?}", e);
+                    anyhow::bail!("Failure deleting Subnet resource: {}", e);
+                }
+            };
         }
-
-        tracing::info!("No existing Subnet found");
         Ok(())
     }
 }
@@ -0,0 +1,152 @@
+use crate::database::models::ConfigVar;
+use crate::utils::command_runner::{CommandHandler, CommandRunner};
+
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, LazyLock, Mutex, MutexGuard};
+use tokio::time::Duration;
+
+/// Interfaces handed to the provider factory by `MOCK_ID`. A ProviderConfig can
+/// only carry strings, so a test registers its mock here and stores the id.
+static REGISTRY: LazyLock<Mutex<HashMap<String, MockInterface>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Failures the mock injects, all keyed on node indices and health-check ticks
+/// so a test can describe a scenario up front.
+#[derive(Clone, Default)]
+pub struct MockScript {
+    /// Launches of an instance type that fail for lack of capacity, consumed one
+    /// per attempt.
+    pub capacity_errors: HashMap<String, u32>,
+    /// Zones where no instance type has capacity at all.
+    pub exhausted_zones: HashSet<String>,
+    /// (tick, node_index): the node's instance dies at that health check.
+    pub instance_deaths: Vec<(u64, usize)>,
+    /// (tick, node_index): an interruption notice for the node is queued.
+    pub spot_notices: Vec<(u64, usize)>,
+    /// Time a dead instance holds its network interface before releasing it.
+    pub eni_release_delay: Duration,
+}
+
+pub struct MockInstance {
+    pub id: String,
+    pub cluster_id: String,
+    pub node_index: usize,
+    pub instance_type: String,
+    pub availability_zone: String,
+    pub private_ip: String,
+    pub public_ip: Option<String>,
+    pub running: bool,
+}
+
+#[derive(Default)]
+pub struct MockState {
+    pub script: MockScript,
+    /// Number of health checks so far; the clock the script runs on.
+    pub tick: u64,
+    pub instances: Vec<MockInstance>,
+    /// Every instance type a launch was attempted with, in order.
+    pub launch_attempts: Vec<String>,
+    /// (ip, script) of every command sent to a node.
+    pub commands: Vec<(String, String)>,
+    pub spot_queues: HashSet<String>,
+    pub eni_release_waits: usize,
+}
+
+impl MockState {
+    pub fn live_instance(&self, cluster_id: &str, node_index: usize) -> Option<&MockInstance> {
+        self.instances
+            .iter()
+            .find(|i| i.running && i.cluster_id == cluster_id && i.node_index == node_index)
+    }
+
+    pub fn live_instance_by_ip(&self, cluster_id: &str, private_ip: &str) -> Option<&MockInstance> {
+        self.instances
+            .iter()
+            .find(|i| i.running && i.cluster_id == cluster_id && i.private_ip == private_ip)
+    }
+
+    pub fn kill(&mut self, cluster_id: &str, node_index: usize) {
+        for instance in self
+            .instances
+            .iter_mut()
+            .filter(|i| i.running && i.cluster_id == cluster_id && i.node_index == node_index)
+        {
+            tracing::info!("Mock instance '{}' died", instance.id);
+            instance.running = false;
+        }
+    }
+}
+
+/// An in-process provider. Instances are rows in a shared state that tests can
+/// script and inspect, and commands go to a handler instead of SSH.
+#[derive(Clone)]
+pub struct MockInterface {
+    state: Arc<Mutex<MockState>>,
+    handler: CommandHandler,
+}
+
+impl MockInterface {
+    /// A mock whose nodes accept every command and print nothing.
+    pub fn new(script: MockScript) -> Self {
+        let state = Arc::new(Mutex::new(MockState {
+            script,
+            ..Default::default()
+        }));
+        let recorder = Arc::clone(&state);
+        let handler: CommandHandler = Arc::new(move |ip: &str, script: &str| {
+            recorder
+                .lock()
+                .unwrap()
+                .commands
+                .push((ip.to_string(), script.to_string()));
+            Ok(String::new())
+        });
+        Self { state, handler }
+    }
+
+    /// Answers node commands with `handler`, after recording them as usual.
+    pub fn with_command_handler(
+        mut self,
+        handler: impl Fn(&str, &str) -> Result<String> + Send + Sync + 'static,
+    ) -> Self {
+        let recorder = self.handler.clone();
+        self.handler = Arc::new(move |ip: &str, script: &str| {
+            recorder(ip, script)?;
+            handler(ip, script)
+        });
+        self
+    }
+
+    /// Makes this mock reachable from a ProviderConfig and returns the value for
+    /// its `MOCK_ID` variable.
+    pub fn register(&self) -> String {
+        let mock_id = crate::utils::generate_id();
+        REGISTRY
+            .lock()
+            .unwrap()
+            .insert(mock_id.clone(), self.clone());
+        mock_id
+    }
+
+    pub fn from_config_vars(config_vars: &[ConfigVar]) -> Result<Self> {
+        let Some(mock_id) = config_vars.iter().find(|v| v.key == "MOCK_ID") else {
+            anyhow::bail!("Missing 'MOCK_ID' in the mock provider configuration");
+        };
+        match REGISTRY.lock().unwrap().get(&mock_id.value) {
+            Some(mock) => Ok(mock.clone()),
+            None => anyhow::bail!("No mock provider registered as '{}'", mock_id.value),
+        }
+    }
+
+    pub fn state(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap()
+    }
+
+    pub fn command_runner(&self, ip: &str) -> CommandRunner {
+        CommandRunner::Scripted {
+            ip: ip.to_string(),
+            handler: self.handler.clone(),
+        }
+    }
+}
@@ -0,0 +1,7 @@
+mod interface;
+mod resource_catalog;
+mod resource_manager;
+mod resources;
+
+pub use interface::{MockInterface, MockScript, MockState};
+pub use resource_catalog::{MOCK_FALLBACK_ZONE, MOCK_IMAGE, MOCK_REGION, MOCK_ZONE};
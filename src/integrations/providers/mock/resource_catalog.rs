@@ -0,0 +1,97 @@
+use crate::database::models::{InstanceType, MachineImage};
+use crate::integrations::CloudInfoProvider;
+use crate::utils::ProgressTracker;
+
+use anyhow::Result;
+use std::collections::HashMap;
+
+use super::interface::MockInterface;
+
+pub const MOCK_REGION: &str = "mock-region-1";
+pub const MOCK_ZONE: &str = "mock-region-1a";
+pub const MOCK_FALLBACK_ZONE: &str = "mock-region-1b";
+pub const MOCK_IMAGE: &str = "mock-image-1";
+
+/// (name, vcpus, on-demand price per hour)
+const MOCK_INSTANCE_TYPES: [(&str, i64, f64); 2] =
+    [("mock.small", 2, 0.10), ("mock.large", 8, 0.32)];
+
+impl CloudInfoProvider for MockInterface {
+    async fn fetch_regions(&self, _tracker: &ProgressTracker) -> Result<Vec<String>> {
+        Ok(vec![MOCK_REGION.to_string()])
+    }
+
+    async fn fetch_zones(&self, region: &str, _tracker: &ProgressTracker) -> Result<Vec<String>> {
+        if region != MOCK_REGION {
+            anyhow::bail!("Invalid mock region: {}", region)
+        }
+        Ok(vec![MOCK_ZONE.to_string(), MOCK_FALLBACK_ZONE.to_string()])
+    }
+
+    async fn fetch_instance_types(
+        &self,
+        region: &str,
+        _tracker: &ProgressTracker,
+    ) -> Result<Vec<InstanceType>> {
+        let instance_types = MOCK_INSTANCE_TYPES
+            .iter()
+            .map(|(name, vcpus, price)| InstanceType {
+                name: name.to_string(),
+                cpu_architecture: "x86_64".to_string(),
+                vcpus: *vcpus,
+                core_count: Some(vcpus / 2),
+                threads_per_core: Some(2),
+                cpu_type: "mock".to_string(),
+                gpu_count: 0,
+                gpu_type: None,
+                fpga_count: 0,
+                fpga_type: None,
+                memory_in_mib: vcpus * 4096,
+                supports_spot: true,
+                is_baremetal: false,
+                is_burstable: false,
+                supports_efa: false,
+                has_affinity_settings: false,
+                on_demand_price_per_hour: Some(*price),
+                spot_price_per_hour: Some(price / 2.0),
+                region: region.to_string(),
+                provider_id: "mock".to_string(),
+            })
+            .collect();
+        Ok(instance_types)
+    }
+
+    async fn fetch_prices(
+        &self,
+        _region: &str,
+        instance_types: &[String],
+        _tracker: &ProgressTracker,
+    ) -> Result<HashMap<String, f64>> {
+        Ok(MOCK_INSTANCE_TYPES
+            .iter()
+            .filter(|(name, _, _)| instance_types.iter().any(|t| t == name))
+            .map(|(name, _, price)| (name.to_string(), *price))
+            .collect())
+    }
+
+    async fn fetch_machine_image(&self, region: &str, image_id: &str) -> Result<MachineImage> {
+        if image_id != MOCK_IMAGE {
+            anyhow::bail!(
+                "Machine Image (id='{}') not found in mock provider",
+                image_id
+            )
+        }
+        let now = chrono::Utc::now().naive_utc();
+        Ok(MachineImage {
+            id: image_id.to_string(),
+            name: "Mock Linux".to_string(),
+            description: "In-process image of the mock provider".to_string(),
+            owner: "mock".to_string(),
+            creation_date: String::new(),
+            provider: "mock".to_string(),
+            region: region.to_string(),
+            created_at: now,
+            updated_at: now,
+        })
+    }
+}
@@ -0,0 +1,426 @@
+use crate::database::models::{
+    Cluster, ClusterEvent, ClusterEventType, ClusterState, InstanceSpec, Node, RecoveryIncident,
+    RecoveryNode, RecoveryPhase, SpawnProgress, SpawnStepKind,
+};
+use crate::integrations::{CloudResourceManager, SpotInterruptionQueue, is_capacity_error};
+use crate::utils::command_runner::CommandRunner;
+use crate::utils::slurm;
+
+use anyhow::Result;
+use sqlx::sqlite::SqlitePool;
+use std::collections::HashMap;
+
+use super::interface::{MockInstance, MockInterface};
+
+impl MockInterface {
+    /// Launches the node's instance in `availability_zone`, trying `candidates`
+    /// in order when the script says a type has no capacity.
+    fn launch_instance(
+        &self,
+        cluster: &Cluster,
+        node: &Node,
+        node_index: usize,
+        candidates: &[String],
+        availability_zone: &str,
+    ) -> Result<MockInstance> {
+        let mut state = self.state();
+        let candidates = if candidates.is_empty() {
+            vec![node.instance_type.clone()]
+        } else {
+            candidates.to_vec()
+        };
+
+        for instance_type in &candidates {
+            state.launch_attempts.push(instance_type.clone());
+            if state.script.exhausted_zones.contains(availability_zone) {
+                tracing::warn!(
+                    "No capacity for '{}' (node {}) in zone '{}', trying next candidate",
+                    instance_type,
+                    node_index,
+                    availability_zone
+                );
+                continue;
+            }
+            if let Some(remaining) = state.script.capacity_errors.get_mut(instance_type)
+                && *remaining > 0
+            {
+                *remaining -= 1;
+                tracing::warn!(
+                    "No capacity for '{}' (node {}), trying next candidate",
+                    instance_type,
+                    node_index
+                );
+                continue;
+            }
+
+            // Like AWS, only the head node gets a public address, and a node keeps
+            // its private address across replacements within a zone. Each zone
+            // has a subnet of its own, in the order restore_zones lists them.
+            let subnet = cluster
+                .restore_zones(None)
+                .iter()
+                .position(|zone| zone == availability_zone)
+                .unwrap_or_default();
+            return Ok(MockInstance {
+                id: format!("mock-{}", crate::utils::generate_id()),
+                cluster_id: cluster.id.clone(),
+                node_index,
+                instance_type: instance_type.clone(),
+                availability_zone: availability_zone.to_string(),
+                private_ip: format!("10.0.{}.{}", subnet, 10 + node_index),
+                public_ip: (node_index == 0).then(|| format!("198.51.100.{}", 10 + node_index)),
+                running: true,
+            });
+        }
+
+        anyhow::bail!(
+            "InsufficientInstanceCapacity: no capacity for node {} (tried {})",
+            node_index,
+            candidates.join(", ")
+        )
+    }
+}
+
+impl CloudResourceManager for MockInterface {
+    async fn spawn_cluster(
+        &self,
+        pool: &SqlitePool,
+        cluster: Cluster,
+        mut nodes: Vec<Node>,
+    ) -> Result<()> {
+        let is_restore = cluster.state == ClusterState::Running;
+        let new_state = if is_restore {
+            ClusterState::Restoring
+        } else {
+            ClusterState::Spawning
+        };
+        cluster.update_state(pool, new_state).await?;
+
+        let mut capacity_fallbacks: HashMap<String, Vec<String>> = HashMap::new();
+        if is_restore {
+            for recovery_node in RecoveryNode::fetch_all_by_cluster_id(pool, &cluster.id).await? {
+                let types = recovery_node.launch_order();
+                if types.len() > 1 {
+                    capacity_fallbacks.entry(types[0].clone()).or_insert(types);
+                }
+            }
+        }
+
+        let mut progress = SpawnProgress::load(pool, &cluster.id).await?;
+
+        for node in nodes.iter_mut() {
+            let node_index = node.node_index as usize;
+            let existing = self
+                .state()
+                .live_instance(&cluster.id, node_index)
+                .map(|i| i.id.clone());
+            if let Some(instance_id) = existing {
+                tracing::info!(
+                    "Found existing mock instance '{}', skipping creation",
+                    instance_id
+                );
+                progress
+                    .complete_instance(pool, &node.id, &instance_id)
+                    .await?;
+                continue;
+            }
+
+            let candidates = capacity_fallbacks
+                .get(&node.instance_type)
+                .cloned()
+                .unwrap_or_default();
+            // On a restore, a worker whose zone has capacity for none of its
+            // types moves on to the next zone, as on AWS.
+            let started_in = node
+                .availability_zone
+                .clone()
+                .unwrap_or_else(|| cluster.availability_zone.clone());
+            let zones = if is_restore && node_index > 0 {
+                cluster.restore_zones(Some(&started_in))
+            } else {
+                vec![started_in.clone()]
+            };
+            let mut attempt = 0;
+            let instance = loop {
+                let zone = &zones[attempt];
+                match self.launch_instance(&cluster, node, node_index, &candidates, zone) {
+                    Ok(instance) => break instance,
+                    Err(e) => match zones.get(attempt + 1) {
+                        Some(next_zone) if is_capacity_error(&e.to_string()) => {
+                            tracing::warn!(
+                                "No capacity for node {} in zone '{}', trying zone '{}'",
+                                node_index,
+                                zone,
+                                next_zone
+                            );
+                            attempt += 1;
+                        }
+                        _ => return Err(e),
+                    },
+                }
+            };
+            if instance.availability_zone != started_in {
+                tracing::warn!(
+                    "Node {} now runs in zone '{}', away from the rest of the cluster in '{}'",
+                    node_index,
+                    instance.availability_zone,
+                    cluster.availability_zone
+                );
+                let moved_to = (instance.availability_zone != cluster.availability_zone)
+                    .then_some(instance.availability_zone.as_str());
+                node.set_availability_zone(pool, moved_to).await?;
+                node.availability_zone = moved_to.map(|zone| zone.to_string());
+                ClusterEvent::record(
+                    pool,
+                    &cluster.id,
+                    ClusterEventType::ZoneFallbackUsed,
+                    Some(&node.id),
+                    &format!(
+                        "Node {} moved from zone '{}' to '{}'",
+                        node_index, started_in, instance.availability_zone
+                    ),
+                    Some(serde_json::json!({
+                        "node_index": node_index,
+                        "from": started_in,
+                        "to": instance.availability_zone,
+                        "private_ip": instance.private_ip,
+                    })),
+                )
+                .await?;
+            }
+            let market_type = if node.allocation_mode == "spot" {
+                "spot"
+            } else {
+                "on-demand"
+            };
+
+            node.set_ips(
+                pool,
+                &instance.private_ip,
+                instance.public_ip.as_deref().unwrap_or_default(),
+            )
+            .await?;
+            node.set_market_type(pool, market_type).await?;
+            if instance.instance_type != node.instance_type {
+                ClusterEvent::record(
+                    pool,
+                    &cluster.id,
+                    ClusterEventType::CapacityFallbackUsed,
+                    Some(&node.id),
+                    &format!(
+                        "Node {} fell back from '{}' to '{}'",
+                        node_index, node.instance_type, instance.instance_type
+                    ),
+                    Some(serde_json::json!({
+                        "node_index": node_index,
+                        "requested": node.instance_type,
+                        "launched": instance.instance_type,
+                    })),
+                )
+                .await?;
+                node.update_instance_spec(
+                    pool,
+                    &InstanceSpec {
+                        instance_type: &instance.instance_type,
+                        ..node.instance_spec()
+                    },
+                )
+                .await?;
+            }
+            node.private_ip = Some(instance.private_ip.clone());
+            node.public_ip = instance.public_ip.clone();
+            node.instance_type = instance.instance_type.clone();
+
+            tracing::info!(
+                "Launched mock instance '{}' (type='{}') for node {}",
+                instance.id,
+                instance.instance_type,
+                node_index
+            );
+            ClusterEvent::record(
+                pool,
+                &cluster.id,
+                ClusterEventType::NodeLaunched,
+                Some(&node.id),
+                &format!(
+                    "Node {} launched as '{}' ({})",
+                    node_index, instance.instance_type, market_type
+                ),
+                Some(serde_json::json!({
+                    "node_index": node_index,
+                    "instance_id": instance.id,
+                    "instance_type": instance.instance_type,
+                    "market_type": market_type,
+                })),
+            )
+            .await?;
+            progress
+                .complete_instance(pool, &node.id, &instance.id)
+                .await?;
+            self.state().instances.push(instance);
+        }
+        // Mock instances run and accept commands as soon as they are created.
+        for phase in [
+            RecoveryPhase::InstancesRequested,
+            RecoveryPhase::InstancesRunning,
+            RecoveryPhase::SshReady,
+        ] {
+            RecoveryIncident::record_phase(pool, &cluster.id, phase).await?;
+        }
+
+        // Nodes that completed init on their current instance, during a first
+        // spawn or an attempt being resumed, are left alone.
+        for node in nodes.iter() {
+            if progress.is_completed(Some(&node.id), SpawnStepKind::Init) {
+                continue;
+            }
+            let private_ip = node.private_ip.clone().unwrap_or_default();
+            let runner = self.command_runner(&private_ip);
+            runner.run_command("chmod 600 ~/.ssh/id_rsa").await?;
+            let node_init_commands = node.get_init_commands(pool).await?;
+            if !node_init_commands.is_empty() {
+                let init_script = format!("set -e\n{}", node_init_commands.join("\n"));
+                runner.run_command(&init_script).await?;
+            }
+            progress
+                .complete(pool, Some(&node.id), SpawnStepKind::Init, None)
+                .await?;
+        }
+        RecoveryIncident::record_phase(pool, &cluster.id, RecoveryPhase::InitDone).await?;
+
+        if cluster.scheduler.as_deref() == Some("slurm") {
+            let slurm_nodes: Vec<(String, CommandRunner)> = nodes
+                .iter()
+                .map(|node| {
+                    let private_ip = node.private_ip.clone().unwrap_or_default();
+                    let runner = self.command_runner(&private_ip);
+                    (private_ip, runner)
+                })
+                .collect();
+            // The controller runs on node 0, the head holding the public IP.
+            let head_ip = nodes
+                .iter()
+                .find(|node| node.node_index == 0)
+                .and_then(|node| node.private_ip.clone())
+                .unwrap_or_default();
+            slurm::configure_slurm(&cluster.id, &head_ip, &slurm_nodes).await?;
+        }
+
+        cluster.update_state(pool, ClusterState::Running).await?;
+
+        if !is_restore && nodes.iter().any(|n| n.is_spot()) {
+            self.ensure_spot_interruption_queue(&cluster.id, &cluster.region)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn terminate_cluster(
+        &self,
+        pool: &SqlitePool,
+        cluster: Cluster,
+        nodes: Vec<Node>,
+    ) -> Result<()> {
+        cluster
+            .update_state(pool, ClusterState::Terminating)
+            .await?;
+
+        {
+            let mut state = self.state();
+            for instance in state
+                .instances
+                .iter_mut()
+                .filter(|i| i.cluster_id == cluster.id)
+            {
+                instance.running = false;
+            }
+            state.spot_queues.remove(&cluster.id);
+        }
+
+        for node in &nodes {
+            node.reset(pool).await?;
+        }
+        cluster.update_state(pool, ClusterState::Pending).await?;
+        Ok(())
+    }
+
+    async fn simulate_cluster_failure(
+        &self,
+        pool: &SqlitePool,
+        cluster: Cluster,
+        node_private_ip: &str,
+    ) -> Result<()> {
+        let node_index = self
+            .state()
+            .live_instance_by_ip(&cluster.id, node_private_ip)
+            .map(|i| i.node_index);
+        let Some(node_index) = node_index else {
+            tracing::warn!(
+                "Private IP: '{}' not found in Cluster '{}'",
+                node_private_ip,
+                cluster.display_name
+            );
+            return Ok(());
+        };
+        self.state().kill(&cluster.id, node_index);
+
+        if let Some(failed_node) =
+            Node::fetch_by_private_ip(pool, &cluster.id, node_private_ip).await?
+        {
+            failed_node.set_efs_configuration_state(pool, false).await?;
+        }
+        Ok(())
+    }
+
+    async fn check_cluster_health(
+        &self,
+        pool: &SqlitePool,
+        cluster: &Cluster,
+    ) -> Result<Vec<String>> {
+        let failed_ips: Vec<String> = {
+            let mut state = self.state();
+            state.tick += 1;
+            let tick = state.tick;
+
+            let due: Vec<usize> = state
+                .script
+                .instance_deaths
+                .iter()
+                .filter(|(at, _)| *at <= tick)
+                .map(|(_, node_index)| *node_index)
+                .collect();
+            state.script.instance_deaths.retain(|(at, _)| *at > tick);
+            for node_index in due {
+                state.kill(&cluster.id, node_index);
+            }
+
+            // A node has failed when the address it holds has no running instance
+            // behind it, which is what the replacement will restore.
+            state
+                .instances
+                .iter()
+                .filter(|i| i.cluster_id == cluster.id && !i.running)
+                .filter(|i| {
+                    state
+                        .live_instance_by_ip(&cluster.id, &i.private_ip)
+                        .is_none()
+                })
+                .map(|i| i.private_ip.clone())
+                .collect::<std::collections::BTreeSet<_>>()
+                .into_iter()
+                .collect()
+        };
+
+        let mut nodes_ips = Vec::new();
+        for node in cluster.get_nodes(pool).await? {
+            if let Some(ip) = node.private_ip {
+                nodes_ips.push(ip);
+            }
+        }
+        Ok(failed_ips
+            .into_iter()
+            .filter(|ip| nodes_ips.contains(ip))
+            .collect())
+    }
+}
@@ -0,0 +1,107 @@
+use crate::database::models::Cluster;
+use crate::integrations::{NetworkInterfaceRelease, SpotInterruptionQueue};
+
+use anyhow::Result;
+use tokio::time::{Duration, sleep};
+
+use super::interface::MockInterface;
+
+impl SpotInterruptionQueue for MockInterface {
+    async fn ensure_spot_interruption_queue(
+        &self,
+        cluster_id: &str,
+        _region: &str,
+    ) -> Result<String> {
+        self.state().spot_queues.insert(cluster_id.to_string());
+        Ok(format!("mock://{}", cluster_id))
+    }
+
+    async fn get_spot_interruption_queue_url(
+        &self,
+        cluster_id: &str,
+        _region: &str,
+    ) -> Result<Option<String>> {
+        let exists = self.state().spot_queues.contains(cluster_id);
+        Ok(exists.then(|| format!("mock://{}", cluster_id)))
+    }
+
+    /// Delivers, once, every scripted notice that is due at the current tick.
+    async fn poll_spot_interruption_queue(
+        &self,
+        cluster_id: &str,
+        _region: &str,
+        _queue_url: &str,
+    ) -> Result<Vec<String>> {
+        let mut state = self.state();
+        let tick = state.tick;
+        let due: Vec<usize> = state
+            .script
+            .spot_notices
+            .iter()
+            .filter(|(at, _)| *at <= tick)
+            .map(|(_, node_index)| *node_index)
+            .collect();
+        state.script.spot_notices.retain(|(at, _)| *at > tick);
+
+        Ok(due
+            .into_iter()
+            .filter_map(|node_index| state.live_instance(cluster_id, node_index))
+            .map(|instance| instance.private_ip.clone())
+            .collect())
+    }
+
+    async fn cleanup_spot_interruption_queue(
+        &self,
+        cluster_id: &str,
+        _region: &str,
+        _queue_url: &str,
+    ) -> Result<()> {
+        self.state().spot_queues.remove(cluster_id);
+        Ok(())
+    }
+
+    async fn send_simulated_spot_interruption(
+        &self,
+        cluster: &Cluster,
+        node_private_ip: &str,
+    ) -> Result<()> {
+        let mut state = self.state();
+        let Some(node_index) = state
+            .live_instance_by_ip(&cluster.id, node_private_ip)
+            .map(|i| i.node_index)
+        else {
+            anyhow::bail!(
+                "No running mock instance with private IP '{}'",
+                node_private_ip
+            )
+        };
+        let tick = state.tick;
+        state.script.spot_notices.push((tick, node_index));
+        Ok(())
+    }
+}
+
+impl NetworkInterfaceRelease for MockInterface {
+    async fn wait_for_enis_released(
+        &self,
+        _region: &str,
+        _private_ips: &[String],
+        max_wait: Duration,
+    ) -> bool {
+        let delay = {
+            let mut state = self.state();
+            state.eni_release_waits += 1;
+            state.script.eni_release_delay
+        };
+        sleep(delay.min(max_wait)).await;
+        delay <= max_wait
+    }
+
+    async fn delete_detached_eni_by_private_ip(
+        &self,
+        _region: &str,
+        _private_ip: &str,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
@@ -1,2 +1,4 @@
 pub mod aws;
+#[cfg(test)]
+pub mod mock;
 pub mod vultr;
@@ -1,8 +1,65 @@
-use crate::database::models::{ConfigVar, ConfigVarFinder};
+use crate::database::models::{Cluster, ConfigVar, ConfigVarFinder};
 
 use anyhow::Result;
-use reqwest::{Client as HttpClient, header};
+use reqwest::{Client as HttpClient, Method, StatusCode, header};
 use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+
+/// Context struct containing all cluster-related information and resource identifiers
+/// used throughout the cluster lifecycle operations
+pub struct VultrClusterContext {
+    // Core cluster information for tagging and filtering
+    pub cluster_id: String,
+    pub cluster_tag: String,
+    pub region: String,
+
+    // Standardized resource naming
+    pub vpc_name: String,
+    pub ssh_key_name: String,
+
+    // Resource identifiers (populated during creation/discovery)
+    pub vpc_id: Option<String>,
+    pub ssh_key_id: Option<String>,
+    pub instance_ids: HashMap<usize, String>,
+
+    // Cluster and network configuration
+    pub public_ssh_key_path: String,
+    pub vpc_ip_block: String,
+    pub vpc_prefix_length: i64,
+}
+
+impl VultrClusterContext {
+    pub fn new(cluster: &Cluster) -> Self {
+        let cluster_id = cluster.id.to_string();
+        Self {
+            cluster_id: cluster_id.clone(),
+            // Vultr tags are plain strings, so the key is folded into the value
+            cluster_tag: format!("ClusterId:{}", cluster_id),
+            region: cluster.region.clone(),
+
+            vpc_name: format!("{}-VPC", cluster_id),
+            ssh_key_name: format!("{}-KEY", cluster_id),
+
+            vpc_id: None,
+            ssh_key_id: None,
+            instance_ids: HashMap::new(),
+
+            public_ssh_key_path: cluster.public_ssh_key_path.clone(),
+            vpc_ip_block: "10.0.0.0".to_string(),
+            vpc_prefix_length: 24,
+        }
+    }
+
+    /// Generate an instance label for a specific node index
+    pub fn instance_label(&self, node_index: usize) -> String {
+        format!("{}-INSTANCE-{}", self.cluster_id, node_index)
+    }
+
+    /// Generate a startup script name for a specific node index
+    pub fn startup_script_name(&self, node_index: usize) -> String {
+        format!("{}-INIT-{}", self.cluster_id, node_index)
+    }
+}
 
 pub struct VultrInterface {
     pub config_vars: Vec<ConfigVar>,
@@ -41,10 +98,37 @@ impl VultrInterface {
 
     // Helper function to make API requests and parse JSON response
     pub async fn make_api_request(&self, endpoint: &str) -> Result<JsonValue> {
+        self.send_api_request(Method::GET, endpoint, None).await
+    }
+
+    pub async fn make_api_post_request(
+        &self,
+        endpoint: &str,
+        body: &JsonValue,
+    ) -> Result<JsonValue> {
+        self.send_api_request(Method::POST, endpoint, Some(body))
+            .await
+    }
+
+    pub async fn make_api_delete_request(&self, endpoint: &str) -> Result<JsonValue> {
+        self.send_api_request(Method::DELETE, endpoint, None).await
+    }
+
+    async fn send_api_request(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Option<&JsonValue>,
+    ) -> Result<JsonValue> {
         let client = self.get_http_client()?;
         let url = format!("{}{}", Self::API_BASE_URL, endpoint);
 
-        let response = match client.get(&url).send().await {
+        let mut request = client.request(method.clone(), &url);
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+
+        let response = match request.send().await {
             Ok(result) => result,
             Err(e) => {
                 tracing::error!("{:?}", e);
@@ -54,6 +138,8 @@ impl VultrInterface {
 
         let response_status = response.status();
         let json_response = match response_status.is_success() {
+            // DELETE (and a few other mutations) answer 204 with an empty body.
+            true if response_status == StatusCode::NO_CONTENT => JsonValue::Null,
             true => match response.json().await {
                 Ok(result) => result,
                 Err(e) => {
@@ -70,14 +156,27 @@ impl VultrInterface {
                     }
                 };
                 tracing::error!(
-                    "Vultr API returned error status {}: {}",
+                    "Vultr API returned error status {} for {} {}: {}",
                     response_status,
+                    method,
+                    endpoint,
                     body
                 );
-                anyhow::bail!("Vultr API returned an error")
+                // The status goes into the error so callers can tell a missing
+                // resource (404) apart from a real failure.
+                anyhow::bail!(
+                    "Vultr API returned an error ({}): {}",
+                    response_status,
+                    body
+                )
             }
         };
 
         Ok(json_response)
     }
+
+    /// Create a ClusterContext for the given cluster.
+    pub fn create_cluster_context(&self, cluster: &Cluster) -> VultrClusterContext {
+        VultrClusterContext::new(cluster)
+    }
 }
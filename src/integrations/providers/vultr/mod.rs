@@ -1,5 +1,6 @@
 mod interface;
 mod resource_catalog;
 mod resource_manager;
+mod resources;
 
 pub use interface::VultrInterface;
@@ -256,18 +256,92 @@ impl CloudInfoProvider for VultrInterface {
 
     async fn fetch_prices(
         &self,
-        _region: &str,
-        _instance_types: &[String],
-        _tracker: &ProgressTracker,
+        region: &str,
+        instance_types: &[String],
+        tracker: &ProgressTracker,
     ) -> Result<HashMap<String, f64>> {
-        // Vultr does not need a separate fetch prices method, as the pricing info
-        // is returned in the instance_types api calls already.
-        let map: HashMap<String, f64> = HashMap::new();
+        // Vultr publishes the hourly cost on the plan itself, so pricing is just a
+        // filtered read of the same listings fetch_instance_types walks.
+        let mut price_map: HashMap<String, f64> = HashMap::new();
+        for (endpoint, key) in [
+            ("/plans?per_page=500", "plans"),
+            ("/plans-metal?per_page=500", "plans_metal"),
+        ] {
+            let json_response = self.make_api_request(endpoint).await?;
+            let plans = match json_response[key].as_array() {
+                Some(plans) => plans,
+                None => {
+                    tracing::error!("{:?}", json_response);
+                    anyhow::bail!("Missing '{}' array from Vultr API response", key)
+                }
+            };
+
+            for plan in plans {
+                let Some(name) = plan["id"].as_str() else {
+                    continue;
+                };
+                if !instance_types.iter().any(|t| t == name) {
+                    continue;
+                }
+                let available_in_region = plan["locations"]
+                    .as_array()
+                    .is_some_and(|locations| locations.iter().any(|l| l.as_str() == Some(region)));
+                if !available_in_region {
+                    tracing::warn!("Plan '{}' is not available in region '{}'", name, region);
+                    continue;
+                }
+                if let Some(price) = plan["hourly_cost"].as_f64() {
+                    price_map.insert(name.to_string(), price);
+                    tracker.inc(1);
+                }
+            }
+        }
 
-        Ok(map)
+        Ok(price_map)
     }
 
-    async fn fetch_machine_image(&self, _region: &str, _image_id: &str) -> Result<MachineImage> {
-        anyhow::bail!("Not implemented")
+    async fn fetch_machine_image(&self, region: &str, image_id: &str) -> Result<MachineImage> {
+        // Vultr images are operating systems identified by a numeric id, shared
+        // across every region.
+        let os_id: i64 = match image_id.parse() {
+            Ok(os_id) => os_id,
+            Err(_) => anyhow::bail!(
+                "Invalid Vultr image_id '{}': expected a numeric OS id (see GET /v2/os)",
+                image_id
+            ),
+        };
+
+        let json_response = self.make_api_request("/os?per_page=500").await?;
+        let operating_systems = match json_response["os"].as_array() {
+            Some(operating_systems) => operating_systems,
+            None => {
+                tracing::error!("{:?}", json_response);
+                anyhow::bail!("Missing 'os' array from Vultr API response");
+            }
+        };
+
+        let os = match operating_systems
+            .iter()
+            .find(|os| os["id"].as_i64() == Some(os_id))
+        {
+            Some(os) => os,
+            None => anyhow::bail!("Machine Image (id='{}') not found in Vultr", image_id),
+        };
+
+        let now = chrono::Utc::now().naive_utc();
+        let image = MachineImage {
+            id: image_id.to_string(),
+            name: os["name"].as_str().unwrap_or_default().to_string(),
+            description: os["family"].as_str().unwrap_or_default().to_string(),
+            owner: "vultr".to_string(),
+            // Vultr does not publish a creation date for its OS images
+            creation_date: String::new(),
+            provider: "vultr".to_string(),
+            region: region.to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+
+        Ok(image)
     }
 }
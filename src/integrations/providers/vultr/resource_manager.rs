@@ -1,44 +1,548 @@
-use crate::database::models::{Cluster, Node};
+use crate::database::models::{
+    Cluster, ClusterEvent, ClusterEventType, ClusterState, Node, RecoveryIncident, RecoveryPhase,
+    SpawnProgress, SpawnStepKind, slot_order,
+};
 use crate::integrations::CloudResourceManager;
+use crate::utils;
+use crate::utils::command_runner::CommandRunner;
+use crate::utils::slurm;
+use crate::utils::ssh::SshSession;
 
 use anyhow::Result;
 use sqlx::sqlite::SqlitePool;
+use tokio::time::{Duration, Instant, sleep};
 
 use super::interface::VultrInterface;
+use super::resources::{
+    instance::is_unhealthy,
+    startup_script::{INIT_EXIT_CODE_PATH, INIT_LOG_PATH},
+};
 
 impl CloudResourceManager for VultrInterface {
     async fn spawn_cluster(
         &self,
-        _pool: &SqlitePool,
-        _cluster: Cluster,
-        _nodes: Vec<Node>,
+        pool: &SqlitePool,
+        cluster: Cluster,
+        mut nodes: Vec<Node>,
     ) -> Result<()> {
-        anyhow::bail!("Not implemented")
+        // Vultr assigns private IPs by DHCP, so no address marks the head: it
+        // is the first node in slot order, and every list below follows it.
+        slot_order(&mut nodes);
+        let mut context = self.create_cluster_context(&cluster);
+        let mut steps = 3 + (6 * nodes.len());
+        if cluster.scheduler.is_some() {
+            steps += 1;
+        }
+
+        let spawning_message = format!("Spawning Cluster '{}'...", cluster.display_name);
+        tracing::info!(spawning_message);
+
+        let is_restore = cluster.state == ClusterState::Running;
+        let new_state = if is_restore {
+            steps += 1;
+            ClusterState::Restoring
+        } else {
+            ClusterState::Spawning
+        };
+        cluster.update_state(pool, new_state).await?;
+
+        let (multi, _guard) = utils::ProgressTracker::create_multi();
+        let main_progress =
+            utils::ProgressTracker::add_to_multi(&multi, steps as u64, Some(&spawning_message));
+        let operation_spinner =
+            utils::ProgressTracker::new_indeterminate(&multi, "Initializing...");
+
+        // Steps an earlier, interrupted attempt already completed (resume only).
+        let mut progress = SpawnProgress::load(pool, &cluster.id).await?;
+
+        /*
+         * VULTR CLUSTER CLOUD RESOURCE CREATION CYCLE
+         *
+         * A resumed spawn reuses the VPC and SSH key it recorded, and skips the
+         * setup and init checks of nodes still on the instance they were done on.
+         *
+         * 1. (restore only) Delete stopped or suspended instances
+         * 2. Create VPC 2.0 network
+         * 3. Import SSH Key
+         * 4. for each node {
+         *     4.1. (conditional) Upload the init commands as a boot Startup Script
+         *     4.2. Request instance creation
+         * }
+         * 5. Wait for all instances to be active
+         * 6. Record private (VPC 2.0) and public IPs of every node
+         * 7. Wait for SSH to be ready on all instances
+         * 8. Run the base setup on every node via SSH
+         * 9. Wait for the Startup Scripts to finish and check their exit status
+         * 10. (conditional) Install and configure Slurm on every node
+         */
+
+        // 1. Delete failed instances so their replacements can take their labels
+        if is_restore {
+            operation_spinner.update_message("Deleting failed instances...");
+            let deleted = self.delete_unhealthy_instances(&context).await?;
+            tracing::info!("Deleted {} failed instance(s) before restore", deleted);
+            main_progress.inc(1);
+        }
+
+        // 2. Create VPC 2.0 network
+        operation_spinner.update_message("Creating VPC 2.0 network...");
+        context.vpc_id = Some(
+            progress
+                .resume_or(pool, None, SpawnStepKind::Vpc, self.ensure_vpc(&context))
+                .await?,
+        );
+        main_progress.inc(1);
+
+        // 3. Import SSH Key
+        operation_spinner.update_message("Importing the SSH key...");
+        context.ssh_key_id = Some(
+            progress
+                .resume_or(
+                    pool,
+                    None,
+                    SpawnStepKind::SshKey,
+                    self.ensure_ssh_key(&context),
+                )
+                .await?,
+        );
+        main_progress.inc(1);
+
+        // 4. Request instances, reusing those that survived (restore) or that an
+        // earlier, interrupted spawn already created.
+        let node_count = nodes.len();
+        for (position, node) in nodes.iter().enumerate() {
+            let node_index = node.node_index as usize;
+            operation_spinner.update_message(&format!(
+                "Requesting {} of {} Instances (plan='{}')",
+                position + 1,
+                node_count,
+                node.instance_type
+            ));
+            if let Some(instance_id) = self.find_live_instance(&context, node_index).await? {
+                progress
+                    .complete_instance(pool, &node.id, &instance_id)
+                    .await?;
+                context.instance_ids.insert(node_index, instance_id);
+                main_progress.inc(2);
+                continue;
+            }
+
+            // 4.1. Upload init commands as a boot script
+            let node_init_commands = node.get_init_commands(pool).await?;
+            let script_id = if node_init_commands.is_empty() {
+                None
+            } else {
+                Some(
+                    self.replace_startup_script(&context, node_index, &node_init_commands)
+                        .await?,
+                )
+            };
+            main_progress.inc(1);
+
+            // 4.2. Request instance creation
+            let instance_id = self
+                .request_instance_creation(&context, node, node_index, script_id.as_deref())
+                .await?;
+            ClusterEvent::record(
+                pool,
+                &cluster.id,
+                ClusterEventType::NodeLaunched,
+                Some(&node.id),
+                &format!("Node {} launched as '{}'", node_index, node.instance_type),
+                Some(serde_json::json!({
+                    "node_index": node_index,
+                    "instance_id": instance_id,
+                    "instance_type": node.instance_type,
+                })),
+            )
+            .await?;
+            progress
+                .complete_instance(pool, &node.id, &instance_id)
+                .await?;
+            context.instance_ids.insert(node_index, instance_id);
+            main_progress.inc(1);
+        }
+        RecoveryIncident::record_phase(pool, &cluster.id, RecoveryPhase::InstancesRequested)
+            .await?;
+
+        // 5. Wait for all instances to be active
+        operation_spinner.update_message("Waiting for all Instances to be active...");
+        sleep(Duration::from_secs(5)).await;
+        self.wait_for_all_instances_to_be_active(&context).await?;
+        RecoveryIncident::record_phase(pool, &cluster.id, RecoveryPhase::InstancesRunning).await?;
+        main_progress.inc(1);
+
+        // 6. Record node IPs
+        let mut private_ips: Vec<String> = Vec::with_capacity(node_count);
+        let mut public_ips: Vec<String> = Vec::with_capacity(node_count);
+        for (position, node) in nodes.iter().enumerate() {
+            operation_spinner.update_message(&format!(
+                "Fetching addresses of Node {} of {}...",
+                position + 1,
+                node_count
+            ));
+            let instance_id = &context.instance_ids[&(node.node_index as usize)];
+            let (private_ip, public_ip) =
+                self.get_instance_addresses(&context, instance_id).await?;
+            node.set_ips(pool, &private_ip, &public_ip).await?;
+            private_ips.push(private_ip);
+            public_ips.push(public_ip);
+            main_progress.inc(1);
+        }
+
+        let private_key_content =
+            std::fs::read_to_string(&cluster.private_ssh_key_path).map_err(|e| {
+                anyhow::anyhow!(
+                    "Failed to read private SSH key '{}': {}",
+                    cluster.private_ssh_key_path,
+                    e
+                )
+            })?;
+
+        // 7. Wait for SSH to be ready on all instances. Every Vultr instance has
+        // a public IP, so no node needs the head as a jump host.
+        // Nodes whose Ssh step completed were reached and set up on this very
+        // instance before, so steps 7 and 8 skip them.
+        let ssh_done: Vec<bool> = nodes
+            .iter()
+            .map(|node| progress.is_completed(Some(&node.id), SpawnStepKind::Ssh))
+            .collect();
+        for (node_index, public_ip) in public_ips.iter().enumerate() {
+            if ssh_done[node_index] {
+                main_progress.inc(1);
+                continue;
+            }
+            operation_spinner.update_message(&format!(
+                "Waiting for SSH on Node {} of {} ({})...",
+                node_index + 1,
+                node_count,
+                public_ip
+            ));
+            let ssh = SshSession::for_vultr(public_ip, &cluster.private_ssh_key_path);
+            ssh.wait_until_ready(Duration::from_secs(300)).await?;
+            main_progress.inc(1);
+        }
+        RecoveryIncident::record_phase(pool, &cluster.id, RecoveryPhase::SshReady).await?;
+
+        // 8. Base setup
+        for (node_index, public_ip) in public_ips.iter().enumerate() {
+            if ssh_done[node_index] {
+                main_progress.inc(1);
+                continue;
+            }
+            operation_spinner.update_message(&format!(
+                "Running base setup on Node {} of {}...",
+                node_index + 1,
+                node_count
+            ));
+            let ssh = SshSession::for_vultr(public_ip, &cluster.private_ssh_key_path);
+
+            // Install the private key so nodes can SSH to each other (needed for MPI)
+            ssh.run_command("mkdir -p ~/.ssh && chmod 700 ~/.ssh")
+                .await?;
+            ssh.upload_file("$HOME/.ssh/id_rsa", &private_key_content)
+                .await?;
+            ssh.run_command("chmod 600 ~/.ssh/id_rsa").await?;
+
+            // Vultr's Ubuntu and Debian images ship with ufw enabled and only SSH
+            // allowed, which would block MPI between nodes on the private network.
+            ssh.run_command(&format!(
+                "! command -v ufw &>/dev/null || ufw allow from {}/{}",
+                context.vpc_ip_block, context.vpc_prefix_length
+            ))
+            .await?;
+
+            // Ensure tmux is available (required for cluster tasks). On a fresh
+            // instance apt may still be held by the first-boot upgrade, so wait
+            // for the lock instead of failing.
+            ssh.run_command(
+                "command -v tmux &>/dev/null || { command -v apt-get &>/dev/null \
+                && apt-get -o DPkg::Lock::Timeout=600 install -y tmux || dnf install -y tmux; }",
+            )
+            .await?;
+            progress
+                .complete(pool, Some(&nodes[node_index].id), SpawnStepKind::Ssh, None)
+                .await?;
+            main_progress.inc(1);
+        }
+
+        // 9. Wait for the boot scripts. Vultr runs them unattended, so unlike on
+        // AWS a failing init command does not surface on its own: read back the
+        // exit status the script records, and fail the spawn with its log tail.
+        // Reused instances finished theirs on an earlier spawn and still have
+        // the status file, so checking them as well costs one round trip.
+        for (node_index, node) in nodes.iter().enumerate() {
+            operation_spinner.update_message(&format!(
+                "Waiting for init commands on Node {} of {}...",
+                node_index + 1,
+                node_count
+            ));
+            if progress.is_completed(Some(&node.id), SpawnStepKind::Init) {
+                main_progress.inc(1);
+                continue;
+            }
+            if node.get_init_commands(pool).await?.is_empty() {
+                progress
+                    .complete(pool, Some(&node.id), SpawnStepKind::Init, None)
+                    .await?;
+                main_progress.inc(1);
+                continue;
+            }
+
+            let ssh = SshSession::for_vultr(&public_ips[node_index], &cluster.private_ssh_key_path);
+            let exit_code = wait_for_init_exit_code(&ssh, Duration::from_secs(1800)).await?;
+            if exit_code != 0 {
+                let log_tail = ssh
+                    .run_command(&format!("tail -n 20 {}", INIT_LOG_PATH))
+                    .await
+                    .unwrap_or_default();
+                tracing::error!(
+                    "Init commands failed on Node {} of {} (exit code {}):\n{}",
+                    node_index + 1,
+                    node_count,
+                    exit_code,
+                    log_tail
+                );
+                anyhow::bail!(
+                    "Init commands failed on Node {} of {} with exit code {} (see {} on the node)",
+                    node_index + 1,
+                    node_count,
+                    exit_code,
+                    INIT_LOG_PATH
+                );
+            }
+            tracing::info!(
+                "Init commands completed on Node {} of {}",
+                node_index + 1,
+                node_count
+            );
+            progress
+                .complete(pool, Some(&node.id), SpawnStepKind::Init, None)
+                .await?;
+            main_progress.inc(1);
+        }
+        RecoveryIncident::record_phase(pool, &cluster.id, RecoveryPhase::InitDone).await?;
+
+        // 10. (conditional) Install and configure Slurm. A restore goes through
+        // here too, which rewrites the node list so the replacement nodes join.
+        if cluster.scheduler.as_deref() == Some("slurm") {
+            operation_spinner.update_message("Configuring Slurm...");
+            let slurm_nodes: Vec<(String, CommandRunner)> = private_ips
+                .iter()
+                .zip(&public_ips)
+                .map(|(private_ip, public_ip)| {
+                    let ssh = SshSession::for_vultr(public_ip, &cluster.private_ssh_key_path);
+                    (private_ip.clone(), CommandRunner::Ssh(ssh))
+                })
+                .collect();
+            let head_ip = private_ips.first().cloned().unwrap_or_default();
+            slurm::configure_slurm(&cluster.id, &head_ip, &slurm_nodes).await?;
+            main_progress.inc(1);
+        }
+
+        cluster.update_state(pool, ClusterState::Running).await?;
+
+        operation_spinner.finish_with_message("All Cloud operations completed");
+        main_progress.finish_with_message(&format!(
+            "Cluster '{}' spawned successfully!",
+            cluster.display_name
+        ));
+        tracing::info!(
+            "Cluster spawn completed successfully. Head node: ssh root@{}",
+            public_ips.first().cloned().unwrap_or_default()
+        );
+
+        Ok(())
     }
 
     async fn terminate_cluster(
         &self,
-        _pool: &SqlitePool,
-        _cluster: Cluster,
-        _nodes: Vec<Node>,
+        pool: &SqlitePool,
+        cluster: Cluster,
+        nodes: Vec<Node>,
     ) -> Result<()> {
-        anyhow::bail!("Not implemented")
+        let context = self.create_cluster_context(&cluster);
+        let steps = 5 + 2 * nodes.len();
+
+        let terminating_message = format!("Terminating Cluster '{}'...", cluster.display_name);
+        tracing::info!(terminating_message);
+
+        cluster
+            .update_state(pool, ClusterState::Terminating)
+            .await?;
+
+        let (multi, _guard) = utils::ProgressTracker::create_multi();
+        let main_progress =
+            utils::ProgressTracker::add_to_multi(&multi, steps as u64, Some(&terminating_message));
+        let operation_spinner =
+            utils::ProgressTracker::new_indeterminate(&multi, "Initializing...");
+
+        /*
+         * VULTR CLUSTER CLOUD RESOURCE DESTRUCTION CYCLE
+         *
+         * 1. Request deletion of all instances
+         * 2. Wait for all instances to be deleted
+         * 3. Delete Startup Scripts
+         * 4. Delete SSH Key
+         * 5. Delete VPC 2.0 network
+         */
+
+        // 1. Request deletion of all instances
+        operation_spinner.update_message(&format!(
+            "Requesting deletion of {} Instances...",
+            nodes.len()
+        ));
+        self.request_deletion_of_all_instances(&context).await?;
+        main_progress.inc(nodes.len() as u64);
+
+        // 2. Wait for all instances to be deleted
+        operation_spinner.update_message(&format!(
+            "Waiting for {} Instances to be deleted...",
+            nodes.len()
+        ));
+        self.wait_for_all_instances_to_be_deleted(&context).await?;
+        main_progress.inc(nodes.len() as u64);
+
+        // 3. Delete Startup Scripts
+        operation_spinner.update_message("Deleting Startup Scripts...");
+        self.cleanup_startup_scripts(&context).await?;
+        main_progress.inc(1);
+
+        // 4. Delete SSH Key
+        operation_spinner.update_message("Deleting the SSH key...");
+        self.cleanup_ssh_key(&context).await?;
+        main_progress.inc(1);
+
+        // 5. Delete VPC 2.0 network
+        operation_spinner.update_message("Destroying VPC 2.0 network...");
+        self.cleanup_vpc(&context).await?;
+        main_progress.inc(1);
+
+        for node in &nodes {
+            node.reset(pool).await?;
+        }
+        cluster.update_state(pool, ClusterState::Pending).await?;
+        main_progress.inc(2);
+
+        operation_spinner.finish_with_message("All Cloud operations completed");
+        main_progress.finish_with_message(&format!(
+            "Cluster '{}' terminated successfully!",
+            cluster.display_name
+        ));
+        tracing::info!(
+            "Cluster '{}' is ready to spawn again.",
+            cluster.display_name
+        );
+        Ok(())
     }
 
     async fn simulate_cluster_failure(
         &self,
-        _pool: &SqlitePool,
-        _cluster: Cluster,
-        _node_private_ip: &str,
+        pool: &SqlitePool,
+        cluster: Cluster,
+        node_private_ip: &str,
     ) -> Result<()> {
-        anyhow::bail!("Not implemented")
+        let context = self.create_cluster_context(&cluster);
+
+        // Instance listings only carry the public address, so go through the
+        // node record to match the private IP to an instance.
+        let failed_node =
+            match Node::fetch_by_private_ip(pool, &cluster.id, node_private_ip).await? {
+                Some(node) => node,
+                None => {
+                    tracing::warn!(
+                        "Private IP: '{}' not found in Cluster '{}'",
+                        node_private_ip,
+                        cluster.display_name
+                    );
+                    return Ok(());
+                }
+            };
+
+        let instances = self.list_cluster_instances(&context).await?;
+        let instance_id = instances
+            .iter()
+            .find(|instance| {
+                failed_node.public_ip.is_some()
+                    && instance["main_ip"].as_str() == failed_node.public_ip.as_deref()
+            })
+            .and_then(|instance| instance["id"].as_str());
+        match instance_id {
+            Some(id) => {
+                tracing::info!("Deleting instance with IP: '{}'", node_private_ip);
+                self.delete_instance(id).await?;
+                tracing::info!(
+                    "Requested deletion for Instance '{}' (failure simulation)",
+                    failed_node.id
+                );
+            }
+            None => {
+                tracing::warn!(
+                    "No live instance found for Node (private_ip='{}') in Cluster '{}'",
+                    node_private_ip,
+                    cluster.display_name
+                );
+            }
+        }
+
+        Ok(())
     }
 
     async fn check_cluster_health(
         &self,
-        _pool: &SqlitePool,
-        _cluster: &Cluster,
+        pool: &SqlitePool,
+        cluster: &Cluster,
     ) -> Result<Vec<String>> {
-        anyhow::bail!("Not implemented")
+        let context = self.create_cluster_context(cluster);
+        let instances = self.list_cluster_instances(&context).await?;
+
+        // A deleted Vultr instance vanishes from the listing rather than lingering
+        // in a terminated state, so a node is also failed when its instance is
+        // simply gone.
+        let mut failed_ips = Vec::new();
+        for node in cluster.get_nodes(pool).await? {
+            let (Some(private_ip), Some(public_ip)) = (&node.private_ip, &node.public_ip) else {
+                continue;
+            };
+            let instance = instances
+                .iter()
+                .find(|instance| instance["main_ip"].as_str() == Some(public_ip.as_str()));
+            let failed = match instance {
+                Some(instance) => is_unhealthy(instance),
+                None => true,
+            };
+            if failed {
+                tracing::info!(
+                    "Instance (private_ip='{}') is missing or in a failed state",
+                    private_ip
+                );
+                failed_ips.push(private_ip.clone());
+            }
+        }
+
+        Ok(failed_ips)
+    }
+}
+
+/// Polls the exit status the boot script writes on completion.
+async fn wait_for_init_exit_code(ssh: &SshSession, timeout: Duration) -> Result<i32> {
+    let start = Instant::now();
+    let probe = format!(
+        "if [ -f {path} ]; then cat {path}; else echo pending; fi",
+        path = INIT_EXIT_CODE_PATH
+    );
+    loop {
+        let output = ssh.run_command(&probe).await?;
+        if let Ok(exit_code) = output.trim().parse::<i32>() {
+            return Ok(exit_code);
+        }
+        if start.elapsed() >= timeout {
+            anyhow::bail!(
+                "Timeout waiting for init commands on '{}' after {} seconds",
+                ssh.ip,
+                timeout.as_secs()
+            );
+        }
+        sleep(Duration::from_secs(10)).await;
     }
 }
@@ -0,0 +1,370 @@
+use crate::database::models::Node;
+use crate::integrations::providers::vultr::{VultrInterface, interface::VultrClusterContext};
+
+use anyhow::Result;
+use serde_json::{Value as JsonValue, json};
+use tokio::time::{Duration, sleep};
+
+impl VultrInterface {
+    /// Every instance tagged with this cluster, in any state.
+    pub async fn list_cluster_instances(
+        &self,
+        context: &VultrClusterContext,
+    ) -> Result<Vec<JsonValue>> {
+        let json_response = self
+            .make_api_request(&format!(
+                "/instances?tag={}&per_page=500",
+                context.cluster_tag
+            ))
+            .await?;
+        let instances = match json_response["instances"].as_array() {
+            Some(instances) => instances,
+            None => {
+                tracing::error!("{:?}", json_response);
+                anyhow::bail!("Missing 'instances' array from Vultr API response");
+            }
+        };
+
+        // Filter again client-side: the tag query is a convenience, and a listing
+        // that ignored it would otherwise hand back every instance on the account.
+        let cluster_instances = instances
+            .iter()
+            .filter(|instance| {
+                instance["tags"].as_array().is_some_and(|tags| {
+                    tags.iter()
+                        .any(|t| t.as_str() == Some(&context.cluster_tag))
+                })
+            })
+            .cloned()
+            .collect();
+
+        Ok(cluster_instances)
+    }
+
+    /// Returns the id of the node's instance if one is already up, so a retried
+    /// spawn or a restore only launches what is actually missing.
+    pub async fn find_live_instance(
+        &self,
+        context: &VultrClusterContext,
+        node_index: usize,
+    ) -> Result<Option<String>> {
+        let label = context.instance_label(node_index);
+        let instances = self.list_cluster_instances(context).await?;
+        let Some(instance) = instances
+            .iter()
+            .find(|instance| instance["label"].as_str() == Some(label.as_str()))
+        else {
+            tracing::info!("Instance '{}' not found, requesting a new one...", label);
+            return Ok(None);
+        };
+
+        let instance_id = instance["id"].as_str().unwrap_or_default().to_string();
+        let status = instance["status"].as_str().unwrap_or("unknown");
+        let power_status = instance["power_status"].as_str().unwrap_or("unknown");
+        match (status, power_status) {
+            ("active", "running") | ("pending", _) => {
+                tracing::info!(
+                    "Found existing Instance '{}' (status: {}, power: {}), skipping creation",
+                    instance_id,
+                    status,
+                    power_status
+                );
+                Ok(Some(instance_id))
+            }
+            _ => {
+                tracing::warn!(
+                    "Found existing Instance '{}' in unexpected state (status: {}, power: {})",
+                    instance_id,
+                    status,
+                    power_status
+                );
+                anyhow::bail!(
+                    "Found existing Instance '{}' in unexpected state (status: '{}', power: '{}'). Please check the Vultr web panel.",
+                    instance_id,
+                    status,
+                    power_status
+                )
+            }
+        }
+    }
+
+    /// Vultr has no separate instance type: the plan id stored as the node's
+    /// `instance_type` is the size, and `image_id` is an operating system id.
+    pub async fn request_instance_creation(
+        &self,
+        context: &VultrClusterContext,
+        node: &Node,
+        node_index: usize,
+        startup_script_id: Option<&str>,
+    ) -> Result<String> {
+        let label = context.instance_label(node_index);
+        let os_id: i64 = match node.image_id.parse() {
+            Ok(os_id) => os_id,
+            Err(_) => anyhow::bail!(
+                "Invalid Vultr image_id '{}' for node {}: expected a numeric OS id",
+                node.image_id,
+                node_index
+            ),
+        };
+
+        let mut body = json!({
+            "region": context.region,
+            "plan": node.instance_type,
+            "os_id": os_id,
+            "label": label,
+            "hostname": label.to_lowercase(),
+            "sshkey_id": [context.ssh_key_id.clone().unwrap()],
+            "attach_vpc2": [context.vpc_id.clone().unwrap()],
+            "tags": [context.cluster_tag],
+            "backups": "disabled",
+        });
+        if let Some(script_id) = startup_script_id {
+            body["script_id"] = json!(script_id);
+        }
+
+        let json_response = self.make_api_post_request("/instances", &body).await?;
+        match json_response["instance"]["id"].as_str() {
+            Some(instance_id) => {
+                tracing::info!(
+                    "Requested new instance '{}' (plan='{}') with ID '{}'",
+                    label,
+                    node.instance_type,
+                    instance_id
+                );
+                Ok(instance_id.to_string())
+            }
+            None => {
+                tracing::warn!("{:?}", json_response);
+                anyhow::bail!("Failure finding the id of the requested Instance");
+            }
+        }
+    }
+
+    pub async fn wait_for_all_instances_to_be_active(
+        &self,
+        context: &VultrClusterContext,
+    ) -> Result<()> {
+        let max_wait_time = Duration::from_secs(900);
+        let poll_interval = Duration::from_secs(15);
+        let start_time = std::time::Instant::now();
+
+        loop {
+            if start_time.elapsed() >= max_wait_time {
+                anyhow::bail!(
+                    "Timeout waiting for Vultr instances to become active after {} seconds",
+                    max_wait_time.as_secs()
+                );
+            }
+
+            let mut not_ready: Vec<String> = Vec::new();
+            for instance_id in context.instance_ids.values() {
+                let json_response = self
+                    .make_api_request(&format!("/instances/{}", instance_id))
+                    .await?;
+                let instance = &json_response["instance"];
+                let status = instance["status"].as_str().unwrap_or("unknown");
+                let power_status = instance["power_status"].as_str().unwrap_or("unknown");
+                let server_status = instance["server_status"].as_str().unwrap_or("unknown");
+                // main_ip stays 0.0.0.0 until the address is assigned
+                let has_ip = instance["main_ip"]
+                    .as_str()
+                    .is_some_and(|ip| !ip.is_empty() && ip != "0.0.0.0");
+
+                if status == "active"
+                    && power_status == "running"
+                    && server_status == "ok"
+                    && has_ip
+                {
+                    continue;
+                }
+                tracing::info!(
+                    "Instance '{}' not yet ready (status: {}, power: {}, server: {})",
+                    instance_id,
+                    status,
+                    power_status,
+                    server_status
+                );
+                not_ready.push(instance_id.clone());
+            }
+
+            if not_ready.is_empty() {
+                tracing::info!(
+                    "All {} instance(s) are active and ready",
+                    context.instance_ids.len()
+                );
+                return Ok(());
+            }
+
+            tracing::info!(
+                "Waiting for {} instance(s) to become active...",
+                not_ready.len()
+            );
+            sleep(poll_interval).await;
+        }
+    }
+
+    /// Returns the (private, public) addresses of an instance. The private one is
+    /// its address on the cluster's VPC 2.0 network, which is what the nodes use
+    /// to reach each other.
+    pub async fn get_instance_addresses(
+        &self,
+        context: &VultrClusterContext,
+        instance_id: &str,
+    ) -> Result<(String, String)> {
+        let json_response = self
+            .make_api_request(&format!("/instances/{}", instance_id))
+            .await?;
+        let public_ip = match json_response["instance"]["main_ip"].as_str() {
+            Some(ip) => ip.to_string(),
+            None => anyhow::bail!("Instance '{}' has no public address", instance_id),
+        };
+
+        let json_response = self
+            .make_api_request(&format!("/instances/{}/vpc2", instance_id))
+            .await?;
+        let private_ip = json_response["vpcs"]
+            .as_array()
+            .and_then(|vpcs| {
+                vpcs.iter()
+                    .find(|vpc| vpc["id"].as_str() == context.vpc_id.as_deref())
+            })
+            .and_then(|vpc| vpc["ip_address"].as_str());
+        match private_ip {
+            Some(private_ip) => Ok((private_ip.to_string(), public_ip)),
+            None => {
+                tracing::warn!("{:?}", json_response);
+                anyhow::bail!(
+                    "Instance '{}' is not attached to the cluster VPC 2.0 network",
+                    instance_id
+                );
+            }
+        }
+    }
+
+    pub async fn delete_instance(&self, instance_id: &str) -> Result<()> {
+        tracing::info!("Requesting deletion of instance '{}'", instance_id);
+        match self
+            .make_api_delete_request(&format!("/instances/{}", instance_id))
+            .await
+        {
+            Ok(_) => {
+                tracing::info!("Instance '{}' deletion initiated", instance_id);
+                Ok(())
+            }
+            Err(e) => {
+                tracing::error!("{:?}", e);
+                anyhow::bail!("Failure deleting instance '{}': {}", instance_id, e);
+            }
+        }
+    }
+
+    pub async fn request_deletion_of_all_instances(
+        &self,
+        context: &VultrClusterContext,
+    ) -> Result<()> {
+        let instances = self.list_cluster_instances(context).await?;
+        if instances.is_empty() {
+            tracing::info!("No instances found for cluster '{}'", context.cluster_id);
+            return Ok(());
+        }
+
+        tracing::info!(
+            "Requesting deletion of {} instance(s) in cluster '{}'...",
+            instances.len(),
+            context.cluster_id
+        );
+        for instance in &instances {
+            if let Some(instance_id) = instance["id"].as_str() {
+                self.delete_instance(instance_id).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Deletes the cluster instances that are stopped or suspended. A failed
+    /// instance on Vultr lingers in that state instead of disappearing, and its
+    /// label would otherwise block the replacement from being created.
+    pub async fn delete_unhealthy_instances(&self, context: &VultrClusterContext) -> Result<usize> {
+        let instances = self.list_cluster_instances(context).await?;
+        let unhealthy: Vec<&str> = instances
+            .iter()
+            .filter(|instance| is_unhealthy(instance))
+            .filter_map(|instance| instance["id"].as_str())
+            .collect();
+
+        for instance_id in &unhealthy {
+            self.delete_instance(instance_id).await?;
+        }
+
+        let max_wait_time = Duration::from_secs(600);
+        let start_time = std::time::Instant::now();
+        while !unhealthy.is_empty() {
+            let remaining = self
+                .list_cluster_instances(context)
+                .await?
+                .iter()
+                .filter(|instance| {
+                    instance["id"]
+                        .as_str()
+                        .is_some_and(|id| unhealthy.contains(&id))
+                })
+                .count();
+            if remaining == 0 {
+                break;
+            }
+            if start_time.elapsed() >= max_wait_time {
+                anyhow::bail!("Timeout waiting for unhealthy Vultr instances to be deleted");
+            }
+            tracing::info!(
+                "Waiting for {} unhealthy instance(s) to be deleted...",
+                remaining
+            );
+            sleep(Duration::from_secs(10)).await;
+        }
+
+        Ok(unhealthy.len())
+    }
+
+    pub async fn wait_for_all_instances_to_be_deleted(
+        &self,
+        context: &VultrClusterContext,
+    ) -> Result<()> {
+        tracing::info!("Ensuring all cluster instances are deleted...");
+
+        let max_wait_time = Duration::from_secs(600);
+        let poll_interval = Duration::from_secs(10);
+        let start_time = std::time::Instant::now();
+
+        loop {
+            let remaining = self.list_cluster_instances(context).await?;
+            if remaining.is_empty() {
+                tracing::info!(
+                    "All instances for cluster '{}' are now deleted!",
+                    context.cluster_id
+                );
+                return Ok(());
+            }
+
+            if start_time.elapsed() >= max_wait_time {
+                tracing::warn!(
+                    "Timeout waiting for cluster '{}' instances to be deleted after {} seconds",
+                    context.cluster_id,
+                    max_wait_time.as_secs()
+                );
+                anyhow::bail!("Timeout waiting for Vultr instances to be deleted");
+            }
+
+            tracing::info!(
+                "Still waiting for {} instance(s) to be deleted...",
+                remaining.len()
+            );
+            sleep(poll_interval).await;
+        }
+    }
+}
+
+/// Whether a listed instance has failed: powered off or suspended by Vultr.
+pub fn is_unhealthy(instance: &JsonValue) -> bool {
+    instance["power_status"].as_str() == Some("stopped")
+        || instance["status"].as_str() == Some("suspended")
+}
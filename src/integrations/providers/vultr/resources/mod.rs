@@ -0,0 +1,4 @@
+pub mod instance;
+pub mod ssh_key;
+pub mod startup_script;
+pub mod vpc;
@@ -0,0 +1,79 @@
+use crate::integrations::providers::vultr::{VultrInterface, interface::VultrClusterContext};
+
+use anyhow::Result;
+use serde_json::json;
+use std::fs;
+
+impl VultrInterface {
+    async fn find_ssh_key_id(&self, context: &VultrClusterContext) -> Result<Option<String>> {
+        let json_response = self.make_api_request("/ssh-keys?per_page=500").await?;
+        let ssh_keys = match json_response["ssh_keys"].as_array() {
+            Some(ssh_keys) => ssh_keys,
+            None => {
+                tracing::error!("{:?}", json_response);
+                anyhow::bail!("Missing 'ssh_keys' array from Vultr API response");
+            }
+        };
+
+        let key_id = ssh_keys
+            .iter()
+            .find(|key| key["name"].as_str() == Some(context.ssh_key_name.as_str()))
+            .and_then(|key| key["id"].as_str())
+            .map(String::from);
+
+        Ok(key_id)
+    }
+
+    pub async fn ensure_ssh_key(&self, context: &VultrClusterContext) -> Result<String> {
+        if let Some(key_id) = self.find_ssh_key_id(context).await? {
+            tracing::info!("Found existing SSH Key: '{}'", key_id);
+            return Ok(key_id);
+        }
+
+        tracing::info!("No existing SSH Key found, importing a new one...");
+
+        let public_key_material = match fs::read_to_string(&context.public_ssh_key_path) {
+            Ok(material) => material,
+            Err(e) => {
+                tracing::error!("{:?}", e);
+                anyhow::bail!(
+                    "Failure reading public SSH Key file from '{}'",
+                    context.public_ssh_key_path,
+                );
+            }
+        };
+
+        let body = json!({
+            "name": context.ssh_key_name,
+            "ssh_key": public_key_material.trim(),
+        });
+        let json_response = self.make_api_post_request("/ssh-keys", &body).await?;
+
+        match json_response["ssh_key"]["id"].as_str() {
+            Some(key_id) => {
+                tracing::info!("Successfully imported SSH Key '{}'", key_id);
+                Ok(key_id.to_string())
+            }
+            None => {
+                tracing::warn!("{:?}", json_response);
+                anyhow::bail!("Failure finding the id of the created SSH Key resource");
+            }
+        }
+    }
+
+    pub async fn cleanup_ssh_key(&self, context: &VultrClusterContext) -> Result<()> {
+        let key_id = match self.find_ssh_key_id(context).await? {
+            Some(key_id) => key_id,
+            None => {
+                tracing::info!("No existing SSH Key found");
+                return Ok(());
+            }
+        };
+
+        tracing::info!("Deleting SSH Key '{}'...", key_id);
+        self.make_api_delete_request(&format!("/ssh-keys/{}", key_id))
+            .await?;
+        tracing::info!("SSH Key '{}' deleted successfully", key_id);
+        Ok(())
+    }
+}
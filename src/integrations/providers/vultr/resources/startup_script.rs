@@ -0,0 +1,108 @@
+use crate::integrations::providers::vultr::{VultrInterface, interface::VultrClusterContext};
+
+use anyhow::Result;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use serde_json::json;
+
+/// Written by the startup script when it exits, holding its exit status.
+pub const INIT_EXIT_CODE_PATH: &str = "/var/lib/hpcac-init.exit";
+/// Combined stdout/stderr of the startup script.
+pub const INIT_LOG_PATH: &str = "/var/log/hpcac-init.log";
+
+impl VultrInterface {
+    async fn list_startup_scripts(&self) -> Result<Vec<serde_json::Value>> {
+        let json_response = self
+            .make_api_request("/startup-scripts?per_page=500")
+            .await?;
+        match json_response["startup_scripts"].as_array() {
+            Some(scripts) => Ok(scripts.clone()),
+            None => {
+                tracing::error!("{:?}", json_response);
+                anyhow::bail!("Missing 'startup_scripts' array from Vultr API response");
+            }
+        }
+    }
+
+    /// Uploads `init_commands` as the boot script for one node, replacing any
+    /// script left under the same name by an earlier spawn.
+    ///
+    /// Replacing rather than reusing matters on a restore: a recovery slot can
+    /// override the init commands of the node it replaces, and a stale script
+    /// would silently prepare the replacement like the original.
+    ///
+    /// The commands run with `set -e`, like the SSH-dispatched init commands on
+    /// AWS. Vultr runs boot scripts unattended, so the script records its exit
+    /// status at INIT_EXIT_CODE_PATH for spawn to wait on and report.
+    pub async fn replace_startup_script(
+        &self,
+        context: &VultrClusterContext,
+        node_index: usize,
+        init_commands: &[String],
+    ) -> Result<String> {
+        let script_name = context.startup_script_name(node_index);
+        for script in self.list_startup_scripts().await? {
+            if script["name"].as_str() == Some(script_name.as_str())
+                && let Some(script_id) = script["id"].as_str()
+            {
+                tracing::info!("Replacing existing Startup Script '{}'", script_id);
+                self.make_api_delete_request(&format!("/startup-scripts/{}", script_id))
+                    .await?;
+            }
+        }
+
+        let script = format!(
+            "#!/bin/bash\nexec >{log} 2>&1\ntrap 'echo $? > {exit}' EXIT\nset -e\n{commands}\n",
+            log = INIT_LOG_PATH,
+            exit = INIT_EXIT_CODE_PATH,
+            commands = init_commands.join("\n")
+        );
+        let body = json!({
+            "name": script_name,
+            "type": "boot",
+            "script": BASE64.encode(script),
+        });
+        let json_response = self
+            .make_api_post_request("/startup-scripts", &body)
+            .await?;
+
+        match json_response["startup_script"]["id"].as_str() {
+            Some(script_id) => {
+                tracing::info!(
+                    "Created Startup Script '{}' ({} init command(s)) for node {}",
+                    script_id,
+                    init_commands.len(),
+                    node_index
+                );
+                Ok(script_id.to_string())
+            }
+            None => {
+                tracing::warn!("{:?}", json_response);
+                anyhow::bail!("Failure finding the id of the created Startup Script resource");
+            }
+        }
+    }
+
+    pub async fn cleanup_startup_scripts(&self, context: &VultrClusterContext) -> Result<()> {
+        let prefix = format!("{}-INIT-", context.cluster_id);
+        for script in self.list_startup_scripts().await? {
+            let is_cluster_script = script["name"]
+                .as_str()
+                .is_some_and(|name| name.starts_with(&prefix));
+            if is_cluster_script && let Some(script_id) = script["id"].as_str() {
+                tracing::info!("Deleting Startup Script '{}'...", script_id);
+                match self
+                    .make_api_delete_request(&format!("/startup-scripts/{}", script_id))
+                    .await
+                {
+                    Ok(_) => {
+                        tracing::info!("Startup Script '{}' deleted successfully", script_id);
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to delete Startup Script '{}': {}", script_id, e);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
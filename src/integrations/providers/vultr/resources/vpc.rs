@@ -0,0 +1,104 @@
+use crate::integrations::providers::vultr::{VultrInterface, interface::VultrClusterContext};
+
+use anyhow::Result;
+use serde_json::json;
+use tokio::time::{Duration, sleep};
+
+impl VultrInterface {
+    async fn find_vpc_id(&self, context: &VultrClusterContext) -> Result<Option<String>> {
+        let json_response = self.make_api_request("/vpc2?per_page=500").await?;
+        let vpcs = match json_response["vpcs"].as_array() {
+            Some(vpcs) => vpcs,
+            None => {
+                tracing::error!("{:?}", json_response);
+                anyhow::bail!("Missing 'vpcs' array from Vultr API response");
+            }
+        };
+
+        // VPC 2.0 networks carry no tags, so the description doubles as the name.
+        let vpc_id = vpcs
+            .iter()
+            .find(|vpc| {
+                vpc["description"].as_str() == Some(context.vpc_name.as_str())
+                    && vpc["region"].as_str() == Some(context.region.as_str())
+            })
+            .and_then(|vpc| vpc["id"].as_str())
+            .map(String::from);
+
+        Ok(vpc_id)
+    }
+
+    pub async fn ensure_vpc(&self, context: &VultrClusterContext) -> Result<String> {
+        if let Some(vpc_id) = self.find_vpc_id(context).await? {
+            tracing::info!("Found existing VPC 2.0 network: '{}'", vpc_id);
+            return Ok(vpc_id);
+        }
+
+        tracing::info!("No existing VPC 2.0 network found, creating a new one...");
+
+        let body = json!({
+            "region": context.region,
+            "description": context.vpc_name,
+            "ip_type": "v4",
+            "ip_block": context.vpc_ip_block,
+            "prefix_length": context.vpc_prefix_length,
+        });
+        let json_response = self.make_api_post_request("/vpc2", &body).await?;
+
+        match json_response["vpc"]["id"].as_str() {
+            Some(vpc_id) => {
+                tracing::info!("Created new VPC 2.0 network '{}'", vpc_id);
+                Ok(vpc_id.to_string())
+            }
+            None => {
+                tracing::warn!("{:?}", json_response);
+                anyhow::bail!("Failure finding the id of the created VPC 2.0 network");
+            }
+        }
+    }
+
+    pub async fn cleanup_vpc(&self, context: &VultrClusterContext) -> Result<()> {
+        let vpc_id = match self.find_vpc_id(context).await? {
+            Some(vpc_id) => vpc_id,
+            None => {
+                tracing::info!("No existing VPC 2.0 network found");
+                return Ok(());
+            }
+        };
+
+        // Vultr detaches a destroyed instance from the network a little after the
+        // instance itself disappears from the API, and refuses to delete a network
+        // that still has members. Retry until the detach catches up.
+        const MAX_ATTEMPTS: u32 = 10;
+        const RETRY_DELAY_SECS: u64 = 15;
+
+        let mut attempt = 1;
+        loop {
+            tracing::info!("Deleting VPC 2.0 network '{}'...", vpc_id);
+            match self
+                .make_api_delete_request(&format!("/vpc2/{}", vpc_id))
+                .await
+            {
+                Ok(_) => {
+                    tracing::info!("VPC 2.0 network '{}' deleted successfully", vpc_id);
+                    return Ok(());
+                }
+                Err(e) if attempt < MAX_ATTEMPTS => {
+                    tracing::warn!(
+                        "Could not delete VPC 2.0 network '{}' yet (attempt {}/{}): {}",
+                        vpc_id,
+                        attempt,
+                        MAX_ATTEMPTS,
+                        e
+                    );
+                    attempt += 1;
+                    sleep(Duration::from_secs(RETRY_DELAY_SECS)).await;
+                }
+                Err(e) => {
+                    tracing::error!("{:?}", e);
+                    anyhow::bail!("Failure deleting VPC 2.0 network '{}': {}", vpc_id, e);
+                }
+            }
+        }
+    }
+}
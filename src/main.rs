@@ -22,6 +22,8 @@ mod commands;
 mod constants;
 mod database;
 mod integrations;
+#[cfg(test)]
+mod testing;
 mod utils;
 
 #[derive(Parser)]
@@ -40,6 +42,12 @@ enum Commands {
         command: ClusterCommands,
     },
 
+    /// Database schema and maintenance commands
+    Db {
+        #[command(subcommand)]
+        command: DbCommands,
+    },
+
     /// Instance type management commands
     InstanceType {
         #[command(subcommand)]
@@ -80,6 +88,43 @@ enum ClusterCommands {
     /// List existing Clusters
     List {},
 
+    /// Show the event timeline of a Cluster
+    Events {
+        /// Cluster identifier
+        #[arg(long)]
+        cluster_id: String,
+
+        /// Only show events at or after this time (UTC, RFC 3339 or 'YYYY-MM-DD[ HH:MM[:SS]]')
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Print the events as JSON instead of a table
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+
+    /// Export the timings of every recovery `watch` performed on a Cluster
+    RecoveryReport {
+        /// Cluster identifier
+        #[arg(long)]
+        cluster_id: String,
+
+        /// Export as JSON instead of CSV
+        #[arg(long, default_value_t = false)]
+        json: bool,
+
+        /// Write the report to this file instead of stdout
+        #[arg(short = 'o', long = "output")]
+        output: Option<String>,
+    },
+
+    /// Show the task runs of a Cluster, with the logs of those that failed
+    TaskRuns {
+        /// Cluster identifier
+        #[arg(long)]
+        cluster_id: String,
+    },
+
     /// Spawn a new Cluster
     Spawn {
         /// Cluster identifier
@@ -93,6 +138,10 @@ enum ClusterCommands {
         /// Retry up to N times (60s apart) on InsufficientInstanceCapacity
         #[arg(long, default_value_t = 0)]
         retry: u32,
+
+        /// Continue an interrupted or failed spawn, skipping the steps it completed
+        #[arg(long, default_value_t = false)]
+        resume: bool,
     },
 
     /// Terminates a new Cluster
@@ -143,6 +192,25 @@ enum ClusterCommands {
         /// Path to the tasks YAML file
         #[arg(short = 'f', long = "file")]
         yaml_file_path: String,
+
+        /// Skip the runs of this tasks file that already succeeded on the cluster
+        #[arg(long, default_value_t = false)]
+        resume: bool,
+
+        /// Stream the output of each run while it goes
+        #[arg(long, default_value_t = false)]
+        follow: bool,
+    },
+
+    /// Stream the log of the task run in progress on a Cluster
+    Attach {
+        /// Cluster identifier
+        #[arg(long)]
+        cluster_id: String,
+
+        /// Task to attach to, when several are running
+        #[arg(long = "task")]
+        task_name: Option<String>,
     },
 
     /// Watch a Cluster and automatically restore on node failure
@@ -155,7 +223,8 @@ enum ClusterCommands {
         #[arg(long, default_value = "30")]
         interval: u64,
 
-        /// Path to a tasks YAML file to relaunch automatically after each restore
+        /// Path to a tasks YAML file to relaunch automatically after each restore,
+        /// skipping the runs that already succeeded
         #[arg(long)]
         tasks_yaml: Option<String>,
 
@@ -163,6 +232,50 @@ enum ClusterCommands {
         /// remaining nodes instead of provisioning a replacement
         #[arg(long, default_value_t = false)]
         no_replace: bool,
+
+        /// Proactive mode: on a spot interruption notice, launch a replacement
+        /// right away as a new node, and retire the noticed node once it dies
+        #[arg(long, default_value_t = false, conflicts_with = "no_replace")]
+        proactive: bool,
+
+        /// Run in the background, logging to the logs directory, and return once
+        /// the watcher holds the Cluster's watch lock
+        #[arg(long, default_value_t = false)]
+        detach: bool,
+
+        /// Set on the background process `--detach` starts
+        #[arg(long, hide = true, default_value_t = false, conflicts_with = "detach")]
+        daemon: bool,
+    },
+
+    /// Show what the watcher of a Cluster is doing
+    WatchStatus {
+        /// Cluster identifier
+        #[arg(long)]
+        cluster_id: String,
+    },
+
+    /// Ask the watcher of a Cluster to stop after its current pass
+    WatchStop {
+        /// Cluster identifier
+        #[arg(long)]
+        cluster_id: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum DbCommands {
+    /// Show applied and pending migrations
+    Status {},
+
+    /// Apply pending migrations
+    Migrate {},
+
+    /// Write a consistent copy of the database, safe while other commands run
+    Backup {
+        /// Destination file (default: next to the database, with a timestamp)
+        #[arg(short = 'o', long = "output")]
+        output: Option<String>,
     },
 }
 
@@ -320,6 +433,7 @@ async fn main() -> Result<()> {
     // makes a blocked writer wait its turn rather than error out.
     let connect_options = match SqliteConnectOptions::from_str(&db_url) {
         Ok(options) => options
+            .create_if_missing(true)
             .journal_mode(SqliteJournalMode::Wal)
             .busy_timeout(std::time::Duration::from_secs(30)),
         Err(e) => {
@@ -327,6 +441,7 @@ async fn main() -> Result<()> {
             anyhow::bail!("Invalid SQLite connection string: '{}'", db_url);
         }
     };
+    let database_path = connect_options.get_filename().to_path_buf();
     let sqlite_pool = match SqlitePool::connect_with(connect_options).await {
         Ok(result) => result,
         Err(e) => {
@@ -335,6 +450,16 @@ async fn main() -> Result<()> {
         }
     };
 
+    // Bring the schema up to date before any command touches it. The `db`
+    // commands are exempt so a database can be inspected or backed up before
+    // its migrations are applied.
+    if !matches!(cli.command, Commands::Db { .. }) {
+        let applied = database::migrations::migrate(&sqlite_pool).await?;
+        if !applied.is_empty() {
+            tracing::info!("Applied {} pending database migration(s)", applied.len());
+        }
+    }
+
     // Match clap commands and pass the SQLite pool to the command handlers
     tracing::debug!("Invoked command: {:?}", cli.command);
     match &cli.command {
@@ -351,8 +476,37 @@ async fn main() -> Result<()> {
             ClusterCommands::List {} => {
                 commands::cluster::list(&sqlite_pool).await?;
             }
-            ClusterCommands::Spawn { cluster_id, yes, retry } => {
-                commands::cluster::spawn(&sqlite_pool, cluster_id, *yes, *retry).await?;
+            ClusterCommands::Events {
+                cluster_id,
+                since,
+                json,
+            } => {
+                commands::cluster::events(&sqlite_pool, cluster_id, since.as_deref(), *json)
+                    .await?;
+            }
+            ClusterCommands::RecoveryReport {
+                cluster_id,
+                json,
+                output,
+            } => {
+                commands::cluster::recovery_report(
+                    &sqlite_pool,
+                    cluster_id,
+                    *json,
+                    output.as_deref(),
+                )
+                .await?;
+            }
+            ClusterCommands::TaskRuns { cluster_id } => {
+                commands::cluster::task_runs(&sqlite_pool, cluster_id).await?;
+            }
+            ClusterCommands::Spawn {
+                cluster_id,
+                yes,
+                retry,
+                resume,
+            } => {
+                commands::cluster::spawn(&sqlite_pool, cluster_id, *yes, *retry, *resume).await?;
             }
             ClusterCommands::Terminate { cluster_id, yes } => {
                 commands::cluster::terminate(&sqlite_pool, cluster_id, *yes).await?;
@@ -378,16 +532,73 @@ async fn main() -> Result<()> {
                 )
                 .await?;
             }
-            ClusterCommands::Tasks { yaml_file_path } => {
-                commands::cluster::tasks(&sqlite_pool, yaml_file_path).await?;
+            ClusterCommands::Tasks {
+                yaml_file_path,
+                resume,
+                follow,
+            } => {
+                commands::cluster::tasks(&sqlite_pool, yaml_file_path, *resume, *follow).await?;
+            }
+            ClusterCommands::Attach {
+                cluster_id,
+                task_name,
+            } => {
+                commands::cluster::attach(&sqlite_pool, cluster_id, task_name.as_deref()).await?;
             }
             ClusterCommands::Watch {
                 cluster_id,
                 interval,
                 tasks_yaml,
                 no_replace,
+                proactive,
+                detach,
+                daemon,
             } => {
-                commands::cluster::watch(&sqlite_pool, cluster_id, *interval, tasks_yaml.as_deref(), *no_replace).await?;
+                if *detach {
+                    commands::cluster::watch_detach(&sqlite_pool, cluster_id).await?;
+                } else if *daemon {
+                    commands::cluster::watch_daemon(
+                        &sqlite_pool,
+                        cluster_id,
+                        *interval,
+                        tasks_yaml.as_deref(),
+                        *no_replace,
+                        *proactive,
+                    )
+                    .await?;
+                } else {
+                    let socket_path =
+                        commands::cluster::WatchFiles::for_cluster(cluster_id).socket_path;
+                    let control =
+                        commands::cluster::WatchControl::new(cluster_id, Some(socket_path), None);
+                    commands::cluster::watch(
+                        &sqlite_pool,
+                        cluster_id,
+                        *interval,
+                        tasks_yaml.as_deref(),
+                        *no_replace,
+                        *proactive,
+                        &control,
+                    )
+                    .await?;
+                }
+            }
+            ClusterCommands::WatchStatus { cluster_id } => {
+                commands::cluster::watch_status(&sqlite_pool, cluster_id).await?;
+            }
+            ClusterCommands::WatchStop { cluster_id } => {
+                commands::cluster::watch_stop(&sqlite_pool, cluster_id).await?;
+            }
+        },
+        Commands::Db { command } => match command {
+            DbCommands::Status {} => {
+                commands::db::status(&sqlite_pool).await?;
+            }
+            DbCommands::Migrate {} => {
+                commands::db::migrate(&sqlite_pool).await?;
+            }
+            DbCommands::Backup { output } => {
+                commands::db::backup(&sqlite_pool, &database_path, output.as_deref()).await?;
             }
         },
         Commands::InstanceType { command } => match command {
@@ -0,0 +1,96 @@
+//! Fixtures shared by the in-crate tests: a throwaway SQLite database with the
+//! real migrations applied, and a mock provider wired into it.
+
+use crate::database::migrations;
+use crate::database::models::{ConfigVar, InstanceType, ProviderConfig};
+use crate::integrations::CloudInfoProvider;
+use crate::integrations::providers::mock::{MOCK_REGION, MockInterface};
+use crate::utils::{self, ProgressTracker};
+
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// A temporary directory holding the test database and any files a test
+/// writes. Removed on drop.
+pub struct TestEnv {
+    pub dir: PathBuf,
+    pub pool: SqlitePool,
+}
+
+impl TestEnv {
+    pub async fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("hpcac-test-{}", utils::generate_id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pool = Self::connect(&dir).await;
+        migrations::migrate(&pool).await.unwrap();
+        Self { dir, pool }
+    }
+
+    /// Opens another pool on the same database, as a second process would.
+    pub async fn connect(dir: &std::path::Path) -> SqlitePool {
+        let connect_options = SqliteConnectOptions::new()
+            .filename(dir.join("db.sqlite"))
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .busy_timeout(Duration::from_secs(30));
+        SqlitePool::connect_with(connect_options).await.unwrap()
+    }
+
+    pub fn write_file(&self, name: &str, content: &str) -> String {
+        let path = self.dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    /// Registers `mock` as a provider configuration, loads its instance types
+    /// and returns the configuration id for a cluster YAML.
+    pub async fn add_mock_provider(&self, mock: &MockInterface) -> i64 {
+        sqlx::query(
+            "INSERT OR IGNORE INTO providers (id, display_name, required_variables, supports_spot) \
+             VALUES ('mock', 'Mock Provider', 'MOCK_ID', 1)",
+        )
+        .execute(&self.pool)
+        .await
+        .unwrap();
+
+        let display_name = format!("mock-{}", utils::generate_id());
+        let config_vars = vec![ConfigVar {
+            id: 0,
+            provider_config_id: 0,
+            key: "MOCK_ID".to_string(),
+            value: mock.register(),
+        }];
+        ProviderConfig::insert(
+            &self.pool,
+            display_name.clone(),
+            "mock".to_string(),
+            config_vars,
+        )
+        .await
+        .unwrap();
+
+        let tracker = ProgressTracker::new(1, None);
+        let instance_types = mock
+            .fetch_instance_types(MOCK_REGION, &tracker)
+            .await
+            .unwrap();
+        InstanceType::upsert_many(&self.pool, instance_types)
+            .await
+            .unwrap();
+
+        ProviderConfig::fetch_all_by_provider(&self.pool, "mock")
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|c| c.display_name == display_name)
+            .unwrap()
+            .id
+    }
+}
+
+impl Drop for TestEnv {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
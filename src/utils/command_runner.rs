@@ -0,0 +1,32 @@
+use crate::utils::ssh::SshSession;
+
+use anyhow::Result;
+
+#[cfg(test)]
+use std::sync::Arc;
+
+/// Answers a command sent to a node that has no SSH endpoint, given the node's
+/// IP and the script. Returns what the script would have printed.
+#[cfg(test)]
+pub type CommandHandler = Arc<dyn Fn(&str, &str) -> Result<String> + Send + Sync>;
+
+/// How commands reach a cluster node. Real providers go over SSH; the mock
+/// provider answers them in-process, so nothing leaves the machine.
+pub enum CommandRunner {
+    Ssh(SshSession),
+    #[cfg(test)]
+    Scripted {
+        ip: String,
+        handler: CommandHandler,
+    },
+}
+
+impl CommandRunner {
+    pub async fn run_command(&self, script: &str) -> Result<String> {
+        match self {
+            CommandRunner::Ssh(ssh) => ssh.run_command(script).await,
+            #[cfg(test)]
+            CommandRunner::Scripted { ip, handler } => handler(ip, script),
+        }
+    }
+}
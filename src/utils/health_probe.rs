@@ -0,0 +1,205 @@
+use crate::utils::command_runner::CommandRunner;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::time::{Duration, timeout};
+
+/// How long a probe may take before it counts as failed. A hung sshd or a
+/// stale NFS mount does not fail, it blocks.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What a health probe checks on a node.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HealthCheck {
+    /// The node runs a command over SSH.
+    Ssh,
+    /// `path` is a mount point that answers, e.g. the shared filesystem.
+    Mountpoint { path: String },
+    /// The filesystem holding `path` has at least `min_free_gb` GB free.
+    FreeDisk { path: String, min_free_gb: f64 },
+    /// `command` exits with one of `expected_exit_codes`, [0] when omitted.
+    Command {
+        command: String,
+        #[serde(default)]
+        expected_exit_codes: Vec<i32>,
+    },
+}
+
+/// A check `cluster watch` runs on every node besides looking at the instance
+/// state, declared under `health_probes:` in the cluster YAML. A node failing
+/// it `failure_threshold` times in a row is restored like a terminated one.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct HealthProbe {
+    #[serde(flatten)]
+    pub check: HealthCheck,
+    /// Consecutive failures after which the node is restored. 3 when omitted.
+    pub failure_threshold: Option<u32>,
+}
+
+impl HealthProbe {
+    pub fn failure_threshold(&self) -> u32 {
+        self.failure_threshold.unwrap_or(3)
+    }
+
+    /// Rejects values that would not survive being put in a shell command.
+    pub fn validate(&self) -> Result<()> {
+        if self.failure_threshold == Some(0) {
+            anyhow::bail!("A health probe's failure_threshold must be positive");
+        }
+        match &self.check {
+            HealthCheck::Mountpoint { path } | HealthCheck::FreeDisk { path, .. }
+                if path.is_empty() || path.contains('\'') =>
+            {
+                anyhow::bail!("Invalid health probe path '{}'", path)
+            }
+            HealthCheck::FreeDisk { min_free_gb, .. } if *min_free_gb < 0.0 => {
+                anyhow::bail!("A health probe's min_free_gb cannot be negative")
+            }
+            HealthCheck::Command { command, .. } if command.trim().is_empty() => {
+                anyhow::bail!("A command health probe needs a command")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Short description for logs and events, e.g. "mountpoint /shared".
+    pub fn describe(&self) -> String {
+        match &self.check {
+            HealthCheck::Ssh => "ssh".to_string(),
+            HealthCheck::Mountpoint { path } => format!("mountpoint {}", path),
+            HealthCheck::FreeDisk { path, min_free_gb } => {
+                format!("free_disk {} >= {} GB", path, min_free_gb)
+            }
+            HealthCheck::Command { command, .. } => format!("command '{}'", command),
+        }
+    }
+
+    /// Script printing the probe's verdict, or what to compare it against.
+    fn script(&self) -> String {
+        match &self.check {
+            HealthCheck::Ssh => "echo reachable".to_string(),
+            HealthCheck::Mountpoint { path } => format!(
+                "timeout 10 mountpoint -q '{}' && timeout 10 stat -f '{}' >/dev/null \
+                && echo mounted || echo missing",
+                path, path
+            ),
+            HealthCheck::FreeDisk { path, .. } => {
+                format!("df -Pk '{}' | awk 'NR == 2 {{ print $4 }}'", path)
+            }
+            // On its own lines, so a trailing comment cannot swallow the echo.
+            HealthCheck::Command { command, .. } => {
+                format!("(\n{}\n) >/dev/null 2>&1\necho \"exit=$?\"", command)
+            }
+        }
+    }
+
+    /// Runs the probe on the node behind `runner`. The error is why it failed.
+    pub async fn run(&self, runner: &CommandRunner) -> Result<(), String> {
+        let output = match timeout(PROBE_TIMEOUT, runner.run_command(&self.script())).await {
+            Ok(Ok(output)) => output,
+            Ok(Err(e)) => return Err(format!("{} could not run: {}", self.describe(), e)),
+            Err(_) => {
+                return Err(format!(
+                    "{} did not answer within {}s",
+                    self.describe(),
+                    PROBE_TIMEOUT.as_secs()
+                ));
+            }
+        };
+        let output = output.trim();
+        match &self.check {
+            HealthCheck::Ssh => Ok(()),
+            HealthCheck::Mountpoint { path } => match output {
+                "mounted" => Ok(()),
+                _ => Err(format!("'{}' is not mounted or not responding", path)),
+            },
+            HealthCheck::FreeDisk { path, min_free_gb } => {
+                let Ok(free_kb) = output.parse::<f64>() else {
+                    return Err(format!("could not read the free space of '{}'", path));
+                };
+                let free_gb = free_kb / (1024.0 * 1024.0);
+                if free_gb < *min_free_gb {
+                    return Err(format!(
+                        "'{}' has {:.1} GB free, below {} GB",
+                        path, free_gb, min_free_gb
+                    ));
+                }
+                Ok(())
+            }
+            HealthCheck::Command {
+                command,
+                expected_exit_codes,
+            } => {
+                let exit_code = output
+                    .lines()
+                    .last()
+                    .and_then(|line| line.strip_prefix("exit="))
+                    .and_then(|code| code.parse::<i32>().ok());
+                let Some(exit_code) = exit_code else {
+                    return Err(format!("could not read the exit code of '{}'", command));
+                };
+                let expected = if expected_exit_codes.is_empty() {
+                    &[0][..]
+                } else {
+                    &expected_exit_codes[..]
+                };
+                if !expected.contains(&exit_code) {
+                    return Err(format!(
+                        "'{}' exited with {}, expected {:?}",
+                        command, exit_code, expected
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn health_probes_parse_from_the_cluster_yaml() {
+        let probes: Vec<HealthProbe> = serde_yaml::from_str(
+            "- kind: ssh
+- kind: mountpoint
+  path: /shared
+  failure_threshold: 2
+- kind: free_disk
+  path: /
+  min_free_gb: 5
+- kind: command
+  command: pgrep -x starfwi-fwi
+  expected_exit_codes: [0, 1]
+",
+        )
+        .unwrap();
+        assert_eq!(probes[0].check, HealthCheck::Ssh);
+        assert_eq!(probes[0].failure_threshold(), 3);
+        assert_eq!(probes[1].failure_threshold(), 2);
+        assert_eq!(probes[2].describe(), "free_disk / >= 5 GB");
+        assert_eq!(
+            probes[3].check,
+            HealthCheck::Command {
+                command: "pgrep -x starfwi-fwi".to_string(),
+                expected_exit_codes: vec![0, 1],
+            }
+        );
+
+        // Stored as JSON on the cluster row.
+        let json = serde_json::to_string(&probes).unwrap();
+        let stored: Vec<HealthProbe> = serde_json::from_str(&json).unwrap();
+        assert_eq!(stored, probes);
+
+        let quoted = HealthProbe {
+            check: HealthCheck::Mountpoint {
+                path: "/shared'; reboot".to_string(),
+            },
+            failure_threshold: None,
+        };
+        assert!(quoted.validate().is_err());
+        assert!(probes.iter().all(|p| p.validate().is_ok()));
+    }
+}
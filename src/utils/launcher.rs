@@ -0,0 +1,270 @@
+use crate::utils::slurm;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::time::Duration;
+
+/// The program a task's script starts its parallel job with. It decides the
+/// format of the task's hostfile, the `HPCAC_MPIRUN` prefix and how a signal
+/// reaches the job's ranks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Launcher {
+    #[default]
+    Openmpi,
+    Mpich,
+    IntelMpi,
+    Srun,
+    /// The script is submitted as a Slurm batch job, on a cluster spawned with
+    /// `scheduler: slurm`.
+    Sbatch,
+    /// The script starts its processes itself.
+    None,
+}
+
+/// Where a launcher's signal command must run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalScope {
+    /// On the head node, where the launcher runs.
+    Head,
+    /// On every node the job runs on.
+    JobNodes,
+}
+
+impl std::fmt::Display for Launcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let launcher_str = match self {
+            Launcher::Openmpi => "openmpi",
+            Launcher::Mpich => "mpich",
+            Launcher::IntelMpi => "intel-mpi",
+            Launcher::Srun => "srun",
+            Launcher::Sbatch => "sbatch",
+            Launcher::None => "none",
+        };
+        write!(f, "{}", launcher_str)
+    }
+}
+
+impl std::str::FromStr for Launcher {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "openmpi" => Ok(Launcher::Openmpi),
+            "mpich" => Ok(Launcher::Mpich),
+            "intel-mpi" => Ok(Launcher::IntelMpi),
+            "srun" => Ok(Launcher::Srun),
+            "sbatch" => Ok(Launcher::Sbatch),
+            "none" => Ok(Launcher::None),
+            _ => Err(format!("Invalid launcher: {}", s)),
+        }
+    }
+}
+
+impl Launcher {
+    /// Hostfile listing `hosts` with `slots_per_host` slots each, in the syntax
+    /// the launcher reads.
+    pub fn hostfile(&self, hosts: &[String], slots_per_host: u32) -> String {
+        hosts
+            .iter()
+            .map(|host| match self {
+                Launcher::Openmpi => format!("{} slots={}\n", host, slots_per_host),
+                Launcher::Mpich | Launcher::IntelMpi => format!("{}:{}\n", host, slots_per_host),
+                Launcher::Sbatch => format!("{}\n", slurm::node_name(host)),
+                Launcher::Srun | Launcher::None => format!("{}\n", host),
+            })
+            .collect()
+    }
+
+    /// Command prefix starting `np` ranks on the hosts of `hostfile_path`. None
+    /// for a script that starts its processes itself.
+    pub fn mpirun_prefix(&self, hostfile_path: &str, np: u32) -> Option<String> {
+        match self {
+            Launcher::Openmpi => Some(format!("mpirun -np {} --hostfile {}", np, hostfile_path)),
+            Launcher::Mpich => Some(format!("mpiexec -n {} -f {}", np, hostfile_path)),
+            Launcher::IntelMpi => Some(format!("mpirun -n {} -machinefile {}", np, hostfile_path)),
+            // srun reads a node list from a file when the argument has a '/'.
+            Launcher::Srun => Some(format!("srun -n {} -w {}", np, hostfile_path)),
+            // Inside the batch job srun places the ranks on the job's nodes.
+            Launcher::Sbatch => Some(format!("srun -n {}", np)),
+            Launcher::None => None,
+        }
+    }
+
+    /// Command delivering `signal` (e.g. "USR1") to the job's ranks, and where
+    /// to run it. The command exits non-zero when it matched no process. None
+    /// when the launcher leaves no way to tell the job's processes apart.
+    ///
+    /// Open MPI's mpirun (prterun since 5.0) and srun forward SIGUSR1 and SIGUSR2
+    /// to every rank, so they are signalled on the head node. Hydra, the process
+    /// manager of MPICH and Intel MPI, treats SIGUSR1 as a request to checkpoint
+    /// through its own (rarely configured) mechanism rather than forwarding it, so
+    /// its ranks are signalled directly: they are the children of the
+    /// hydra_pmi_proxy on each node.
+    pub fn signal_command(&self, signal: &str) -> Option<(SignalScope, String)> {
+        match self {
+            Launcher::Openmpi => Some((
+                SignalScope::Head,
+                format!("pkill -{} -x 'mpirun|prterun'", signal),
+            )),
+            Launcher::Srun => Some((SignalScope::Head, format!("pkill -{} -x srun", signal))),
+            // --full reaches the batch script as well as its steps.
+            Launcher::Sbatch => Some((
+                SignalScope::Head,
+                format!(
+                    "squeue -h -t R -u \"$(id -un)\" | grep -q . && scancel --signal={} --full --state=RUNNING --user=\"$(id -un)\"",
+                    signal
+                ),
+            )),
+            Launcher::Mpich | Launcher::IntelMpi => Some((
+                SignalScope::JobNodes,
+                format!(
+                    "proxies=$(pgrep -d, -x hydra_pmi_proxy) && pkill -{} -P \"$proxies\"",
+                    signal
+                ),
+            )),
+            Launcher::None => None,
+        }
+    }
+}
+
+/// How an application is asked to checkpoint, declared as `checkpoint_signal:`
+/// in a cluster or tasks YAML. Without it the job is signalled through its
+/// launcher.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CheckpointSignal {
+    /// Pattern matched against whole process names, e.g. 'starfwi-fwi'. Those
+    /// processes are signalled on each of the job's nodes.
+    pub process: Option<String>,
+    /// Signal name without the SIG prefix. USR1 when omitted.
+    pub signal: Option<String>,
+    /// Command run on each of the job's nodes instead of sending a signal.
+    pub command: Option<String>,
+    /// File the application creates on a node once its checkpoint is flushed,
+    /// taken literally: the shell does not expand it.
+    pub ack_file: Option<String>,
+    /// How long to wait for `ack_file`. 60 when omitted.
+    pub ack_timeout_secs: Option<u64>,
+}
+
+impl CheckpointSignal {
+    pub fn signal(&self) -> &str {
+        self.signal.as_deref().unwrap_or("USR1")
+    }
+
+    pub fn ack_timeout(&self) -> Duration {
+        Duration::from_secs(self.ack_timeout_secs.unwrap_or(60))
+    }
+
+    /// Rejects values that would not survive being put in a shell command.
+    pub fn validate(&self) -> Result<()> {
+        let signal = self.signal();
+        if signal.is_empty() || !signal.chars().all(|c| c.is_ascii_alphanumeric()) {
+            anyhow::bail!(
+                "Invalid checkpoint signal '{}', expected a name like USR1 or a number",
+                signal
+            );
+        }
+        if let Some(process) = &self.process
+            && (process.is_empty() || process.contains('\''))
+        {
+            anyhow::bail!("Invalid checkpoint process pattern '{}'", process);
+        }
+        if let Some(ack_file) = &self.ack_file
+            && (ack_file.is_empty() || ack_file.contains('\''))
+        {
+            anyhow::bail!("Invalid checkpoint ack_file '{}'", ack_file);
+        }
+        if self.process.is_some() && self.command.is_some() {
+            anyhow::bail!("A checkpoint_signal takes either a process or a command, not both");
+        }
+        if self.ack_timeout_secs == Some(0) {
+            anyhow::bail!("A checkpoint_signal's ack_timeout_secs must be positive");
+        }
+        Ok(())
+    }
+
+    /// The command asking a job started with `launcher` to checkpoint, and where
+    /// to run it. Only without a process or command does the launcher decide.
+    pub fn signal_command(&self, launcher: Launcher) -> Option<(SignalScope, String)> {
+        if let Some(command) = &self.command {
+            return Some((SignalScope::JobNodes, command.clone()));
+        }
+        if let Some(process) = &self.process {
+            return Some((
+                SignalScope::JobNodes,
+                format!("pkill -{} -x '{}'", self.signal(), process),
+            ));
+        }
+        launcher.signal_command(self.signal())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn launchers_write_their_own_hostfile_syntax() {
+        let hosts = ["10.0.0.10".to_string(), "10.0.0.11".to_string()];
+        assert_eq!(
+            Launcher::Openmpi.hostfile(&hosts, 4),
+            "10.0.0.10 slots=4\n10.0.0.11 slots=4\n"
+        );
+        assert_eq!(
+            Launcher::Mpich.hostfile(&hosts, 4),
+            "10.0.0.10:4\n10.0.0.11:4\n"
+        );
+        assert_eq!(Launcher::Srun.hostfile(&hosts, 4), "10.0.0.10\n10.0.0.11\n");
+        assert_eq!(
+            Launcher::IntelMpi.mpirun_prefix("/tmp/hosts", 8).as_deref(),
+            Some("mpirun -n 8 -machinefile /tmp/hosts")
+        );
+        assert!(Launcher::None.mpirun_prefix("/tmp/hosts", 8).is_none());
+        assert_eq!(
+            Launcher::Sbatch.hostfile(&hosts, 4),
+            "hpcac-10-0-0-10\nhpcac-10-0-0-11\n"
+        );
+        assert_eq!("intel-mpi".parse::<Launcher>(), Ok(Launcher::IntelMpi));
+        assert_eq!(Launcher::IntelMpi.to_string(), "intel-mpi");
+    }
+
+    #[test]
+    fn checkpoint_signals_override_the_launcher() {
+        let by_process = CheckpointSignal {
+            process: Some("starfwi-fwi".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            by_process.signal_command(Launcher::Openmpi),
+            Some((
+                SignalScope::JobNodes,
+                "pkill -USR1 -x 'starfwi-fwi'".to_string()
+            ))
+        );
+
+        let by_signal = CheckpointSignal {
+            signal: Some("USR2".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            by_signal.signal_command(Launcher::Srun),
+            Some((SignalScope::Head, "pkill -USR2 -x srun".to_string()))
+        );
+        assert!(by_signal.signal_command(Launcher::None).is_none());
+
+        let injected = CheckpointSignal {
+            signal: Some("USR1; rm -rf /".to_string()),
+            ..Default::default()
+        };
+        assert!(injected.validate().is_err());
+        assert!(by_process.validate().is_ok());
+
+        let quote_in_ack_file = CheckpointSignal {
+            ack_file: Some("/tmp/ckpt'; rm -rf /; '".to_string()),
+            ..Default::default()
+        };
+        assert!(quote_in_ack_file.validate().is_err());
+    }
+}
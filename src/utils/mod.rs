@@ -1,8 +1,12 @@
+pub mod command_runner;
 mod formatting;
+pub mod health_probe;
+pub mod launcher;
 pub mod os;
 pub mod progress_bars;
 pub mod prompts;
 pub mod random;
+pub mod slurm;
 pub mod ssh;
 
 pub use formatting::*;
@@ -8,3 +8,18 @@ pub fn expand_tilde(path: &str) -> String {
     }
     path.to_string()
 }
+
+/// Directory for log files and the files of detached watchers, `LOGS_DIRECTORY`
+/// or ./logs.
+pub fn logs_directory() -> String {
+    env::var("LOGS_DIRECTORY").unwrap_or_else(|_| "./logs".to_string())
+}
+
+/// Whether a process with id `pid` runs on this machine.
+pub fn process_is_alive(pid: i64) -> bool {
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
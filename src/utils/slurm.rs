@@ -0,0 +1,209 @@
+use crate::utils::command_runner::CommandRunner;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+const SLURM_CONF_PATH: &str = "/etc/slurm/slurm.conf";
+const MUNGE_KEY_PATH: &str = "/etc/munge/munge.key";
+
+/// Workload manager set up on a cluster during spawn, `scheduler:` in the
+/// cluster YAML.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scheduler {
+    Slurm,
+}
+
+impl std::fmt::Display for Scheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Scheduler::Slurm => write!(f, "slurm"),
+        }
+    }
+}
+
+impl std::str::FromStr for Scheduler {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "slurm" => Ok(Scheduler::Slurm),
+            _ => Err(format!("Invalid scheduler: {}", s)),
+        }
+    }
+}
+
+/// Slurm name of the node with private IP `private_ip`. Names come from the IP
+/// rather than the slot so they survive a restore, where a replacement keeps
+/// its node's address but scale-ups shift the slots.
+pub fn node_name(private_ip: &str) -> String {
+    format!("hpcac-{}", private_ip.replace('.', "-"))
+}
+
+/// Script submitting `job_script` as batch job `job_name` on the nodes with
+/// private IPs `hosts`, `tasks_per_node` tasks each, and waiting for it. The
+/// environment it was started with reaches the job. Slurm writes the output on
+/// the job's first node, so it is printed once the job ended, and the script
+/// exits with the job's exit code. Killing the script cancels the job.
+pub fn sbatch_submission(
+    job_name: &str,
+    hosts: &[String],
+    tasks_per_node: u32,
+    job_script: &str,
+) -> String {
+    let node_list: Vec<String> = hosts.iter().map(|host| node_name(host)).collect();
+    format!(
+        "trap 'scancel --name={job_name}; exit 143' HUP TERM INT\n\
+        job_id=$(sbatch --parsable --wait --job-name={job_name} --nodes={nodes} \
+        --nodelist={node_list} --ntasks-per-node={tasks_per_node} \
+        --output=/tmp/hpcac_slurm_%j.out <<'HPCAC_JOB'\n\
+        #!/bin/bash\n{job_script}\nHPCAC_JOB\n)\n\
+        status=$?\n\
+        job_id=${{job_id%%;*}}\n\
+        batch_host=$(scontrol show job \"$job_id\" 2>/dev/null | sed -n 's/.*BatchHost=hpcac-\\([^ ]*\\).*/\\1/p' | tr - .)\n\
+        ssh -o StrictHostKeyChecking=no \"${{batch_host:-localhost}}\" cat \"/tmp/hpcac_slurm_$job_id.out\"\n\
+        exit $status\n",
+        nodes = hosts.len(),
+        node_list = node_list.join(","),
+    )
+}
+
+/// slurm.conf for `nodes`, (private IP, CPU count) pairs. The head, private IP
+/// `head_ip`, runs the controller.
+fn slurm_conf(cluster_id: &str, head_ip: &str, nodes: &[(String, u32)]) -> String {
+    let mut conf = format!(
+        "# Written by hpcac, rewritten on every spawn and restore.\n\
+         ClusterName={}\n\
+         SlurmctldHost={}({})\n\
+         AuthType=auth/munge\n\
+         SlurmUser=slurm\n\
+         StateSaveLocation=/var/spool/slurmctld\n\
+         SlurmdSpoolDir=/var/spool/slurmd\n\
+         ProctrackType=proctrack/linuxproc\n\
+         TaskPlugin=task/none\n\
+         MpiDefault=none\n\
+         SchedulerType=sched/backfill\n\
+         SelectType=select/cons_tres\n\
+         SelectTypeParameters=CR_Core\n\
+         ReturnToService=2\n",
+        cluster_id.to_lowercase(),
+        node_name(head_ip),
+        head_ip
+    );
+    for (ip, cpus) in nodes {
+        conf.push_str(&format!(
+            "NodeName={} NodeAddr={} CPUs={} State=UNKNOWN\n",
+            node_name(ip),
+            ip,
+            cpus
+        ));
+    }
+    conf.push_str("PartitionName=hpcac Nodes=ALL Default=YES MaxTime=INFINITE State=UP\n");
+    conf
+}
+
+/// Installs Slurm and munge where missing and (re)writes their configuration
+/// on every node: slurmctld on the head node, slurmd on all of them, the head
+/// included since tasks run there too, and one munge key generated on the head.
+/// `nodes` are the cluster's Node rows as (private IP, runner), among them the
+/// head with private IP `head_ip`.
+///
+/// Run again after a restore, it rewrites the node list and restarts the
+/// daemons, so replacement nodes join and nodes a scale-up added appear.
+/// ReturnToService=2 brings back a node slurmctld had marked down when its
+/// instance was reclaimed.
+pub async fn configure_slurm(
+    cluster_id: &str,
+    head_ip: &str,
+    nodes: &[(String, CommandRunner)],
+) -> Result<()> {
+    let Some((_, head_runner)) = nodes.iter().find(|(private_ip, _)| private_ip == head_ip) else {
+        anyhow::bail!(
+            "Head node '{}' of Cluster '{}' is not among the nodes to run Slurm on",
+            head_ip,
+            cluster_id
+        );
+    };
+    let mut conf_nodes: Vec<(String, u32)> = Vec::new();
+    for (private_ip, runner) in nodes {
+        runner
+            .run_command(
+                "command -v slurmd &>/dev/null || { command -v apt-get &>/dev/null \
+                && sudo DEBIAN_FRONTEND=noninteractive apt-get install -y slurm-wlm munge \
+                || sudo dnf install -y slurm slurm-slurmctld slurm-slurmd munge; }",
+            )
+            .await?;
+        let cpus = match runner.run_command("nproc").await?.trim().parse::<u32>() {
+            Ok(cpus) => cpus,
+            Err(_) => anyhow::bail!("Could not read the CPU count of node '{}'", private_ip),
+        };
+        conf_nodes.push((private_ip.clone(), cpus));
+    }
+
+    // The key is kept across runs so restarting the daemons is enough for the
+    // nodes that already had it.
+    let munge_key = head_runner
+        .run_command(&format!(
+            "sudo test -s {key} || sudo dd if=/dev/urandom of={key} bs=1 count=1024 status=none; \
+            sudo base64 -w0 {key}",
+            key = MUNGE_KEY_PATH
+        ))
+        .await?;
+    let munge_key = munge_key.trim();
+    let conf = slurm_conf(cluster_id, head_ip, &conf_nodes);
+
+    for (private_ip, runner) in nodes {
+        // slurmd is told its NodeName, the instance's own hostname being unknown
+        // to slurm.conf.
+        let setup = format!(
+            "set -e\n\
+            sudo mkdir -p /etc/slurm /var/spool/slurmd /var/spool/slurmctld /etc/sysconfig /etc/default\n\
+            sudo chown slurm: /var/spool/slurmctld 2>/dev/null || true\n\
+            echo '{munge_key}' | base64 -d | sudo tee {key} >/dev/null\n\
+            sudo chown munge: {key} && sudo chmod 400 {key}\n\
+            sudo tee {conf_path} >/dev/null <<'HPCAC_SLURM_CONF'\n{conf}HPCAC_SLURM_CONF\n\
+            echo 'SLURMD_OPTIONS=\"-N {name}\"' | sudo tee /etc/sysconfig/slurmd /etc/default/slurmd >/dev/null\n\
+            sudo systemctl enable munge && sudo systemctl restart munge\n",
+            key = MUNGE_KEY_PATH,
+            conf_path = SLURM_CONF_PATH,
+            name = node_name(private_ip),
+        );
+        runner.run_command(&setup).await?;
+        if private_ip == head_ip {
+            runner
+                .run_command("sudo systemctl enable slurmctld && sudo systemctl restart slurmctld")
+                .await?;
+        }
+    }
+    for (_, runner) in nodes {
+        runner
+            .run_command("sudo systemctl enable slurmd && sudo systemctl restart slurmd")
+            .await?;
+    }
+    tracing::info!(
+        "Slurm configured on Cluster '{}' with {} node(s)",
+        cluster_id,
+        conf_nodes.len()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slurm_conf_lists_every_node_by_address() {
+        let conf = slurm_conf(
+            "MyCluster",
+            "10.0.0.10",
+            &[("10.0.0.10".to_string(), 4), ("10.0.0.11".to_string(), 8)],
+        );
+        assert!(conf.contains("ClusterName=mycluster\n"));
+        assert!(conf.contains("SlurmctldHost=hpcac-10-0-0-10(10.0.0.10)\n"));
+        assert!(
+            conf.contains("NodeName=hpcac-10-0-0-11 NodeAddr=10.0.0.11 CPUs=8 State=UNKNOWN\n")
+        );
+        assert_eq!("slurm".parse::<Scheduler>(), Ok(Scheduler::Slurm));
+    }
+}
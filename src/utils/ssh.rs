@@ -40,7 +40,6 @@ impl SshSession {
         }
     }
 
-    #[allow(dead_code)]
     pub fn for_vultr(ip: &str, private_key_path: &str) -> Self {
         Self {
             ip: ip.to_string(),
@@ -50,6 +49,25 @@ impl SshSession {
         }
     }
 
+    /// Session to a cluster node, with the login user and route of its provider.
+    /// AWS workers have no public IP and are reached through the head node;
+    /// every Vultr instance has one and is reached directly.
+    pub fn for_cluster_node(
+        provider_id: &str,
+        private_ip: &str,
+        public_ip: Option<&str>,
+        head_public_ip: &str,
+        private_key_path: &str,
+    ) -> Self {
+        let public_ip = public_ip.filter(|ip| !ip.is_empty());
+        match (provider_id, public_ip) {
+            ("vultr", Some(ip)) => Self::for_vultr(ip, private_key_path),
+            ("vultr", None) => Self::for_vultr(private_ip, private_key_path),
+            (_, Some(ip)) => Self::for_aws(ip, private_key_path),
+            (_, None) => Self::for_aws_worker(private_ip, head_public_ip, private_key_path),
+        }
+    }
+
     fn base_args(&self) -> Vec<String> {
         let mut args = vec![
             "-i".to_string(),
@@ -354,27 +372,80 @@ impl SshSession {
         }
     }
 
-    /// Download the *contents* of a remote directory into a local directory using rsync.
-    /// The remote directory itself is not created locally — only its contents are merged in.
-    pub async fn download_dir(&self, remote_path: &str, local_path: &str) -> Result<()> {
-        // Ensure trailing slash on remote so rsync copies contents, not the directory itself.
-        let remote_src = format!(
-            "{}@{}:{}/",
-            self.username,
-            self.ip,
-            remote_path.trim_end_matches('/')
-        );
-        tracing::info!("Downloading '{}' -> '{}'", remote_src, local_path);
+    /// Stream a remote file line by line as it grows, like `tail -F`, prefixing
+    /// each line with `session_name`. Lines go through tracing, so they are
+    /// printed above any active progress bars. Runs until the tmux session
+    /// `session_name` ends or the connection drops. Dropping the future only
+    /// ends the local `ssh`: without a tty the remote `tail` gets no hangup, so
+    /// it is tied to the session instead.
+    pub async fn follow_file(&self, remote_path: &str, session_name: &str) -> Result<()> {
+        use tokio::io::{AsyncBufReadExt, BufReader};
+
+        let mut args = self.base_args();
+        // The file may not exist yet: -F waits for it to appear. The tail is
+        // stopped a second after the session ends, once it has read the last
+        // lines, or at once if the session is already gone.
+        args.push(format!(
+            "tail -n +1 -F '{}' 2>/dev/null & \
+            while tmux has-session -t '{}' 2>/dev/null; do sleep 1; done; \
+            sleep 1; kill $! 2>/dev/null; true",
+            remote_path, session_name
+        ));
 
-        let ssh_opts = format!(
+        let mut child = Command::new("ssh")
+            .args(&args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+        while let Some(line) = lines.next_line().await? {
+            tracing::info!("[{}] {}", session_name, line);
+        }
+
+        let output = child.wait_with_output().await?;
+        if output.status.success() {
+            return Ok(());
+        }
+        anyhow::bail!(
+            "Stopped following '{}:{}': {}",
+            self.display(),
+            remote_path,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    }
+
+    /// The `ssh` invocation rsync connects with, through the jump host if any.
+    /// rsync splits it on spaces but honours quotes.
+    fn rsync_shell(&self) -> String {
+        let mut shell = format!(
             "ssh -i {} -o StrictHostKeyChecking=no -o UserKnownHostsFile=/dev/null -o LogLevel=ERROR",
             self.private_key_path
         );
+        if let Some(ref jump) = self.jump_host {
+            shell.push_str(&format!(
+                " -o 'ProxyCommand=ssh -i {} -o StrictHostKeyChecking=no -o UserKnownHostsFile=/dev/null -o LogLevel=ERROR -W %h:%p {}'",
+                self.private_key_path, jump
+            ));
+        }
+        shell
+    }
+
+    /// Download a remote path into a local directory using rsync. With a trailing
+    /// slash the remote directory's contents are merged in, without one the
+    /// directory itself is copied.
+    pub async fn download_path(&self, remote_path: &str, local_path: &str) -> Result<()> {
+        let remote_src = format!("{}@{}:{}", self.username, self.ip, remote_path);
+        tracing::info!("Downloading '{}' -> '{}'", remote_src, local_path);
+
         let output = Command::new("rsync")
             .args([
                 "-az",
                 "--no-relative",
-                "-e", &ssh_opts,
+                "-e",
+                &self.rsync_shell(),
                 &remote_src,
                 local_path,
             ])
@@ -393,12 +464,53 @@ impl SshSession {
         Ok(())
     }
 
-    pub async fn upload_file_binary(&self, local_path: &str, remote_path: &str) -> Result<()> {
-        tracing::info!(
-            "Uploading '{}' -> '{}:{}'",
+    /// Download the *contents* of a remote directory into a local directory using rsync.
+    /// The remote directory itself is not created locally — only its contents are merged in.
+    pub async fn download_dir(&self, remote_path: &str, local_path: &str) -> Result<()> {
+        // Ensure trailing slash on remote so rsync copies contents, not the directory itself.
+        self.download_path(
+            &format!("{}/", remote_path.trim_end_matches('/')),
             local_path,
+        )
+        .await
+    }
+
+    /// Upload local files or directories to a remote path using rsync. Only what
+    /// changed is sent, and files are compared by checksum rather than by size
+    /// and modification time, so a re-upload never leaves a stale copy behind.
+    /// Same rules as rsync for `remote_path`: a directory source with a trailing
+    /// slash has its contents merged in, and several sources need a directory.
+    pub async fn upload_paths(&self, local_paths: &[String], remote_path: &str) -> Result<()> {
+        let remote_dest = format!("{}@{}:{}", self.username, self.ip, remote_path);
+        tracing::info!("Uploading {} -> '{}'", local_paths.join(" "), remote_dest);
+
+        let shell = self.rsync_shell();
+        let mut args = vec!["-az", "--checksum", "-e", shell.as_str()];
+        args.extend(local_paths.iter().map(|p| p.as_str()));
+        args.push(&remote_dest);
+        let output = Command::new("rsync")
+            .args(&args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "rsync failed uploading to '{}': {}",
+                remote_dest,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        Ok(())
+    }
+
+    pub async fn download_file_binary(&self, remote_path: &str, local_path: &str) -> Result<()> {
+        tracing::info!(
+            "Downloading '{}:{}' -> '{}'",
             self.display(),
-            remote_path
+            remote_path,
+            local_path
         );
         let output = Command::new("scp")
             .args([
@@ -410,8 +522,8 @@ impl SshSession {
                 "UserKnownHostsFile=/dev/null",
                 "-o",
                 "LogLevel=ERROR",
-                local_path,
                 &format!("{}@{}:{}", self.username, self.ip, remote_path),
+                local_path,
             ])
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
@@ -420,10 +532,10 @@ impl SshSession {
 
         if !output.status.success() {
             anyhow::bail!(
-                "scp failed uploading '{}' to '{}:{}': {}",
-                local_path,
+                "scp failed downloading '{}:{}' to '{}': {}",
                 self.display(),
                 remote_path,
+                local_path,
                 String::from_utf8_lossy(&output.stderr)
             );
         }